@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pfr::palette::{expand, expand_naive, rgba_lut};
+
+fn test_palette() -> Vec<(u8, u8, u8)> {
+    let mut seed = 0x1234_5678u32;
+    (0..256)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let [red, green, blue, _] = seed.to_le_bytes();
+            (red, green, blue)
+        })
+        .collect()
+}
+
+fn palette(c: &mut Criterion) {
+    let pal = test_palette();
+    for (width, height) in [(320, 609), (640, 480)] {
+        let src: Vec<u8> = (0..width * height).map(|i| (i * 7 % 251) as u8).collect();
+        let mut dst = vec![0; src.len() * 4];
+        c.bench_function(&format!("{width}x{height} naive"), |b| {
+            b.iter(|| {
+                for (src, dst) in src.chunks_exact(width).zip(dst.chunks_exact_mut(width * 4)) {
+                    expand_naive(black_box(&pal), black_box(src), dst);
+                }
+            })
+        });
+        c.bench_function(&format!("{width}x{height} lut"), |b| {
+            b.iter(|| {
+                let lut = rgba_lut(black_box(&pal));
+                for (src, dst) in src.chunks_exact(width).zip(dst.chunks_exact_mut(width * 4)) {
+                    expand(&lut, black_box(src), dst);
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(benches, palette);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Bencher, Criterion};
+use pfr::{
+    config::{Config, TableId},
+    data::{open_data_source, DataSource},
+    table::Table,
+};
+use winit::event::{ElementState, VirtualKeyCode};
+
+fn data_source() -> Option<Box<dyn DataSource>> {
+    let Some(dir) = std::env::var_os("PFR_DATA") else {
+        eprintln!("PFR_DATA is not set, skipping");
+        return None;
+    };
+    Some(open_data_source(Path::new(&dir)).unwrap())
+}
+
+fn press(table: &mut Table, key: VirtualKeyCode, frames: u32) {
+    table.inject_key(key, ElementState::Pressed);
+    for _ in 0..frames {
+        table.step();
+    }
+    table.inject_key(key, ElementState::Released);
+    table.step();
+}
+
+fn config() -> Config {
+    Config {
+        no_audio: true,
+        ..Config::default()
+    }
+}
+
+fn in_play(table: &Table) -> bool {
+    !table.in_attract() && !table.at_spring() && !table.in_drain()
+}
+
+// Starts a game and launches the ball, returning a snapshot taken while it is
+// moving around the table.
+fn busy_state(data: &dyn DataSource, table_id: TableId) -> Vec<u8> {
+    let mut table = Table::new(data, config(), table_id, 0).unwrap();
+    for _ in 0..60 {
+        table.step();
+    }
+    press(&mut table, VirtualKeyCode::Return, 1);
+    while table.in_attract() || !table.at_spring() {
+        table.step();
+    }
+    press(&mut table, VirtualKeyCode::Down, 60);
+    for _ in 0..120 {
+        table.step();
+    }
+    assert!(in_play(&table), "{table_id:?}: ball is not in play");
+    table.save_state()
+}
+
+// Times f on a table kept in play; the table is restored from the snapshot
+// whenever the ball leaves play, outside of the measured time.
+fn bench_in_play(
+    b: &mut Bencher,
+    data: &dyn DataSource,
+    saved: &[u8],
+    mut f: impl FnMut(&mut Table),
+) {
+    let restore = || Table::load_state(data, config(), saved).unwrap();
+    let mut table = restore();
+    b.iter_custom(|iters| {
+        let mut total = Duration::ZERO;
+        for _ in 0..iters {
+            if !in_play(&table) {
+                table = restore();
+            }
+            let start = Instant::now();
+            f(black_box(&mut table));
+            total += start.elapsed();
+        }
+        total
+    });
+}
+
+fn physics(c: &mut Criterion) {
+    let Some(data) = data_source() else {
+        return;
+    };
+    for table_id in [
+        TableId::Table1,
+        TableId::Table2,
+        TableId::Table3,
+        TableId::Table4,
+    ] {
+        let saved = busy_state(&*data, table_id);
+        c.bench_function(&format!("{table_id:?} physics_frame"), |b| {
+            bench_in_play(b, &*data, &saved, |table| table.physics_frame())
+        });
+        c.bench_function(&format!("{table_id:?} run_frame"), |b| {
+            bench_in_play(b, &*data, &saved, |table| {
+                table.step();
+            })
+        });
+    }
+}
+
+criterion_group!(benches, physics);
+criterion_main!(benches);
@@ -1,4 +1,81 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use crate::{data::open_data_source, error::PfrError};
+
 pub mod iff;
 pub mod intro;
 pub mod mz;
 pub mod table;
+
+pub const DATA_FILES: [&str; 11] = [
+    "INTRO.PRG",
+    "INTRO.MOD",
+    "MOD2.MOD",
+    "TABLE1.PRG",
+    "TABLE1.MOD",
+    "TABLE2.PRG",
+    "TABLE2.MOD",
+    "TABLE3.PRG",
+    "TABLE3.MOD",
+    "TABLE4.PRG",
+    "TABLE4.MOD",
+];
+
+pub struct DataReport {
+    pub dir: PathBuf,
+    // file name and size, or None if it's absent
+    pub files: Vec<(&'static str, Option<u64>)>,
+}
+
+impl DataReport {
+    pub fn missing(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.files
+            .iter()
+            .filter(|(_, size)| size.is_none())
+            .map(|&(name, _)| name)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing().next().is_none()
+    }
+}
+
+impl Display for DataReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "game data in {}:", self.dir.display())?;
+        for &(name, size) in &self.files {
+            match size {
+                Some(size) => writeln!(f, "  {name:<12}{size:>8} bytes")?,
+                None => writeln!(f, "  {name:<12} missing")?,
+            }
+        }
+        if !self.is_complete() {
+            let missing: Vec<_> = self.missing().collect();
+            write!(
+                f,
+                "copy {} from the original game into {}",
+                missing.join(", "),
+                self.dir.display()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn verify_data_dir(dir: &Path) -> Result<DataReport, PfrError> {
+    let data = open_data_source(dir)?;
+    let mut files = vec![];
+    for name in DATA_FILES {
+        let size = data
+            .size(name)
+            .map_err(|e| PfrError::from_io(&data.path(name), e))?;
+        files.push((name, size));
+    }
+    Ok(DataReport {
+        dir: dir.to_path_buf(),
+        files,
+    })
+}
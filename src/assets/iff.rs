@@ -1,42 +1,94 @@
 use arrayref::array_ref;
 use ndarray::Array2;
 
+use super::mz::MzExe;
+
 #[derive(Clone, Debug)]
 pub struct Image {
     pub data: Array2<u8>,
     pub cmap: Vec<(u8, u8, u8)>,
 }
 
+fn next_byte(data: &[u8], pos: &mut usize) -> Result<u8, String> {
+    let byte = *data
+        .get(*pos)
+        .ok_or("BODY ends in the middle of the image")?;
+    *pos += 1;
+    Ok(byte)
+}
+
+// Expands one ByteRun1 run into dst, returning how many bytes it filled.
+fn unpack_run(data: &[u8], pos: &mut usize, dst: &mut [u8]) -> Result<usize, String> {
+    let b = next_byte(data, pos)?;
+    let n = if b < 0x80 {
+        (b as usize) + 1
+    } else if b == 0x80 {
+        return Err("unsupported 0x80 run".into());
+    } else {
+        0x101 - (b as usize)
+    };
+    if n > dst.len() {
+        return Err("run past the end of a line".into());
+    }
+    if b < 0x80 {
+        for dst in &mut dst[..n] {
+            *dst = next_byte(data, pos)?;
+        }
+    } else {
+        dst[..n].fill(next_byte(data, pos)?);
+    }
+    Ok(n)
+}
+
 impl Image {
-    pub fn parse(data: &[u8]) -> Image {
-        assert_eq!(&data[..4], b"FORM");
+    // The image starting at a segment of a DOS executable.
+    pub fn parse_segment(exe: &MzExe, seg: u16) -> Result<Image, String> {
+        let data = exe
+            .image
+            .get(usize::from(seg) * 0x10..)
+            .ok_or("image past the end of the executable")?;
+        Image::try_parse(data)
+    }
+
+    pub fn try_parse(data: &[u8]) -> Result<Image, String> {
+        if data.len() < 12 || &data[..4] != b"FORM" {
+            return Err("not an IFF image".into());
+        }
         let total_len = u32::from_be_bytes(*array_ref![data, 4, 4]) as usize;
-        let data = &data[8..8 + total_len];
-        let is_ilbm = match &data[..4] {
-            b"PBM " => false,
-            b"ILBM" => true,
-            unk => panic!("unknown IFF format {unk:?}"),
+        let data = data
+            .get(8..8 + total_len)
+            .ok_or("FORM is longer than the file")?;
+        let is_ilbm = match data.get(..4) {
+            Some(b"PBM ") => false,
+            Some(b"ILBM") => true,
+            unk => return Err(format!("unknown IFF format {unk:?}")),
         };
         let mut pos = 4;
         let mut image = None;
         let mut cmap = None;
-        while pos != total_len {
-            let chunk_hdr = array_ref![data, pos, 4];
-            let chunk_len = u32::from_be_bytes(*array_ref![data, pos + 4, 4]) as usize;
-            let chunk_data = &data[pos + 8..pos + chunk_len + 8];
+        while pos < total_len {
+            let chunk = data.get(pos..pos + 8).ok_or("truncated chunk header")?;
+            let chunk_hdr = array_ref![chunk, 0, 4];
+            let chunk_len = u32::from_be_bytes(*array_ref![chunk, 4, 4]) as usize;
+            let chunk_data = data
+                .get(pos + 8..pos + 8 + chunk_len)
+                .ok_or("chunk is longer than the FORM")?;
             match chunk_hdr {
                 b"BMHD" => {
-                    assert_eq!(chunk_len, 0x14);
+                    if chunk_len != 0x14 {
+                        return Err(format!("BMHD is {chunk_len} bytes"));
+                    }
                     let width = u16::from_be_bytes(*array_ref![chunk_data, 0, 2]) as usize;
                     let height = u16::from_be_bytes(*array_ref![chunk_data, 2, 2]) as usize;
-                    assert!(image.is_none());
+                    if image.is_some() {
+                        return Err("more than one BMHD".into());
+                    }
                     image = Some(Array2::zeros((width, height)));
                 }
                 b"CMAP" => {
-                    if is_ilbm {
-                        assert_eq!(chunk_len, 0x30);
-                    } else {
-                        assert_eq!(chunk_len, 0x300);
+                    let expected = if is_ilbm { 0x30 } else { 0x300 };
+                    if chunk_len != expected {
+                        return Err(format!("CMAP is {chunk_len} bytes"));
                     }
                     let mut c = vec![];
                     for i in 0..chunk_len / 3 {
@@ -49,65 +101,35 @@ impl Image {
                     cmap = Some(c);
                 }
                 b"BODY" => {
-                    let image = image.as_mut().unwrap();
+                    let image: &mut Array2<u8> = image.as_mut().ok_or("BODY before BMHD")?;
                     let mut chunk_pos = 0;
-                    for y in 0..image.dim().1 {
+                    let (width, height) = image.dim();
+                    let mut line = vec![
+                        0;
                         if is_ilbm {
-                            for plane in 0..4 {
-                                let lw = (image.dim().0 + 15) / 16 * 2;
-                                let mut line = vec![0; lw];
-                                let mut lpos = 0;
-                                while lpos != lw {
-                                    let b = chunk_data[chunk_pos];
-                                    chunk_pos += 1;
-                                    if b < 0x80 {
-                                        let n = (b as usize) + 1;
-                                        for _ in 0..n {
-                                            line[lpos] = chunk_data[chunk_pos];
-                                            lpos += 1;
-                                            chunk_pos += 1;
-                                        }
-                                    } else {
-                                        assert_ne!(b, 0x80);
-                                        let n = 0x101 - (b as usize);
-                                        let b = chunk_data[chunk_pos];
-                                        chunk_pos += 1;
-                                        for _ in 0..n {
-                                            line[lpos] = b;
-                                            lpos += 1;
-                                        }
-                                    }
-                                }
-                                for x in 0..image.dim().0 {
-                                    image[(x, y)] |= (line[x / 8] >> (7 - (x & 7)) & 1) << plane;
-                                }
-                            }
+                            width.div_ceil(16) * 2
                         } else {
-                            let mut x = 0;
-                            while x != image.dim().0 {
-                                let b = chunk_data[chunk_pos];
-                                chunk_pos += 1;
-                                if b < 0x80 {
-                                    let n = (b as usize) + 1;
-                                    for _ in 0..n {
-                                        image[(x, y)] = chunk_data[chunk_pos];
-                                        x += 1;
-                                        chunk_pos += 1;
-                                    }
+                            width
+                        }
+                    ];
+                    for y in 0..height {
+                        for plane in 0..if is_ilbm { 4 } else { 1 } {
+                            let mut lpos = 0;
+                            while lpos != line.len() {
+                                lpos += unpack_run(chunk_data, &mut chunk_pos, &mut line[lpos..])?;
+                            }
+                            for x in 0..width {
+                                if is_ilbm {
+                                    image[(x, y)] |= (line[x / 8] >> (7 - (x & 7)) & 1) << plane;
                                 } else {
-                                    assert_ne!(b, 0x80);
-                                    let n = 0x101 - (b as usize);
-                                    let b = chunk_data[chunk_pos];
-                                    chunk_pos += 1;
-                                    for _ in 0..n {
-                                        image[(x, y)] = b;
-                                        x += 1;
-                                    }
+                                    image[(x, y)] = line[x];
                                 }
                             }
                         }
                     }
-                    assert_eq!(chunk_pos, chunk_data.len());
+                    if chunk_pos != chunk_data.len() {
+                        return Err("BODY is longer than the image".into());
+                    }
                 }
                 _ => {}
             }
@@ -116,9 +138,9 @@ impl Image {
                 pos += 1;
             }
         }
-        Image {
-            data: image.unwrap(),
-            cmap: cmap.unwrap(),
-        }
+        Ok(Image {
+            data: image.ok_or("no BMHD")?,
+            cmap: cmap.ok_or("no CMAP")?,
+        })
     }
 }
@@ -1,9 +1,10 @@
 use std::path::Path;
 
-use crate::assets::mz::MzExe;
+use crate::{assets::mz::MzExe, data::DataSource, error::PfrError};
 
 use super::iff::Image;
 use ndarray::{concatenate, prelude::*};
+use serde::Deserialize;
 use unnamed_entity::{entity_id, EntityVec};
 
 entity_id! {
@@ -44,6 +45,31 @@ pub struct Slide {
     pub fade_from_white: bool,
 }
 
+// slides.toml in a custom slide directory: one [[slide]] table per image.
+#[derive(Deserialize)]
+struct SlideManifest {
+    slide: Vec<SlideEntry>,
+}
+
+#[derive(Deserialize)]
+struct SlideEntry {
+    image: String,
+    #[serde(default)]
+    gap_frames: u8,
+    #[serde(default = "default_fade_frames")]
+    fade_in_frames: u8,
+    #[serde(default = "default_fade_frames")]
+    fade_out_frames: u8,
+    // music ticks to show the slide for, counted from the previous slide's fade out
+    show_ticks: u32,
+    #[serde(default)]
+    fade_from_white: bool,
+}
+
+fn default_fade_frames() -> u8 {
+    20
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum TableSet {
     Table12,
@@ -63,12 +89,25 @@ fn get_left_text(exe: &MzExe, off: u16) -> Vec<Vec<u8>> {
 }
 
 impl Assets {
-    pub fn load(file: impl AsRef<Path>) -> std::io::Result<Self> {
-        let exe = MzExe::load(file, 0x80)?;
+    pub fn load(data: &dyn DataSource, file: &str) -> Result<Self, PfrError> {
+        let path = data.path(file);
+        let exe = MzExe::parse(data.read(file)?, 0x80).map_err(|e| PfrError::from_io(&path, e))?;
+        let image =
+            |seg| Image::parse_segment(&exe, seg).map_err(|e| PfrError::BadAsset(path.clone(), e));
+        let check_halves = |upper: &Image, lower: &Image| {
+            if upper.cmap == lower.cmap {
+                Ok(())
+            } else {
+                Err(PfrError::BadAsset(
+                    path.clone(),
+                    "logo halves have different palettes".into(),
+                ))
+            }
+        };
 
-        let logo0_u = Image::parse(exe.segment(0x3b41));
-        let logo0_l = Image::parse(exe.segment(0x4285));
-        assert_eq!(logo0_u.cmap, logo0_l.cmap);
+        let logo0_u = image(0x3b41)?;
+        let logo0_l = image(0x4285)?;
+        check_halves(&logo0_u, &logo0_l)?;
         let mut cmap = logo0_u.cmap;
         for i in 0..0x20 {
             let (r, g, b) = cmap[i];
@@ -83,9 +122,9 @@ impl Assets {
             cmap,
         };
 
-        let logo1_u = Image::parse(exe.segment(0x4d6a));
-        let logo1_l = Image::parse(exe.segment(0x4653));
-        assert_eq!(logo1_u.cmap, logo1_l.cmap);
+        let logo1_u = image(0x4d6a)?;
+        let logo1_l = image(0x4653)?;
+        check_halves(&logo1_u, &logo1_l)?;
         let logo1 = Image {
             data: concatenate!(
                 Axis(1),
@@ -95,9 +134,9 @@ impl Assets {
             cmap: logo1_u.cmap,
         };
 
-        let logo2_u = Image::parse(exe.segment(0x11c2));
-        let logo2_l = Image::parse(exe.segment(0x17b2));
-        assert_eq!(logo2_u.cmap, logo2_l.cmap);
+        let logo2_u = image(0x11c2)?;
+        let logo2_l = image(0x17b2)?;
+        check_halves(&logo2_u, &logo2_l)?;
         let logo2 = Image {
             data: concatenate!(
                 Axis(1),
@@ -107,10 +146,10 @@ impl Assets {
             cmap: logo2_u.cmap,
         };
 
-        let mut presents = Image::parse(exe.segment(0x10a6));
+        let mut presents = image(0x10a6)?;
         presents.data = concatenate!(Axis(1), presents.data, Array2::zeros((320, 40)));
 
-        let mut pflogo = Image::parse(exe.segment(0xa05));
+        let mut pflogo = image(0xa05)?;
         pflogo.data = concatenate!(
             Axis(1),
             Array2::zeros((640, 150)),
@@ -118,7 +157,7 @@ impl Assets {
             Array2::zeros((640, 152)),
         );
 
-        let slides = [
+        let slides: EntityVec<SlideId, Slide> = [
             Slide {
                 image: logo0,
                 gap_frames: 0,
@@ -163,26 +202,26 @@ impl Assets {
         .into_iter()
         .collect();
 
-        let mut left = Image::parse(exe.segment(0x1c71));
+        let mut left = image(0x1c71)?;
         left.data = left.data.slice(s![..130, ..240]).to_owned();
 
-        let read_table = |seg| {
-            let mut table = Image::parse(exe.segment(seg));
+        let read_table = |seg| -> Result<Image, PfrError> {
+            let mut table = image(seg)?;
             table.data = table.data.slice(s![..440, ..95]).to_owned();
-            table
+            Ok(table)
         };
-        let table1 = read_table(0x2003);
-        let table2 = read_table(0x2465);
-        let table3 = read_table(0x2901);
-        let table4 = read_table(0x2d94);
+        let table1 = read_table(0x2003)?;
+        let table2 = read_table(0x2465)?;
+        let table3 = read_table(0x2901)?;
+        let table4 = read_table(0x2d94)?;
 
-        let font_hq = Image::parse(exe.segment(0x677));
-        let mut font_lq = Image::parse(exe.segment(0x870));
+        let font_hq = image(0x677)?;
+        let mut font_lq = image(0x870)?;
         font_lq.data = font_lq.data.slice(s![.., ..28]).to_owned();
 
-        let mut hiscores_lq = Image::parse(exe.segment(0x3301));
+        let mut hiscores_lq = image(0x3301)?;
         hiscores_lq.data = hiscores_lq.data.slice(s![..400, ..40]).to_owned();
-        let mut hiscores_hq = Image::parse(exe.segment(0x3499));
+        let mut hiscores_hq = image(0x3499)?;
         hiscores_hq.data = hiscores_hq.data.slice(s![..400, ..40]).to_owned();
 
         let mut text_pages = EntityVec::new();
@@ -237,6 +276,10 @@ impl Assets {
         }
         let warp_table = warp_table.into_iter().map(|x| x.unwrap()).collect();
 
+        for slide in slides.values() {
+            check_slide_size(&path, &slide.image)?;
+        }
+
         Ok(Self {
             slides,
             left,
@@ -257,6 +300,52 @@ impl Assets {
     }
 }
 
+impl Assets {
+    pub fn load_extra_slides(&mut self, dir: &dyn DataSource) -> Result<(), PfrError> {
+        let manifest = dir.read("slides.toml")?;
+        let manifest: SlideManifest = std::str::from_utf8(&manifest)
+            .map_err(|e| e.to_string())
+            .and_then(|manifest| toml::from_str(manifest).map_err(|e| e.to_string()))
+            .map_err(|e| PfrError::BadAsset(dir.path("slides.toml"), e))?;
+        let mut tick = self
+            .slides
+            .values()
+            .last()
+            .map_or(0, |slide| slide.fade_out_tick);
+        let mut slides = vec![];
+        for entry in manifest.slide {
+            let path = dir.path(&entry.image);
+            let raw = dir.read(&entry.image)?;
+            let image = Image::try_parse(&raw).map_err(|e| PfrError::BadAsset(path.clone(), e))?;
+            check_slide_size(&path, &image)?;
+            tick += entry.show_ticks;
+            slides.push(Slide {
+                image,
+                gap_frames: entry.gap_frames,
+                fade_in_frames: entry.fade_in_frames.max(1),
+                fade_out_frames: entry.fade_out_frames.max(1),
+                fade_out_tick: tick,
+                fade_from_white: entry.fade_from_white,
+            });
+        }
+        for slide in slides {
+            self.slides.push(slide);
+        }
+        Ok(())
+    }
+}
+
+fn check_slide_size(path: &Path, image: &Image) -> Result<(), PfrError> {
+    match image.data.dim() {
+        (320, 240) | (640, 480) => Ok(()),
+        (width, height) => Err(PfrError::UnsupportedImageDimensions {
+            path: path.to_path_buf(),
+            width,
+            height,
+        }),
+    }
+}
+
 pub const CGA_FONT: [[u8; 8]; 0x80] = [
     [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
     [0x7e, 0x81, 0xa5, 0x81, 0xbd, 0x99, 0x81, 0x7e],
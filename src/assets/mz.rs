@@ -1,5 +1,3 @@
-use std::path::Path;
-
 use arrayref::array_ref;
 
 use crate::bcd::Bcd;
@@ -22,20 +20,31 @@ pub struct FarPtr {
 }
 
 impl MzExe {
-    pub fn load(file: impl AsRef<Path>, ds: u16) -> std::io::Result<Self> {
-        let data = std::fs::read(file)?;
-        assert_eq!(&data[..2], b"MZ");
+    pub fn parse(data: Vec<u8>, ds: u16) -> std::io::Result<Self> {
+        let bad = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        if data.len() < 0x20 || &data[..2] != b"MZ" {
+            return Err(bad("not a DOS executable"));
+        }
         let sz_last = u16::from_le_bytes(*array_ref![data, 2, 2]) as usize;
         let sz_pages = u16::from_le_bytes(*array_ref![data, 4, 2]) as usize;
+        if sz_pages == 0 {
+            return Err(bad("empty DOS executable"));
+        }
         let image_sz = (sz_pages - 1) * 0x200 + sz_last;
         let num_relocs = u16::from_le_bytes(*array_ref![data, 6, 2]) as usize;
         let header_sz = u16::from_le_bytes(*array_ref![data, 8, 2]) as usize * 0x10;
-        let image = data[header_sz..image_sz].to_vec();
+        let image = data
+            .get(header_sz..image_sz)
+            .ok_or_else(|| bad("DOS executable header doesn't match the file size"))?
+            .to_vec();
         let ss = u16::from_le_bytes(*array_ref![data, 0xe, 2]);
         let sp = u16::from_le_bytes(*array_ref![data, 0x10, 2]);
         let ip = u16::from_le_bytes(*array_ref![data, 0x14, 2]);
         let cs = u16::from_le_bytes(*array_ref![data, 0x16, 2]);
         let reloc_base = u16::from_le_bytes(*array_ref![data, 0x18, 2]) as usize;
+        if reloc_base + num_relocs * 4 > data.len() {
+            return Err(bad("DOS executable relocations past the end of the file"));
+        }
         let relocs = (0..num_relocs)
             .map(|i| {
                 let off = reloc_base + i * 4;
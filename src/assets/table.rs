@@ -1,4 +1,4 @@
-use std::{collections::HashMap, path::Path};
+use std::collections::HashMap;
 
 use arrayvec::ArrayVec;
 use enum_map::EnumMap;
@@ -21,6 +21,8 @@ use crate::{
     },
     bcd::Bcd,
     config::TableId,
+    data::DataSource,
+    error::PfrError,
 };
 
 use sound::{Jingle, JingleBind, Sfx, SfxBind};
@@ -102,16 +104,33 @@ pub struct Assets {
 }
 
 impl Assets {
-    pub fn load(file: impl AsRef<Path>, table: TableId) -> std::io::Result<Self> {
-        let mut exe = MzExe::load(file, 0)?;
-        assert_eq!(exe.code_byte(exe.ip + 0xe), 0xb8);
-        let ds = exe.code_word(exe.ip + 0xf);
+    pub fn load(data: &dyn DataSource, file: &str, table: TableId) -> Result<Self, PfrError> {
+        let mut exe = MzExe::parse(data.read(file)?, 0)
+            .map_err(|e| PfrError::from_io(&data.path(file), e))?;
+        // the entry point starts with mov ax, <data segment>
+        let entry = usize::from(exe.cs) * 0x10 + usize::from(exe.ip) + 0xe;
+        let ds = match exe.image.get(entry..entry + 3) {
+            Some(&[0xb8, lo, hi]) => u16::from_le_bytes([lo, hi]),
+            _ => {
+                return Err(PfrError::BadAsset(
+                    data.path(file),
+                    "unexpected code at the entry point".into(),
+                ))
+            }
+        };
+        if usize::from(ds) * 0x10 >= exe.image.len() {
+            return Err(PfrError::BadAsset(
+                data.path(file),
+                "data segment past the end of the file".into(),
+            ));
+        }
         exe.ds = ds;
 
         let (lights, dm_palette) = extract_lights(&exe, table);
         let attract_lights = extract_attract_lights(&exe, table);
         let light_binds = extract_light_binds(table);
-        let main_board = extract_main_board(&exe, table);
+        let main_board =
+            extract_main_board(&exe, table).map_err(|e| PfrError::BadAsset(data.path(file), e))?;
         let occmaps = gfx::extract_occmaps(&exe, table);
         let spring = Image {
             data: extract_spring(&exe, table),
@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use arrayvec::ArrayVec;
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::Deserialize;
 
-use crate::{assets::mz::MzExe, config::TableId};
+use crate::{assets::mz::MzExe, config::TableId, data::DataSource, error::PfrError};
+
+use super::Assets;
 
 #[derive(Clone, Debug)]
 pub struct DmPalette {
@@ -13,7 +16,7 @@ pub struct DmPalette {
     pub color_on: (u8, u8, u8),
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Enum)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Enum, Deserialize)]
 pub enum DmFont {
     H5,
     H8,
@@ -32,6 +35,89 @@ impl DmFont {
     }
 }
 
+// fonts.toml in a custom font directory: one [[font]] table per image, each
+// holding the glyphs of chars side by side in 8 pixel wide cells.
+#[derive(Deserialize)]
+struct FontManifest {
+    font: Vec<FontEntry>,
+}
+
+#[derive(Deserialize)]
+struct FontEntry {
+    size: DmFont,
+    image: String,
+    chars: String,
+}
+
+impl Assets {
+    pub fn load_dm_fonts(&mut self, dir: &dyn DataSource) -> Result<(), PfrError> {
+        let manifest = dir.read("fonts.toml")?;
+        let manifest: FontManifest = std::str::from_utf8(&manifest)
+            .map_err(|e| e.to_string())
+            .and_then(|manifest| toml::from_str(manifest).map_err(|e| e.to_string()))
+            .map_err(|e| PfrError::BadAsset(dir.path("fonts.toml"), e))?;
+        let mut fonts = self.dm_fonts.clone();
+        for entry in manifest.font {
+            let path = dir.path(&entry.image);
+            let (width, height, lit) = load_glyph_image(&dir.read(&entry.image)?, &path)?;
+            let font = entry.size;
+            if !entry.chars.is_ascii() || height != font.height() || width != entry.chars.len() * 8
+            {
+                return Err(PfrError::BadAsset(
+                    path,
+                    format!(
+                        "{font:?} glyphs of {} chars need a {}x{} image, not {width}x{height}",
+                        entry.chars.len(),
+                        entry.chars.len() * 8,
+                        font.height()
+                    ),
+                ));
+            }
+            for (i, chr) in entry.chars.bytes().enumerate() {
+                let glyph = (0..height)
+                    .map(|y| {
+                        (0..8)
+                            .filter(|x| lit[y * width + i * 8 + x])
+                            .fold(0, |line, x| line | 0x80 >> x)
+                    })
+                    .collect();
+                fonts[font].insert(chr.to_ascii_uppercase(), glyph);
+            }
+        }
+        self.dm_fonts = fonts;
+        Ok(())
+    }
+}
+
+// Reads an image as a grid of lit pixels: anything bright and not transparent.
+fn load_glyph_image(raw: &[u8], path: &Path) -> Result<(usize, usize, Vec<bool>), PfrError> {
+    let mut decoder = png::Decoder::new(raw);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| PfrError::BadAsset(path.to_path_buf(), e.to_string()))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| PfrError::BadAsset(path.to_path_buf(), e.to_string()))?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    let samples = info.color_type.samples();
+    let has_alpha = matches!(
+        info.color_type,
+        png::ColorType::GrayscaleAlpha | png::ColorType::Rgba
+    );
+    let colors = if has_alpha { samples - 1 } else { samples };
+    let mut lit = Vec::with_capacity(width * height);
+    for row in buf.chunks(info.line_size).take(height) {
+        for pixel in row.chunks_exact(samples).take(width) {
+            let bright = pixel[..colors].iter().any(|&c| c >= 0x80);
+            let opaque = !has_alpha || pixel[samples - 1] >= 0x80;
+            lit.push(bright && opaque);
+        }
+    }
+    Ok((width, height, lit))
+}
+
 pub(super) fn extract_dm_fonts(
     exe: &MzExe,
     table: TableId,
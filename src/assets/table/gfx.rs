@@ -9,15 +9,18 @@ use crate::{
 
 use super::{dm::DmPalette, physics::Layer, Light, LightId};
 
-pub(super) fn extract_main_board(exe: &MzExe, table: TableId) -> Image {
+pub(super) fn extract_main_board(exe: &MzExe, table: TableId) -> Result<Image, String> {
     let pbm_segs = match table {
         TableId::Table1 => [0x5224, 0x5947, 0x617b, 0x6a9c],
         TableId::Table2 => [0x5054, 0x5820, 0x5fe4, 0x6791],
         TableId::Table3 => [0x4c96, 0x5221, 0x5a4b, 0x632d],
         TableId::Table4 => [0x4ba1, 0x5480, 0x5d87, 0x66c2],
     };
-    let pbms = pbm_segs.map(|x| Image::parse(exe.segment(x)));
-    Image {
+    let pbms = pbm_segs
+        .into_iter()
+        .map(|x| Image::parse_segment(exe, x))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Image {
         data: concatenate!(
             Axis(1),
             pbms[0].data.slice(s![.., ..144]),
@@ -26,7 +29,7 @@ pub(super) fn extract_main_board(exe: &MzExe, table: TableId) -> Image {
             pbms[3].data.slice(s![.., ..144]),
         ),
         cmap: pbms[3].cmap.clone(),
-    }
+    })
 }
 
 pub(super) fn extract_lights(
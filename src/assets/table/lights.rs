@@ -1,7 +1,10 @@
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::Deserialize;
 use unnamed_entity::{entity_id, EntityId, EntityVec};
 
-use crate::{assets::mz::MzExe, config::TableId};
+use crate::{assets::mz::MzExe, config::TableId, data::DataSource, error::PfrError};
+
+use super::Assets;
 
 entity_id! {
     pub id LightId u8;
@@ -137,6 +140,72 @@ pub(super) fn extract_attract_lights(
     res
 }
 
+// A light show for attract mode: steps played in a loop, each lighting some
+// of the table's lights (numbered from 1, as in the game data) for a number of
+// frames.
+const MAX_LIGHT_SHOW_FRAMES: u16 = 30000;
+
+#[derive(Deserialize)]
+struct LightShow {
+    step: Vec<LightShowStep>,
+}
+
+#[derive(Deserialize)]
+struct LightShowStep {
+    lights: Vec<u16>,
+    frames: u16,
+}
+
+impl Assets {
+    pub fn load_attract_lights(
+        &mut self,
+        dir: &dyn DataSource,
+        name: &str,
+    ) -> Result<(), PfrError> {
+        let path = dir.path(name);
+        let bad = |msg: String| PfrError::BadAsset(path.clone(), msg);
+        let show = String::from_utf8(dir.read(name)?).map_err(|e| bad(e.to_string()))?;
+        let show: LightShow = toml::from_str(&show).map_err(|e| bad(e.to_string()))?;
+        if show.step.len() < 2 {
+            return Err(bad("a light show needs at least two steps".into()));
+        }
+        if show.step.iter().any(|step| step.frames == 0) {
+            return Err(bad("every step needs at least one frame".into()));
+        }
+        let period: u32 = show.step.iter().map(|step| u32::from(step.frames)).sum();
+        if period > MAX_LIGHT_SHOW_FRAMES.into() {
+            return Err(bad(format!(
+                "the steps take {period} frames, more than {MAX_LIGHT_SHOW_FRAMES}"
+            )));
+        }
+        let period = period as u16;
+        // Each light of a step gets an entry of its own that keeps it dark
+        // for the rest of the loop, starting with the lights of the first
+        // step once the loop has run once.
+        let mut res = EntityVec::new();
+        let mut start = 0;
+        for step in &show.step {
+            for &light in &step.lights {
+                if light == 0 || usize::from(light) > self.lights.len() {
+                    return Err(bad(format!(
+                        "no light {light}, the table has lights 1 to {}",
+                        self.lights.len()
+                    )));
+                }
+                res.push(AttractLight {
+                    ctr_reset: start,
+                    ctr_off: start + step.frames,
+                    ctr_on: start + period,
+                    light: LightId::from_idx(usize::from(light) - 1),
+                });
+            }
+            start += step.frames;
+        }
+        self.attract_lights = res;
+        Ok(())
+    }
+}
+
 pub(super) fn extract_light_binds(table: TableId) -> EnumMap<LightBind, Vec<LightId>> {
     let light_sets: &[(_, &[_])] = match table {
         TableId::Table1 => &[
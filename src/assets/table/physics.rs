@@ -2,6 +2,7 @@
 
 use enum_map::{enum_map, Enum, EnumMap};
 use ndarray::prelude::*;
+use serde::{Deserialize, Serialize};
 use unnamed_entity::{entity_id, EntityVec};
 
 use crate::{assets::mz::MzExe, bcd::Bcd, config::TableId};
@@ -12,7 +13,9 @@ entity_id! {
     pub id BumperId u8, reserve 1;
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Enum)]
+#[derive(
+    Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Enum, Serialize, Deserialize,
+)]
 pub enum Layer {
     Ground,
     Overhead,
@@ -51,7 +54,7 @@ pub struct HitTriggerArea {
     pub kind: HitTrigger,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum RollTrigger {
     Dummy,
     PlungerBottom,
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
 use unnamed_entity::{entity_id, EntityMap, EntityVec};
 
 use crate::{assets::mz::MzExe, bcd::Bcd, config::TableId};
@@ -112,7 +113,7 @@ pub struct DmCoord {
     pub y: i16,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ScriptScore {
     Bonus,
     ModeHit,
@@ -0,0 +1,147 @@
+// The intro's attract demo: a table started by a bundled input log and then
+// played by the autoplay.  It goes back to the intro when the game is over,
+// the time is up or any key is pressed.
+
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+
+use crate::{
+    config::{Config, KeyBindings, Options, TableId},
+    data::DataSource,
+    demo::{Demo, DemoInput},
+    error::PfrError,
+    table::{Autoplay, Table},
+    view::{Action, PadButton, PanelLayout, Route, View},
+};
+
+const DEMO: &str = include_str!("../demos/attract.toml");
+const DEMO_SECONDS: u32 = 60;
+
+pub struct AttractDemo {
+    table: Table,
+    table_id: TableId,
+    demo: Demo,
+    autoplay: Autoplay,
+    frame: u64,
+    started: bool,
+    done: bool,
+}
+
+impl AttractDemo {
+    pub fn new(data: &dyn DataSource, config: Config, table: TableId) -> Result<Self, PfrError> {
+        let demo: Demo = toml::from_str(DEMO).expect("bad attract demo");
+        // The game has to go as it was recorded, so only the presentation
+        // follows the player's options.
+        let options = Options {
+            balls: demo.options.balls,
+            hifps: demo.options.hifps,
+            tilt_sensitivity: demo.options.tilt_sensitivity,
+            ball_save: demo.options.ball_save,
+            replay_score: demo.options.replay_score,
+            attract_timeout: 0,
+            cheat_menu: false,
+            ..config.options_for(table)
+        };
+        let config = Config {
+            options,
+            ignore_table_options: true,
+            key_bindings: KeyBindings::default(),
+            tournament: false,
+            run_ahead: false,
+            ..config
+        };
+        Ok(AttractDemo {
+            table: Table::new(data, config, table, demo.seed)?,
+            table_id: table,
+            demo,
+            autoplay: Autoplay::default(),
+            frame: 0,
+            started: false,
+            done: false,
+        })
+    }
+
+    fn send_input(&mut self, input: DemoInput, state: ElementState) {
+        match input {
+            DemoInput::Key(key) => self.table.handle_key(key, state),
+            DemoInput::Button(button) => self.table.handle_button(button, state),
+            DemoInput::Mouse(pos, button) => {
+                self.table.handle_mouse(pos, button, state);
+            }
+            DemoInput::FocusLost => self.table.on_focus_lost(),
+        }
+    }
+}
+
+impl View for AttractDemo {
+    fn get_resolution(&self) -> (u32, u32) {
+        self.table.get_resolution()
+    }
+
+    fn get_fps(&self) -> u32 {
+        self.table.get_fps()
+    }
+
+    fn run_frame(&mut self) -> Action {
+        if self.done || self.frame >= u64::from(DEMO_SECONDS * self.get_fps()) {
+            return Action::Navigate(Route::Intro(Some(self.table_id)));
+        }
+        let events: Vec<_> = self.demo.events_at(self.frame).copied().collect();
+        for event in events {
+            self.send_input(event.input, event.state);
+        }
+        self.table.autoplay_frame(&mut self.autoplay);
+        let action = self.table.run_frame();
+        self.frame += 1;
+        // back in attract mode after the game is over
+        self.started |= !self.table.in_attract();
+        if self.started && self.table.in_attract() {
+            self.done = true;
+        }
+        if matches!(action, Action::Navigate(_) | Action::Exit) {
+            self.done = true;
+        }
+        Action::None
+    }
+
+    fn handle_key(&mut self, _key: VirtualKeyCode, state: ElementState) {
+        self.done |= state == ElementState::Pressed;
+    }
+
+    fn handle_button(&mut self, _button: PadButton, state: ElementState) {
+        self.done |= state == ElementState::Pressed;
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _pos: (u32, u32),
+        _button: MouseButton,
+        state: ElementState,
+    ) -> bool {
+        self.done |= state == ElementState::Pressed;
+        true
+    }
+
+    fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.table.render(data, pal);
+    }
+
+    fn render_frame_stats(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)], text: &[u8]) {
+        self.table.render_frame_stats(data, pal, text);
+    }
+
+    fn get_extra_panel(&self) -> Option<PanelLayout> {
+        self.table.get_extra_panel()
+    }
+
+    fn render_panel(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.table.render_panel(data, pal);
+    }
+
+    fn scroll_blend(&self) -> Option<(usize, u8)> {
+        self.table.scroll_blend()
+    }
+
+    fn display_text(&self) -> Vec<String> {
+        self.table.display_text()
+    }
+}
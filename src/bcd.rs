@@ -50,6 +50,22 @@ impl Bcd {
         }
     }
 
+    // keeps the lowest 12 digits
+    pub fn from_u64(mut value: u64) -> Self {
+        let mut res = Self::ZERO;
+        for digit in res.digits.iter_mut().rev() {
+            *digit = (value % 10) as u8;
+            value /= 10;
+        }
+        res
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.digits
+            .iter()
+            .fold(0, |acc, &digit| acc * 10 + u64::from(digit))
+    }
+
     pub fn leading_zeros(self) -> usize {
         let mut res = 0;
         while self.digits[res] == 0 && res < 12 {
@@ -99,3 +115,24 @@ impl core::ops::Mul<u8> for Bcd {
         res
     }
 }
+
+impl serde::Serialize for Bcd {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ascii = self.to_ascii();
+        let start = ascii.iter().position(|&x| x != b' ').unwrap();
+        serializer.serialize_str(core::str::from_utf8(&ascii[start..]).unwrap())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Bcd {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.is_empty() || s.len() > Self::DIGITS || !s.bytes().all(|x| x.is_ascii_digit()) {
+            return Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"a decimal number of at most 12 digits",
+            ));
+        }
+        Ok(Self::from_ascii(s.as_bytes()))
+    }
+}
@@ -1,6 +1,7 @@
 use clap::Parser;
 use pfr::assets::iff::Image;
 use pfr::assets::intro::{Assets, SlideId, TextPage};
+use pfr::data::open_data_source;
 use std::io::BufWriter;
 use std::{
     fs::File,
@@ -43,7 +44,8 @@ fn save_png(image: &Image, output_dir: impl AsRef<Path>, name: &str) -> std::io:
 
 fn main() -> std::io::Result<()> {
     let args = Args::parse();
-    let assets = Assets::load(args.input_dir.join("INTRO.PRG"))?;
+    let data = open_data_source(&args.input_dir)?;
+    let assets = Assets::load(&*data, "INTRO.PRG")?;
     save_png(
         &assets.slides[SlideId::from_idx(0)].image,
         &args.output_dir,
@@ -3,6 +3,7 @@ use ndarray::{s, Array2};
 use pfr::assets::iff::Image;
 use pfr::assets::table::{physics::Layer, Assets};
 use pfr::config::TableId;
+use pfr::data::open_data_source;
 use std::io::BufWriter;
 use std::{
     fs::File,
@@ -52,7 +53,8 @@ fn main() -> std::io::Result<()> {
         4 => (TableId::Table4, "TABLE4.PRG"),
         _ => panic!("oops weird table"),
     };
-    let assets = Assets::load(args.input_dir.join(file), table)?;
+    let data = open_data_source(&args.input_dir)?;
+    let assets = Assets::load(&*data, file, table)?;
     println!("DS: {ds:04x}", ds = assets.exe.ds);
     let mut main_board = assets.main_board.clone();
 
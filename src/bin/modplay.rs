@@ -15,7 +15,7 @@ fn main() -> std::io::Result<()> {
     let mut f = File::open(args.modfile)?;
     let module = pfr::sound::loader::load(&mut f)?;
     let sequencer = Arc::new(TableSequencer::new(0, 0, 0, false));
-    let player = pfr::sound::player::play(module, Some(sequencer.clone()));
+    let player = pfr::sound::player::play(module, Some(sequencer.clone()), None);
     // println!("NAME: {}", module.name);
     // for (i, pat) in module.patterns.iter().enumerate() {
     //     println!("--- PAT {i:02x} ---");
@@ -47,6 +47,22 @@ fn main() -> std::io::Result<()> {
                 None,
             );
         }
+        if let Some(r) = c.strip_prefix('t') {
+            if let Ok(scale) = r.parse::<f32>() {
+                if scale > 0.0 && scale.is_finite() {
+                    player.set_tempo_scale(scale);
+                }
+            }
+            continue;
+        }
+        if let Some(r) = c.strip_prefix('p') {
+            if let Ok(scale) = r.parse::<f32>() {
+                if scale > 0.0 && scale.is_finite() {
+                    player.set_pitch_scale(scale);
+                }
+            }
+            continue;
+        }
         if let Some(r) = c.strip_prefix('m') {
             let Ok(r) = u32::from_str_radix(r, 16) else {
                 continue;
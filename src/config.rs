@@ -1,29 +1,177 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-use crate::bcd::Bcd;
+use crate::{
+    bcd::Bcd,
+    game::Key,
+    lang::Language,
+    sound::player::{Backend, DEFAULT_BUFFER_MS},
+};
 use arrayref::array_ref;
+use arrayvec::ArrayVec;
+use directories::ProjectDirs;
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    pub slides_dir: Option<PathBuf>,
+    pub dm_font_dir: Option<PathBuf>,
+    pub audio_device: Option<String>,
+    pub audio_latency: AudioLatency,
+    pub vsync: bool,
+    // presented frames per second, on top of vsync
+    pub fps_cap: Option<u16>,
+    pub run_ahead: bool,
+    pub language: Language,
+    // modules played with the tables instead of their own
+    pub music: BTreeMap<TableId, PathBuf>,
+    // light shows played in attract mode instead of the tables' own
+    pub attract_lights: BTreeMap<TableId, PathBuf>,
     pub options: Options,
-    pub high_scores: EnumMap<TableId, [HighScore; 4]>,
+    pub table_options: BTreeMap<TableId, TableOptions>,
+    pub high_scores: EnumMap<TableId, Vec<HighScore>>,
+    pub tournament_high_scores: EnumMap<TableId, Vec<HighScore>>,
+    pub key_bindings: KeyBindings,
+    #[serde(skip)]
+    pub tournament: bool,
+    #[serde(skip)]
+    pub no_audio: bool,
+    // demos record and replay the global options only
+    #[serde(skip)]
+    pub ignore_table_options: bool,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Options {
     pub balls: u8,
     pub angle_high: bool,
     pub scroll_speed: ScrollSpeed,
     pub resolution: Resolution,
     pub no_music: bool,
-    pub mono: bool,
+    pub music_volume: u8,
+    pub sfx_volume: u8,
+    pub color_mode: ColorMode,
+    pub tilt_sensitivity: TiltSensitivity,
+    pub hifps: bool,
+    pub window_scale: WindowScale,
+    pub crt_filter: bool,
+    pub reduce_flashing: bool,
+    pub captions: bool,
+    pub ball_style: BallStyle,
+    pub ball_color: BallColor,
+    pub gamma: u8,
+    pub skip_intro_slides: bool,
+    pub table_select: bool,
+    pub attract_timeout: u16,
+    pub attract_demo: bool,
+    pub ball_save: u8,
+    pub smooth_scroll: bool,
+    pub keep_ball_in_view: bool,
+    pub side_panel: bool,
+    pub fullscreen: bool,
+    pub cheat_menu: bool,
+    pub ball_trail: bool,
+    pub ball_trail_length: u8,
+    // brightness kept by each older trail sprite, in percent
+    pub ball_trail_fade: u8,
+    pub aim_guide: bool,
+    pub high_score_saving: HighScoreSaving,
+    // score that earns each player one extra ball, 0 for none
+    pub replay_score: u64,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+// Settings a table can keep apart from the global options; the ones left unset
+// follow the global options.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableOptions {
+    pub angle_high: Option<bool>,
+    pub scroll_speed: Option<ScrollSpeed>,
+    pub resolution: Option<Resolution>,
+}
+
+pub const MAX_VOLUME: u8 = 10;
+
+pub const MIN_BALLS: u8 = 1;
+pub const MAX_BALLS: u8 = 9;
+
+// Sixty-fourths of the distance to the ball the view scrolls each frame.
+pub const MIN_SCROLL_SPEED: u8 = 4;
+pub const DEFAULT_CUSTOM_SCROLL_SPEED: u8 = 30;
+pub const MAX_SCROLL_SPEED: u8 = 64;
+
+pub const REPLAY_SCORE_STEP: u64 = 5_000_000;
+pub const MAX_REPLAY_SCORE: u64 = 500_000_000;
+
+// Audio buffer length in milliseconds.
+pub const MIN_AUDIO_LATENCY: u16 = 5;
+pub const MAX_AUDIO_LATENCY: u16 = 200;
+
+pub const MIN_FPS_CAP: u16 = 30;
+pub const MAX_FPS_CAP: u16 = 1000;
+
+// Gamma in tenths.
+pub const MIN_GAMMA: u8 = 5;
+pub const DEFAULT_GAMMA: u8 = 10;
+pub const MAX_GAMMA: u8 = 25;
+
+pub type KeyBinding = ArrayVec<Key, 4>;
+
+// The views still take winit's key codes, which the bindings are matched
+// against here.
+pub(crate) fn is_bound(binding: &KeyBinding, key: VirtualKeyCode) -> bool {
+    binding.iter().any(|bound| bound.to_winit() == key)
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub flipper_left: KeyBinding,
+    pub flipper_right: KeyBinding,
+    pub nudge: KeyBinding,
+    pub nudge_left: KeyBinding,
+    pub nudge_right: KeyBinding,
+    pub plunger: KeyBinding,
+    pub pause: KeyBinding,
+    pub quit: KeyBinding,
+}
+
+pub const MAX_HIGH_SCORES: usize = 10;
+
+pub const MAX_NAME_LEN: usize = 12;
+
+pub type HighScoreName = ArrayVec<u8, MAX_NAME_LEN>;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct HighScore {
     pub score: Bcd,
-    pub name: [u8; 3],
+    #[serde(with = "name_str")]
+    pub name: HighScoreName,
+}
+
+impl HighScore {
+    pub fn new(name: &[u8], score: Bcd) -> Self {
+        Self {
+            score,
+            name: name_from_bytes(name),
+        }
+    }
+}
+
+// What resetting the high scores leaves: the four blank entries of a fresh
+// DOS high score file.
+pub fn cleared_high_scores() -> Vec<HighScore> {
+    vec![HighScore::new(b"", Bcd::from_digit(0)); 4]
+}
+
+fn name_from_bytes(name: &[u8]) -> HighScoreName {
+    name.iter().copied().take(MAX_NAME_LEN).collect()
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -32,21 +180,123 @@ pub enum Angle {
     High,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum ScrollSpeed {
     Hard,
     Medium,
     Soft,
+    Custom(u8),
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+impl ScrollSpeed {
+    pub fn speed(self) -> u8 {
+        match self {
+            ScrollSpeed::Hard => 20,
+            ScrollSpeed::Medium => 11,
+            ScrollSpeed::Soft => 9,
+            ScrollSpeed::Custom(speed) => speed.clamp(MIN_SCROLL_SPEED, MAX_SCROLL_SPEED),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum Resolution {
     Normal,
     High,
     Full,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Enum, Debug)]
+impl Resolution {
+    pub fn next(self) -> Self {
+        match self {
+            Resolution::Normal => Resolution::High,
+            Resolution::High => Resolution::Full,
+            Resolution::Full => Resolution::Normal,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum TiltSensitivity {
+    Low,
+    Normal,
+    High,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum ColorMode {
+    Color,
+    Mono,
+    Deuteranopia,
+    Protanopia,
+}
+
+// How the ball is drawn: the original sprite, the sprite with its edge in a
+// bright color, or all in the bright color.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum BallStyle {
+    Sprite,
+    Outline,
+    Solid,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Enum, Debug, Serialize, Deserialize)]
+pub enum BallColor {
+    White,
+    Yellow,
+    Cyan,
+    Magenta,
+}
+
+impl BallColor {
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            BallColor::White => (0xff, 0xff, 0xff),
+            BallColor::Yellow => (0xff, 0xff, 0x00),
+            BallColor::Cyan => (0x00, 0xff, 0xff),
+            BallColor::Magenta => (0xff, 0x00, 0xff),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum WindowScale {
+    X1,
+    X2,
+    X3,
+    Fit,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum AudioLatency {
+    Low,
+    Medium,
+    High,
+    Custom(u16),
+}
+
+impl AudioLatency {
+    pub fn buffer_ms(self) -> u32 {
+        match self {
+            AudioLatency::Low => 10,
+            AudioLatency::Medium => DEFAULT_BUFFER_MS,
+            AudioLatency::High => 50,
+            AudioLatency::Custom(ms) => ms.clamp(MIN_AUDIO_LATENCY, MAX_AUDIO_LATENCY).into(),
+        }
+    }
+}
+
+// When a table's new high scores are written to disk: as soon as they're
+// entered, or once when leaving the table.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum HighScoreSaving {
+    Immediate,
+    OnExit,
+}
+
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Enum, Debug, Serialize, Deserialize,
+)]
 pub enum TableId {
     Table1,
     Table2,
@@ -54,6 +304,13 @@ pub enum TableId {
     Table4,
 }
 
+pub const TABLES: [TableId; 4] = [
+    TableId::Table1,
+    TableId::Table2,
+    TableId::Table3,
+    TableId::Table4,
+];
+
 impl Default for Options {
     fn default() -> Self {
         Self {
@@ -62,7 +319,81 @@ impl Default for Options {
             scroll_speed: ScrollSpeed::Medium,
             resolution: Resolution::Normal,
             no_music: false,
-            mono: false,
+            music_volume: MAX_VOLUME,
+            sfx_volume: MAX_VOLUME,
+            color_mode: ColorMode::Color,
+            tilt_sensitivity: TiltSensitivity::Normal,
+            hifps: false,
+            window_scale: WindowScale::Fit,
+            crt_filter: false,
+            reduce_flashing: false,
+            captions: false,
+            ball_style: BallStyle::Sprite,
+            ball_color: BallColor::White,
+            gamma: DEFAULT_GAMMA,
+            skip_intro_slides: false,
+            table_select: false,
+            attract_timeout: 120,
+            attract_demo: true,
+            ball_save: 0,
+            smooth_scroll: false,
+            keep_ball_in_view: false,
+            side_panel: false,
+            fullscreen: false,
+            cheat_menu: false,
+            ball_trail: false,
+            ball_trail_length: 4,
+            ball_trail_fade: 60,
+            aim_guide: false,
+            high_score_saving: HighScoreSaving::Immediate,
+            replay_score: 0,
+        }
+    }
+}
+
+impl ColorMode {
+    pub fn apply(self, pal: &mut [(u8, u8, u8)]) {
+        // How the deficiency sees RGB, in 1/256 units.
+        let sim: [[i32; 3]; 3] = match self {
+            ColorMode::Color => return,
+            ColorMode::Mono => {
+                for color in pal {
+                    let mono = ((color.0 as u16 + color.1 as u16 + color.2 as u16) / 3) as u8;
+                    *color = (mono, mono, mono);
+                }
+                return;
+            }
+            ColorMode::Deuteranopia => [[160, 96, 0], [179, 77, 0], [0, 77, 179]],
+            ColorMode::Protanopia => [[145, 111, 0], [143, 113, 0], [0, 62, 194]],
+        };
+        for color in pal {
+            let rgb = [color.0, color.1, color.2].map(i32::from);
+            let seen = sim.map(|row| (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]) >> 8);
+            // Shift the red/green difference the player can't see into green and blue.
+            let err = [rgb[0] - seen[0], rgb[1] - seen[1], rgb[2] - seen[2]];
+            let shift = (err[0] * 179) >> 8;
+            let fixed = [rgb[0], rgb[1] + shift + err[1], rgb[2] + shift + err[2]]
+                .map(|c| c.clamp(0, 0xff) as u8);
+            *color = (fixed[0], fixed[1], fixed[2]);
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            flipper_left: [Key::LShift, Key::LControl, Key::LAlt]
+                .into_iter()
+                .collect(),
+            flipper_right: [Key::RShift, Key::RControl, Key::RAlt]
+                .into_iter()
+                .collect(),
+            nudge: [Key::Space].into_iter().collect(),
+            nudge_left: [Key::Left].into_iter().collect(),
+            nudge_right: [Key::Right].into_iter().collect(),
+            plunger: [Key::Down].into_iter().collect(),
+            pause: [Key::P].into_iter().collect(),
+            quit: [Key::Escape].into_iter().collect(),
         }
     }
 }
@@ -71,38 +402,120 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             options: Default::default(),
+            table_options: BTreeMap::new(),
             high_scores: enum_map! {
-                TableId::Table1 => [
-                    HighScore { name: *b"TSP", score: Bcd::from_ascii(b"50000000") },
-                    HighScore { name: *b"ICE", score: Bcd::from_ascii(b"25000000") },
-                    HighScore { name: *b"ANY", score: Bcd::from_ascii(b"10000000") },
-                    HighScore { name: *b"J L", score: Bcd::from_ascii(b"5000000") },
+                TableId::Table1 => vec![
+                    HighScore::new(b"TSP", Bcd::from_ascii(b"50000000")),
+                    HighScore::new(b"ICE", Bcd::from_ascii(b"25000000")),
+                    HighScore::new(b"ANY", Bcd::from_ascii(b"10000000")),
+                    HighScore::new(b"J L", Bcd::from_ascii(b"5000000")),
                 ],
-                TableId::Table2 => [
-                    HighScore { name: *b"TSP", score: Bcd::from_ascii(b"100000000") },
-                    HighScore { name: *b"J L", score: Bcd::from_ascii(b"50000000") },
-                    HighScore { name: *b"ICE", score: Bcd::from_ascii(b"25000000") },
-                    HighScore { name: *b"ANY", score: Bcd::from_ascii(b"10000000") },
+                TableId::Table2 => vec![
+                    HighScore::new(b"TSP", Bcd::from_ascii(b"100000000")),
+                    HighScore::new(b"J L", Bcd::from_ascii(b"50000000")),
+                    HighScore::new(b"ICE", Bcd::from_ascii(b"25000000")),
+                    HighScore::new(b"ANY", Bcd::from_ascii(b"10000000")),
                 ],
-                TableId::Table3 => [
-                    HighScore { name: *b"TSP", score: Bcd::from_ascii(b"50000000") },
-                    HighScore { name: *b"ANY", score: Bcd::from_ascii(b"25000000") },
-                    HighScore { name: *b"J L", score: Bcd::from_ascii(b"10000000") },
-                    HighScore { name: *b"ICE", score: Bcd::from_ascii(b"5000000") },
+                TableId::Table3 => vec![
+                    HighScore::new(b"TSP", Bcd::from_ascii(b"50000000")),
+                    HighScore::new(b"ANY", Bcd::from_ascii(b"25000000")),
+                    HighScore::new(b"J L", Bcd::from_ascii(b"10000000")),
+                    HighScore::new(b"ICE", Bcd::from_ascii(b"5000000")),
 
                 ],
-                TableId::Table4 => [
-                    HighScore { name: *b"TSP", score: Bcd::from_ascii(b"100000000") },
-                    HighScore { name: *b"ICE", score: Bcd::from_ascii(b"50000000") },
-                    HighScore { name: *b"ANY", score: Bcd::from_ascii(b"25000000") },
-                    HighScore { name: *b"J L", score: Bcd::from_ascii(b"10000000") },
+                TableId::Table4 => vec![
+                    HighScore::new(b"TSP", Bcd::from_ascii(b"100000000")),
+                    HighScore::new(b"ICE", Bcd::from_ascii(b"50000000")),
+                    HighScore::new(b"ANY", Bcd::from_ascii(b"25000000")),
+                    HighScore::new(b"J L", Bcd::from_ascii(b"10000000")),
                 ],
             },
+            slides_dir: None,
+            dm_font_dir: None,
+            audio_device: None,
+            audio_latency: AudioLatency::Medium,
+            vsync: true,
+            fps_cap: None,
+            run_ahead: false,
+            language: Language::English,
+            music: BTreeMap::new(),
+            attract_lights: BTreeMap::new(),
+            tournament_high_scores: Default::default(),
+            key_bindings: Default::default(),
+            tournament: false,
+            no_audio: false,
+            ignore_table_options: false,
         }
     }
 }
 
 impl Config {
+    // Tournament games keep their own high score tables.
+    pub fn table_high_scores(&self, table: TableId) -> &Vec<HighScore> {
+        if self.tournament {
+            &self.tournament_high_scores[table]
+        } else {
+            &self.high_scores[table]
+        }
+    }
+
+    // Without a device the player still mixes in simulated time, so the
+    // jingle timing of the intro and the tables is unchanged.
+    pub fn audio_backend(&self) -> Backend {
+        if self.no_audio {
+            Backend::Null
+        } else {
+            Backend::default()
+        }
+    }
+
+    // The global options with the table's own settings applied.
+    pub fn options_for(&self, table: TableId) -> Options {
+        let mut options = self.options;
+        if self.ignore_table_options {
+            return options;
+        }
+        let table = self.table_options.get(&table).copied().unwrap_or_default();
+        if let Some(angle_high) = table.angle_high {
+            options.angle_high = angle_high;
+        }
+        if let Some(scroll_speed) = table.scroll_speed {
+            options.scroll_speed = scroll_speed;
+        }
+        if let Some(resolution) = table.resolution {
+            options.resolution = resolution;
+        }
+        options
+    }
+
+    // Stores options changed while playing a table: the settings the table
+    // keeps itself go to its own slot, the rest to the global options.  A
+    // resolution picked during play is always kept for the table alone.
+    pub fn set_options_for(&mut self, table: TableId, options: Options) {
+        let global = self.options;
+        let resolution_changed = options.resolution != self.options_for(table).resolution;
+        self.options = options;
+        if self.ignore_table_options {
+            return;
+        }
+        let table = self.table_options.entry(table).or_default();
+        if resolution_changed {
+            table.resolution = Some(options.resolution);
+        }
+        if table.angle_high.is_some() {
+            table.angle_high = Some(options.angle_high);
+            self.options.angle_high = global.angle_high;
+        }
+        if table.scroll_speed.is_some() {
+            table.scroll_speed = Some(options.scroll_speed);
+            self.options.scroll_speed = global.scroll_speed;
+        }
+        if table.resolution.is_some() {
+            table.resolution = Some(options.resolution);
+            self.options.resolution = global.resolution;
+        }
+    }
+
     pub fn load(data: impl AsRef<Path>) -> Config {
         let data = data.as_ref();
         let mut res = Config::default();
@@ -124,7 +537,11 @@ impl Config {
                     2 => Resolution::Full,
                     _ => Resolution::Normal,
                 };
-                res.options.mono = cfg[5] == 1;
+                res.options.color_mode = if cfg[5] == 1 {
+                    ColorMode::Mono
+                } else {
+                    ColorMode::Color
+                };
             }
         }
         for (table, file) in [
@@ -140,7 +557,7 @@ impl Config {
                         let entry = &hi[pos..pos + 0x10];
                         res.high_scores[table][i].score =
                             Bcd::from_bytes(*array_ref![entry, 0, 12]);
-                        res.high_scores[table][i].name = *array_ref![entry, 12, 3];
+                        res.high_scores[table][i].name = name_from_bytes(&entry[12..15]);
                     }
                 }
             }
@@ -149,14 +566,134 @@ impl Config {
     }
 }
 
+fn volume_gain(volume: u8) -> u32 {
+    0x100 * u32::from(volume.min(MAX_VOLUME)) / u32::from(MAX_VOLUME)
+}
+
+// How a menu changes a setting: one down, one up, or on to the next value and
+// around.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Step {
+    Down,
+    Up,
+    Cycle,
+}
+
 impl Options {
+    pub fn step_volume(&mut self, music: bool, step: Step) {
+        let volume = if music {
+            &mut self.music_volume
+        } else {
+            &mut self.sfx_volume
+        };
+        *volume = match step {
+            Step::Down => volume.saturating_sub(1),
+            Step::Up => (*volume + 1).min(MAX_VOLUME),
+            Step::Cycle if *volume >= MAX_VOLUME => 0,
+            Step::Cycle => *volume + 1,
+        };
+    }
+
+    pub fn step_balls(&mut self, step: Step) {
+        let balls = self.balls.clamp(MIN_BALLS, MAX_BALLS);
+        self.balls = match step {
+            Step::Down => (balls - 1).max(MIN_BALLS),
+            Step::Up => (balls + 1).min(MAX_BALLS),
+            Step::Cycle if balls == MAX_BALLS => MIN_BALLS,
+            Step::Cycle => balls + 1,
+        };
+    }
+
+    pub fn step_scroll_speed(&mut self, step: Step) {
+        let speed = self.scroll_speed.speed();
+        self.scroll_speed = match (step, self.scroll_speed) {
+            (Step::Down, _) => ScrollSpeed::Custom((speed - 1).max(MIN_SCROLL_SPEED)),
+            (Step::Up, _) => ScrollSpeed::Custom((speed + 1).min(MAX_SCROLL_SPEED)),
+            (_, ScrollSpeed::Hard) => ScrollSpeed::Medium,
+            (_, ScrollSpeed::Medium) => ScrollSpeed::Soft,
+            (_, ScrollSpeed::Soft) => ScrollSpeed::Custom(DEFAULT_CUSTOM_SCROLL_SPEED),
+            (_, ScrollSpeed::Custom(_)) => ScrollSpeed::Hard,
+        };
+    }
+
+    pub fn step_gamma(&mut self, step: Step) {
+        let gamma = self.gamma.clamp(MIN_GAMMA, MAX_GAMMA);
+        self.gamma = match step {
+            Step::Down => (gamma - 1).max(MIN_GAMMA),
+            Step::Up => (gamma + 1).min(MAX_GAMMA),
+            Step::Cycle if gamma == MAX_GAMMA => MIN_GAMMA,
+            Step::Cycle => gamma + 1,
+        };
+    }
+
+    pub fn step_replay_score(&mut self, step: Step) {
+        let score = self.replay_score.min(MAX_REPLAY_SCORE) / REPLAY_SCORE_STEP * REPLAY_SCORE_STEP;
+        self.replay_score = match step {
+            Step::Down => score.saturating_sub(REPLAY_SCORE_STEP),
+            Step::Up => (score + REPLAY_SCORE_STEP).min(MAX_REPLAY_SCORE),
+            Step::Cycle if score == MAX_REPLAY_SCORE => 0,
+            Step::Cycle => score + REPLAY_SCORE_STEP,
+        };
+    }
+
+    pub fn step_color_mode(&mut self) {
+        self.color_mode = match self.color_mode {
+            ColorMode::Color => ColorMode::Mono,
+            ColorMode::Mono => ColorMode::Deuteranopia,
+            ColorMode::Deuteranopia => ColorMode::Protanopia,
+            ColorMode::Protanopia => ColorMode::Color,
+        }
+    }
+
+    pub fn step_ball_style(&mut self) {
+        self.ball_style = match self.ball_style {
+            BallStyle::Sprite => BallStyle::Outline,
+            BallStyle::Outline => BallStyle::Solid,
+            BallStyle::Solid => BallStyle::Sprite,
+        }
+    }
+
+    pub fn step_ball_color(&mut self) {
+        self.ball_color = match self.ball_color {
+            BallColor::White => BallColor::Yellow,
+            BallColor::Yellow => BallColor::Cyan,
+            BallColor::Cyan => BallColor::Magenta,
+            BallColor::Magenta => BallColor::White,
+        }
+    }
+
+    pub fn music_gain(&self) -> u32 {
+        volume_gain(self.music_volume)
+    }
+
+    pub fn sfx_gain(&self) -> u32 {
+        volume_gain(self.sfx_volume)
+    }
+
+    pub fn apply_gamma(&self, pal: &mut [(u8, u8, u8)]) {
+        let gamma = self.gamma.clamp(MIN_GAMMA, MAX_GAMMA);
+        if gamma == DEFAULT_GAMMA {
+            return;
+        }
+        let exp = f32::from(DEFAULT_GAMMA) / f32::from(gamma);
+        let lut: [u8; 256] =
+            std::array::from_fn(|i| ((i as f32 / 255.0).powf(exp) * 255.0).round() as u8);
+        for color in pal {
+            *color = (
+                lut[usize::from(color.0)],
+                lut[usize::from(color.1)],
+                lut[usize::from(color.2)],
+            );
+        }
+    }
+
     pub fn save(&self, data: impl AsRef<Path>) {
         let raw: [u8; 6] = [
             if self.balls == 5 { 1 } else { 0 },
             if self.angle_high { 0 } else { 1 },
             match self.scroll_speed {
                 ScrollSpeed::Hard => 0,
-                ScrollSpeed::Medium => 1,
+                ScrollSpeed::Medium | ScrollSpeed::Custom(_) => 1,
                 ScrollSpeed::Soft => 2,
             },
             u8::from(self.no_music),
@@ -165,13 +702,13 @@ impl Options {
                 Resolution::High => 1,
                 Resolution::Full => 2,
             },
-            u8::from(self.mono),
+            u8::from(self.color_mode == ColorMode::Mono),
         ];
         let _ = std::fs::write(data.as_ref().join("PINBALL.CFG"), raw);
     }
 }
 
-pub fn save_high_scores(table: TableId, scores: [HighScore; 4], data: impl AsRef<Path>) {
+pub fn save_high_scores(table: TableId, scores: &[HighScore], data: impl AsRef<Path>) {
     let file = match table {
         TableId::Table1 => "TABLE1.HI",
         TableId::Table2 => "TABLE2.HI",
@@ -179,10 +716,88 @@ pub fn save_high_scores(table: TableId, scores: [HighScore; 4], data: impl AsRef
         TableId::Table4 => "TABLE4.HI",
     };
     let mut raw = vec![];
-    for score in scores {
+    for score in scores.iter().take(4) {
         raw.extend(score.score.digits);
-        raw.extend(score.name);
+        let mut name = [b' '; 3];
+        for (dst, &chr) in name.iter_mut().zip(&score.name) {
+            *dst = chr;
+        }
+        raw.extend(name);
         raw.push(0);
     }
+    raw.resize(0x40, 0);
     let _ = std::fs::write(data.as_ref().join(file), raw);
 }
+
+mod name_str {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::HighScoreName;
+
+    pub fn serialize<S: Serializer>(
+        name: &HighScoreName,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(name))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HighScoreName, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(super::name_from_bytes(s.as_bytes()))
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "pfr").map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+pub fn load_from_path(path: &Path) -> Config {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!(
+                "warning: cannot read {}: {e}, using defaults",
+                path.display()
+            );
+            return Config::default();
+        }
+    };
+    match toml::from_str::<Config>(&text) {
+        Ok(mut config) => {
+            for scores in config.high_scores.values_mut() {
+                scores.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+                scores.truncate(MAX_HIGH_SCORES);
+            }
+            config.fps_cap = config
+                .fps_cap
+                .filter(|&cap| cap != 0)
+                .map(|cap| cap.clamp(MIN_FPS_CAP, MAX_FPS_CAP));
+            config
+        }
+        Err(e) => {
+            eprintln!(
+                "warning: cannot parse {}: {e}, using defaults",
+                path.display()
+            );
+            Config::default()
+        }
+    }
+}
+
+pub fn save_to_path(config: &Config, path: &Path) {
+    let text = match toml::to_string(config) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("warning: cannot serialize config: {e}");
+            return;
+        }
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(e) = std::fs::write(path, text) {
+        eprintln!("warning: cannot write {}: {e}", path.display());
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use zip::ZipArchive;
+
+use crate::error::PfrError;
+
+// The sizes in an archive's headers aren't trusted beyond this; bigger entries
+// still load, the buffer just grows as they're read.
+const MAX_PREALLOC: u64 = 0x100_0000;
+
+// Where the files of the original game are read from.
+pub trait DataSource {
+    fn open(&self, name: &str) -> io::Result<Box<dyn Read + '_>>;
+    // size of a file, or None if it's absent
+    fn size(&self, name: &str) -> io::Result<Option<u64>>;
+    // where a file would be, for messages
+    fn path(&self, name: &str) -> PathBuf;
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, PfrError> {
+        let mut buf = vec![];
+        self.open(name)
+            .and_then(|mut f| f.read_to_end(&mut buf))
+            .map_err(|e| PfrError::from_io(&self.path(name), e))?;
+        Ok(buf)
+    }
+}
+
+// for sharing one source between views
+impl<T: DataSource + ?Sized> DataSource for Rc<T> {
+    fn open(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        (**self).open(name)
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        (**self).size(name)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        (**self).path(name)
+    }
+}
+
+pub struct DirSource {
+    dir: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirSource { dir: dir.into() }
+    }
+}
+
+impl DataSource for DirSource {
+    fn open(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(self.path(name))?))
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        match std::fs::metadata(self.path(name)) {
+            Ok(meta) => Ok(Some(meta.len())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+}
+
+pub struct ZipSource {
+    path: PathBuf,
+    archive: RefCell<ZipArchive<File>>,
+    // upper-case file name to its full name in the archive, so the game files
+    // can be in a subdirectory and in any case
+    names: HashMap<String, String>,
+}
+
+impl ZipSource {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, PfrError> {
+        let path = path.into();
+        let file = File::open(&path).map_err(|e| PfrError::from_io(&path, e))?;
+        let archive =
+            ZipArchive::new(file).map_err(|e| PfrError::BadAsset(path.clone(), e.to_string()))?;
+        let names = archive
+            .file_names()
+            .map(|full| {
+                let base = full.rsplit('/').next().unwrap_or(full);
+                (base.to_ascii_uppercase(), full.to_string())
+            })
+            .collect();
+        Ok(ZipSource {
+            path,
+            archive: RefCell::new(archive),
+            names,
+        })
+    }
+
+    fn entry_name(&self, name: &str) -> io::Result<&str> {
+        self.names
+            .get(&name.to_ascii_uppercase())
+            .map(String::as_str)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in the archive"))
+    }
+}
+
+impl DataSource for ZipSource {
+    fn open(&self, name: &str) -> io::Result<Box<dyn Read + '_>> {
+        let entry = self.entry_name(name)?;
+        let mut archive = self.archive.borrow_mut();
+        let mut file = archive.by_name(entry)?;
+        let mut buf = Vec::with_capacity(file.size().min(MAX_PREALLOC) as usize);
+        file.read_to_end(&mut buf)?;
+        Ok(Box::new(Cursor::new(buf)))
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        let Ok(entry) = self.entry_name(name) else {
+            return Ok(None);
+        };
+        Ok(Some(self.archive.borrow_mut().by_name(entry)?.size()))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+// A directory, or a zip archive of the game files.
+pub fn open_data_source(path: &Path) -> Result<Box<dyn DataSource>, PfrError> {
+    if path.is_dir() {
+        Ok(Box::new(DirSource::new(path)))
+    } else if path.is_file() {
+        Ok(Box::new(ZipSource::open(path)?))
+    } else {
+        Err(PfrError::MissingDataDir(path.to_path_buf()))
+    }
+}
@@ -0,0 +1,58 @@
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+
+use crate::{config::Options, view::PadButton};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Demo {
+    pub seed: u64,
+    pub options: Options,
+    pub events: Vec<DemoEvent>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct DemoEvent {
+    pub frame: u64,
+    pub input: DemoInput,
+    pub state: ElementState,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DemoInput {
+    Key(VirtualKeyCode),
+    Button(PadButton),
+    Mouse((u32, u32), MouseButton),
+    // the window lost focus, so held keys were let go without key-up events
+    FocusLost,
+}
+
+impl Demo {
+    pub fn new(seed: u64, options: Options) -> Self {
+        Demo {
+            seed,
+            options,
+            events: vec![],
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Demo> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text =
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+
+    pub fn events_at(&self, frame: u64) -> impl Iterator<Item = &DemoEvent> {
+        let start = self.events.partition_point(|event| event.frame < frame);
+        self.events[start..]
+            .iter()
+            .take_while(move |event| event.frame == frame)
+    }
+}
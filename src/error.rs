@@ -0,0 +1,79 @@
+use std::{
+    fmt::{self, Display},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::assets::table::sound::JingleBind;
+
+#[derive(Debug)]
+pub enum PfrError {
+    MissingDataDir(PathBuf),
+    MissingFile(PathBuf),
+    Io(PathBuf, io::Error),
+    BadAsset(PathBuf, String),
+    UnsupportedImageDimensions {
+        path: PathBuf,
+        width: usize,
+        height: usize,
+    },
+    MissingJingleBind(JingleBind),
+}
+
+impl PfrError {
+    pub fn from_io(path: &Path, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => PfrError::MissingFile(path.to_path_buf()),
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                PfrError::BadAsset(path.to_path_buf(), err.to_string())
+            }
+            _ => PfrError::Io(path.to_path_buf(), err),
+        }
+    }
+}
+
+impl Display for PfrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PfrError::MissingDataDir(path) => {
+                write!(f, "data directory {} does not exist", path.display())
+            }
+            PfrError::MissingFile(path) => write!(
+                f,
+                "{} is missing; copy it from the original game into the data directory",
+                path.display()
+            ),
+            PfrError::Io(path, err) => write!(f, "cannot read {}: {err}", path.display()),
+            PfrError::BadAsset(path, msg) => write!(
+                f,
+                "{} is damaged or from an unsupported version of the game: {msg}",
+                path.display()
+            ),
+            PfrError::UnsupportedImageDimensions {
+                path,
+                width,
+                height,
+            } => write!(
+                f,
+                "{} is {width}x{height}, images must be 320x240 or 640x480",
+                path.display()
+            ),
+            PfrError::MissingJingleBind(bind) => {
+                write!(f, "table data is missing the {bind:?} jingle")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PfrError {}
+
+impl From<PfrError> for io::Error {
+    fn from(err: PfrError) -> Self {
+        let kind = match err {
+            PfrError::MissingDataDir(_) | PfrError::MissingFile(_) => io::ErrorKind::NotFound,
+            PfrError::Io(_, ref err) => err.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
@@ -0,0 +1,345 @@
+// The game as a library: the current view behind one struct that is ticked,
+// fed input and rendered by whatever hosts it.  Window system types stay out
+// of this interface; the host translates its events into Input.
+
+use std::{io, sync::mpsc::Sender};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+
+use crate::{
+    attract::AttractDemo,
+    config::{cleared_high_scores, Config, HighScore, Resolution, TableId, TABLES},
+    data::DataSource,
+    error::PfrError,
+    intro::Intro,
+    palette::expand_naive,
+    spectate::{Snapshot, Spectator},
+    table::{GameEvent, Table},
+    view::{Action, GameStatus, PadButton, PanelLayout, Route, View},
+};
+
+macro_rules! keys {
+    ($($key:ident,)*) => {
+        // The keys the game responds to, named as in winit.
+        #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+        pub enum Key {
+            $($key,)*
+        }
+
+        impl Key {
+            pub(crate) fn to_winit(self) -> VirtualKeyCode {
+                match self {
+                    $(Key::$key => VirtualKeyCode::$key,)*
+                }
+            }
+        }
+    };
+}
+
+keys! {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Up, Down, Left, Right,
+    Return, Escape, Space, Back, Tab,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Comma, Period, Slash, Semicolon, Apostrophe, LBracket, RBracket, Backslash,
+    Minus, Equals, Plus, Grave,
+    NumpadAdd, NumpadSubtract,
+    Insert, Delete, Home, End, PageUp, PageDown,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PointerButton {
+    Left,
+    Right,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Input {
+    Key(Key),
+    Button(PadButton),
+    // pos is in pixels of the rendered frame
+    Pointer((u32, u32), PointerButton),
+    // held inputs were let go without release events, as when the host
+    // window loses focus; pressed is ignored
+    FocusLost,
+}
+
+fn open_view(
+    data: &dyn DataSource,
+    config: &Config,
+    route: Route,
+    seed: u64,
+    events: Option<&Sender<GameEvent>>,
+) -> Result<Box<dyn View>, PfrError> {
+    Ok(match route {
+        Route::Intro(table) => Box::new(Intro::new(data, config.clone(), table)?),
+        Route::TableSelect(table) => Box::new(Intro::table_select(data, config.clone(), table)?),
+        Route::Table(table) => {
+            let table = Table::new(data, config.clone(), table, seed)?;
+            Box::new(with_events(table, events))
+        }
+        Route::AttractDemo(table) => Box::new(AttractDemo::new(data, config.clone(), table)?),
+    })
+}
+
+fn with_events(table: Table, events: Option<&Sender<GameEvent>>) -> Table {
+    match events {
+        Some(events) => table.with_events(events.clone()),
+        None => table,
+    }
+}
+
+pub struct Game {
+    data: Box<dyn DataSource>,
+    config: Config,
+    seed: u64,
+    view: Box<dyn View>,
+    table: Option<TableId>,
+    // handed to every table opened from now on
+    events: Option<Sender<GameEvent>>,
+    // demos have to replay exactly, so the ball can't be rewound in one
+    in_demo: bool,
+    // the table whose held back high scores were last moved into config()
+    stored_high_scores: Option<TableId>,
+}
+
+impl Game {
+    pub fn new(
+        data: Box<dyn DataSource>,
+        config: Config,
+        route: Route,
+        seed: u64,
+    ) -> Result<Self, PfrError> {
+        let view = open_view(&*data, &config, route, seed, None)?;
+        Ok(Game {
+            data,
+            config,
+            seed,
+            view,
+            table: route_table(route),
+            events: None,
+            in_demo: false,
+            stored_high_scores: None,
+        })
+    }
+
+    // Follows a game played elsewhere until it ends or the quit key is
+    // pressed.
+    pub fn spectate(data: Box<dyn DataSource>, config: Config, spectator: Spectator) -> Self {
+        Game {
+            data,
+            config,
+            seed: 0,
+            view: Box::new(spectator),
+            table: None,
+            events: None,
+            in_demo: false,
+            stored_high_scores: None,
+        }
+    }
+
+    pub fn set_event_sender(&mut self, sender: Sender<GameEvent>) {
+        self.events = Some(sender);
+    }
+
+    pub fn set_in_demo(&mut self, in_demo: bool) {
+        self.in_demo = in_demo;
+    }
+
+    // Runs one frame at fps() frames per second.  Navigation is followed
+    // here, and settings and high scores are kept in config(); the action is
+    // returned so that the host can save them, resize its output for a new
+    // view or quit.
+    pub fn tick(&mut self) -> Result<Action, PfrError> {
+        let action = self.view.run_frame();
+        match action {
+            Action::None | Action::Exit => {}
+            Action::Navigate(route) => {
+                self.store_unsaved_high_scores();
+                self.view = open_view(
+                    &*self.data,
+                    &self.config,
+                    route,
+                    self.seed,
+                    self.events.as_ref(),
+                )?;
+                self.table = route_table(route);
+            }
+            Action::SaveOptions(options) => match self.table {
+                Some(table) => self.config.set_options_for(table, options),
+                None => self.config.options = options,
+            },
+            Action::SaveHighScores(table, ref high_scores) => {
+                self.store_high_scores(table, high_scores.clone());
+            }
+            Action::ResetHighScores => {
+                for table in TABLES {
+                    self.store_high_scores(table, cleared_high_scores());
+                }
+            }
+            Action::RewindBall(ref state) => {
+                if self.in_demo {
+                    eprintln!("rewinding is not available while recording or playing a demo");
+                } else {
+                    match Table::load_state(&*self.data, self.config.clone(), state) {
+                        Ok(mut table) => {
+                            table.set_ball_snapshot(state.clone());
+                            self.view = Box::new(with_events(table, self.events.as_ref()));
+                        }
+                        Err(e) => eprintln!("failed to rewind the ball: {e}"),
+                    }
+                }
+            }
+        }
+        Ok(action)
+    }
+
+    pub fn input(&mut self, input: Input, pressed: bool) {
+        let state = if pressed {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        };
+        match input {
+            Input::Key(key) => self.view.handle_key(key.to_winit(), state),
+            Input::Button(button) => self.view.handle_button(button, state),
+            Input::Pointer(pos, button) => {
+                let (mouse_button, flipper) = match button {
+                    PointerButton::Left => {
+                        (MouseButton::Left, &self.config.key_bindings.flipper_left)
+                    }
+                    PointerButton::Right => {
+                        (MouseButton::Right, &self.config.key_bindings.flipper_right)
+                    }
+                };
+                let flipper = flipper.first().copied();
+                if !self.view.handle_mouse(pos, mouse_button, state) {
+                    if let Some(key) = flipper {
+                        self.view.handle_key(key.to_winit(), state);
+                    }
+                }
+            }
+            Input::FocusLost => self.view.on_focus_lost(),
+        }
+    }
+
+    // Replaces the view with a table saved by save_state().
+    pub fn load_state(&mut self, state: &[u8]) -> io::Result<()> {
+        let table = Table::load_state(&*self.data, self.config.clone(), state)?;
+        self.store_unsaved_high_scores();
+        self.table = Some(table.table_id());
+        self.view = Box::new(with_events(table, self.events.as_ref()));
+        Ok(())
+    }
+
+    // Moves high scores the table holds back until it's left into config(),
+    // as before quitting.
+    pub fn store_unsaved_high_scores(&mut self) {
+        if let Some((table, high_scores)) = self.view.take_unsaved_high_scores() {
+            self.store_high_scores(table, high_scores);
+            self.stored_high_scores = Some(table);
+        }
+    }
+
+    // The table whose high scores were stored on leaving it since the last
+    // call, for the host to write out.
+    pub fn take_stored_high_scores(&mut self) -> Option<TableId> {
+        self.stored_high_scores.take()
+    }
+
+    pub fn fps(&self) -> u32 {
+        self.view.get_fps()
+    }
+
+    // of the frame, without the side panel
+    pub fn resolution(&self) -> (u32, u32) {
+        self.view.get_resolution()
+    }
+
+    // false if the view can't switch on the fly
+    pub fn set_resolution(&mut self, resolution: Resolution) -> bool {
+        self.view.set_resolution(resolution)
+    }
+
+    pub fn panel(&self) -> Option<PanelLayout> {
+        self.view.get_extra_panel()
+    }
+
+    // data takes one palette index per pixel, resolution() wide and high
+    pub fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.view.render(data, pal);
+    }
+
+    // the frame after the current one, for run-ahead
+    pub fn render_ahead(&mut self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.view.render_ahead(data, pal);
+    }
+
+    pub fn render_frame_stats(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)], text: &[u8]) {
+        self.view.render_frame_stats(data, pal, text);
+    }
+
+    // panel().width wide and as high as the frame
+    pub fn render_panel(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.view.render_panel(data, pal);
+    }
+
+    pub fn scroll_blend(&self) -> Option<(usize, u8)> {
+        self.view.scroll_blend()
+    }
+
+    // four bytes per pixel, red, green, blue and 0xff
+    pub fn render_rgba(&self, rgba: &mut [u8]) {
+        let (width, height) = self.resolution();
+        let mut data = vec![0; width as usize * height as usize];
+        let mut pal = [(0, 0, 0); 256];
+        self.view.render(&mut data, &mut pal);
+        expand_naive(&pal, &data, rgba);
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn table(&self) -> Option<TableId> {
+        self.table
+    }
+
+    pub fn status(&self) -> Option<GameStatus> {
+        self.view.game_status()
+    }
+
+    pub fn display_text(&self) -> Vec<String> {
+        self.view.display_text()
+    }
+
+    pub fn save_state(&self) -> Option<Vec<u8>> {
+        self.view.save_state()
+    }
+
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        self.view.snapshot()
+    }
+
+    fn store_high_scores(&mut self, table: TableId, high_scores: Vec<HighScore>) {
+        if self.config.tournament {
+            self.config.tournament_high_scores[table] = high_scores;
+        } else {
+            self.config.high_scores[table] = high_scores;
+        }
+    }
+}
+
+fn route_table(route: Route) -> Option<TableId> {
+    match route {
+        Route::Table(table) => Some(table),
+        Route::Intro(_) | Route::TableSelect(_) | Route::AttractDemo(_) => None,
+    }
+}
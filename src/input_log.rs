@@ -0,0 +1,115 @@
+// A plain text log of every input and a periodic snapshot of the game state,
+// to attach to bug reports.  Unlike a demo it isn't meant to be replayed.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use winit::event::ElementState;
+
+use crate::{demo::DemoInput, view::GameStatus};
+
+// Once the log grows past this, it's renamed to <path>.1 (replacing the
+// previous one) and a new log is started.
+const MAX_LOG_BYTES: u64 = 1 << 20;
+const FLUSH_FRAMES: u64 = 60;
+const SNAPSHOT_FRAMES: u64 = 300;
+
+pub struct InputLog {
+    path: PathBuf,
+    file: BufWriter<File>,
+    written: u64,
+    start: Instant,
+    last_flush: u64,
+    last_snapshot: Option<u64>,
+}
+
+impl InputLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(InputLog {
+            path: path.to_path_buf(),
+            file: BufWriter::new(File::create(path)?),
+            written: 0,
+            start: Instant::now(),
+            last_flush: 0,
+            last_snapshot: None,
+        })
+    }
+
+    pub fn input(&mut self, frame: u64, input: DemoInput, state: ElementState) {
+        let state = match state {
+            ElementState::Pressed => "pressed",
+            ElementState::Released => "released",
+        };
+        let event = match input {
+            DemoInput::Key(key) => format!("key {key:?} {state}"),
+            DemoInput::Button(button) => format!("button {button:?} {state}"),
+            DemoInput::Mouse((x, y), button) => format!("mouse {button:?} {state} at {x},{y}"),
+            DemoInput::FocusLost => "focus lost".to_string(),
+        };
+        self.line(frame, &event);
+    }
+
+    // Called once per frame; writes a snapshot every few seconds and flushes
+    // the buffered lines every second.
+    pub fn tick(&mut self, frame: u64, status: Option<GameStatus>) {
+        if self
+            .last_snapshot
+            .is_none_or(|last| frame >= last + SNAPSHOT_FRAMES)
+        {
+            self.last_snapshot = Some(frame);
+            if let Some(status) = status {
+                let text = format!(
+                    "status score {} player {}/{} ball {}/{} tilted {} in_mode {} attract {}",
+                    status.score,
+                    status.cur_player,
+                    status.total_players,
+                    status.cur_ball,
+                    status.total_balls,
+                    status.tilted,
+                    status.in_mode,
+                    status.in_attract
+                );
+                self.line(frame, &text);
+            }
+        }
+        if frame >= self.last_flush + FLUSH_FRAMES {
+            self.last_flush = frame;
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.file.flush() {
+            eprintln!("cannot write input log {}: {e}", self.path.display());
+        }
+    }
+
+    fn line(&mut self, frame: u64, text: &str) {
+        let ms = self.start.elapsed().as_millis();
+        let line = format!("{}.{:03} frame {frame} {text}\n", ms / 1000, ms % 1000);
+        if let Err(e) = self.file.write_all(line.as_bytes()) {
+            eprintln!("cannot write input log {}: {e}", self.path.display());
+            return;
+        }
+        self.written += line.len() as u64;
+        if self.written >= MAX_LOG_BYTES {
+            if let Err(e) = self.rotate() {
+                eprintln!("cannot rotate input log {}: {e}", self.path.display());
+            }
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let mut old = self.path.clone().into_os_string();
+        old.push(".1");
+        std::fs::rename(&self.path, old)?;
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.written = 0;
+        Ok(())
+    }
+}
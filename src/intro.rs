@@ -1,4 +1,4 @@
-use std::{fs::File, path::Path};
+use std::io::Cursor;
 
 use unnamed_entity::EntityId;
 use winit::event::{ElementState, VirtualKeyCode};
@@ -8,9 +8,14 @@ use crate::{
         iff::Image,
         intro::{Assets, SlideId, TableSet, TextPageId, CGA_FONT},
     },
-    config::{Config, Resolution, ScrollSpeed, TableId},
+    config::{
+        cleared_high_scores, ColorMode, Config, Resolution, ScrollSpeed, Step, TableId,
+        WindowScale, MAX_BALLS, MAX_GAMMA, MAX_REPLAY_SCORE, MIN_BALLS, MIN_GAMMA,
+    },
+    data::{DataSource, DirSource},
+    error::PfrError,
     sound::player::Player,
-    view::{Action, Route, View},
+    view::{Action, PadButton, Route, View},
 };
 
 pub struct Intro {
@@ -19,9 +24,14 @@ pub struct Intro {
     config: Config,
     state: State,
     text_page: TextPageId,
+    hiscore_page: usize,
     key: KeyPress,
     left_state: LeftState,
     left_is_options: bool,
+    // Some(yes) while asking whether to reset the high scores
+    reset_confirm: Option<bool>,
+    // where the attract demo plays next, taking turns after the last one
+    demo_table: TableId,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -30,6 +40,7 @@ enum IntroAction {
     SkipToText,
     Options,
     Table(TableId),
+    Demo,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -42,9 +53,27 @@ enum KeyPress {
     Escape,
     Up,
     Down,
+    Left,
+    Right,
 }
 
-#[derive(Copy, Clone, Debug)]
+const OPTION_RESET: u8 = 13;
+const OPTION_SAVE: u8 = 14;
+const OPTIONS_LINE_PITCH: usize = 14;
+
+const SELECT_TABLES: [TableId; 4] = [
+    TableId::Table1,
+    TableId::Table2,
+    TableId::Table3,
+    TableId::Table4,
+];
+const SELECT_TOP: usize = 50;
+const SELECT_LINE_PITCH: usize = 24;
+
+const HISCORES_PER_PAGE: usize = 4;
+const HISCORE_PAGE_FRAMES: u16 = 140;
+
+#[derive(Clone, Debug)]
 enum State {
     Slide(SlideId, SlideState),
     InitDelay(u8),
@@ -63,6 +92,8 @@ enum State {
     Options(u8),
     OptionsFadeOut(u8),
     FadeOut(u8, Action),
+    Select(u8),
+    SelectFadeOut(u8, Action),
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -84,37 +115,80 @@ enum LeftState {
 }
 
 impl Intro {
-    pub fn new(data: &Path, config: Config, table: Option<TableId>) -> Intro {
-        let mut f = File::open(data.join(if table.is_none() {
-            "INTRO.MOD"
-        } else {
-            "MOD2.MOD"
-        }))
-        .unwrap();
-        let module = crate::sound::loader::load(&mut f).unwrap();
-        let player = crate::sound::player::play(module, None);
+    pub fn new(
+        data: &dyn DataSource,
+        config: Config,
+        table: Option<TableId>,
+    ) -> Result<Intro, PfrError> {
+        let skip_slides = table.is_some() || config.options.skip_intro_slides;
+        let module_name = if skip_slides { "MOD2.MOD" } else { "INTRO.MOD" };
+        let module = crate::sound::loader::load(&mut Cursor::new(data.read(module_name)?))
+            .map_err(|e| PfrError::from_io(&data.path(module_name), e))?;
+        let player = crate::sound::player::play_with_backend(
+            module,
+            None,
+            config.audio_backend(),
+            config.audio_device.as_deref(),
+            config.audio_latency.buffer_ms(),
+        );
+        player.set_music_volume(config.options.music_gain());
+        // coming back from a table, resume on the showcase that has it
         let (state, text_page) = match table {
             Some(TableId::Table1 | TableId::Table2) => {
-                (State::InitDelay(0), TextPageId::from_idx(0))
+                (State::TablesGap(0), TextPageId::from_idx(0))
             }
             Some(TableId::Table3 | TableId::Table4) => {
-                (State::InitDelay(0), TextPageId::from_idx(1))
+                (State::TablesGap(0), TextPageId::from_idx(1))
             }
+            None if skip_slides => (State::InitDelay(0), TextPageId::from_idx(0)),
             None => (
                 State::Slide(SlideId::from_idx(0), SlideState::Gap(0)),
                 TextPageId::from_idx(0),
             ),
         };
-        Intro {
+        let left_state = if table.is_some() {
+            LeftState::Image(0)
+        } else {
+            LeftState::None
+        };
+        let mut assets = Assets::load(data, "INTRO.PRG")?;
+        if let Some(ref dir) = config.slides_dir {
+            if let Err(e) = assets.load_extra_slides(&DirSource::new(dir)) {
+                eprintln!("warning: cannot load slides from {}: {e}", dir.display());
+            }
+        }
+        Ok(Intro {
             player,
-            assets: Assets::load(data.join("INTRO.PRG")).unwrap(),
+            assets,
             config,
             state,
             text_page,
+            hiscore_page: 0,
             key: KeyPress::None,
-            left_state: LeftState::None,
+            left_state,
             left_is_options: false,
-        }
+            reset_confirm: None,
+            demo_table: match table {
+                Some(TableId::Table1) => TableId::Table2,
+                Some(TableId::Table2) => TableId::Table3,
+                Some(TableId::Table3) => TableId::Table4,
+                Some(TableId::Table4) | None => TableId::Table1,
+            },
+        })
+    }
+
+    // The one-screen table picker shown instead of the showcase when
+    // table_select is set.
+    pub fn table_select(
+        data: &dyn DataSource,
+        config: Config,
+        table: Option<TableId>,
+    ) -> Result<Intro, PfrError> {
+        let table = table.unwrap_or(TableId::Table1);
+        let mut intro = Intro::new(data, config, Some(table))?;
+        let cursor = SELECT_TABLES.iter().position(|&t| t == table).unwrap_or(0);
+        intro.state = State::Select(cursor as u8);
+        Ok(intro)
     }
 
     fn clear_left(&self, data: &mut [u8], num: usize) {
@@ -128,10 +202,15 @@ impl Intro {
     }
 
     fn render_left_text(&self, data: &mut [u8], num: usize, is_options: bool) {
-        let text = if is_options {
-            &self.assets.left_text_options
+        let strings = self.config.language.strings();
+        let (text, exe_text) = if is_options {
+            (strings.left_text_options, &self.assets.left_text_options)
         } else {
-            &self.assets.left_text_menu
+            (strings.left_text_menu, &self.assets.left_text_menu)
+        };
+        let text: Vec<&[u8]> = match text {
+            Some(lines) => lines.iter().map(|line| line.as_bytes()).collect(),
+            None => exe_text.iter().map(Vec::as_slice).collect(),
         };
         for (ty, line) in text.iter().enumerate() {
             for (tx, &chr) in line.iter().enumerate() {
@@ -223,17 +302,23 @@ impl Intro {
     }
 
     fn render_char(&self, data: &mut [u8], font: &Image, chr: u8, x: usize, y: usize) {
+        let chr = chr.to_ascii_uppercase();
         let fidx = match chr {
+            b' ' => return,
             b'0'..=b'9' => chr - b'0',
             b'A'..=b'Z' => chr - b'A' + 10,
             b'.' => 36,
             b':' => 37,
             b'-' => 38,
             b'>' => 39,
-            _ => return,
+            _ => {
+                if let Some(mask) = extra_glyph(chr) {
+                    self.render_extra_char(data, font, mask, x, y);
+                }
+                return;
+            }
         } as usize;
-        let fx = fidx % 20 * 32;
-        let fy = fidx / 20 * 14;
+        let (fx, fy) = glyph_pos(fidx);
         for cy in 0..14 {
             for cx in 0..18 {
                 let pidx = (y + cy) * 2 * 640 + x + cx;
@@ -244,6 +329,38 @@ impl Intro {
         }
     }
 
+    // Characters the fonts have no glyph for are drawn from a coarse mask at
+    // double size, shaded row by row like the I.
+    fn render_extra_char(&self, data: &mut [u8], font: &Image, mask: [u16; 7], x: usize, y: usize) {
+        let (fx, fy) = glyph_pos(usize::from(b'I' - b'A') + 10);
+        let mut shades = [None; 14];
+        for (cy, shade) in shades.iter_mut().enumerate() {
+            *shade = (0..18)
+                .map(|cx| font.data[(fx + cx, fy + cy)])
+                .find(|&pix| pix != 0);
+        }
+        for cy in 0..14 {
+            // rows the I doesn't reach take the shade of the nearest one
+            let shade = shades[cy..]
+                .iter()
+                .chain(shades[..cy].iter().rev())
+                .flatten()
+                .copied()
+                .next()
+                .unwrap_or(0xf);
+            for cx in 0..18 {
+                let pix = if mask[cy / 2] & 0x100 >> (cx / 2) != 0 {
+                    shade
+                } else {
+                    0
+                };
+                let pidx = (y + cy) * 2 * 640 + x + cx;
+                data[pidx] = pix | 0x10;
+                data[pidx + 640] = pix | 0x10;
+            }
+        }
+    }
+
     fn render_line(&self, data: &mut [u8], font: &Image, line: &[u8], y: usize) {
         let sx = 164 + (24 - line.len()) * 9;
         for (tx, &chr) in line.iter().enumerate() {
@@ -252,21 +369,33 @@ impl Intro {
     }
 
     fn render_hiscores(&self, data: &mut [u8], font: &Image, table: TableId, y: usize) {
-        let name = match table {
-            TableId::Table1 => b"     PARTY LAND         ",
-            TableId::Table2 => b"     SPEED DEVILS       ",
-            TableId::Table3 => b"     BILLION DOLLAR     ",
-            TableId::Table4 => b"     STONES N BONES     ",
-        };
-        self.render_line(data, font, name, y);
-        for (i, score) in self.config.high_scores[table].iter().enumerate() {
+        let mut name = [b' '; 24];
+        let table_name = table_name(table);
+        name[5..5 + table_name.len()].copy_from_slice(table_name);
+        self.render_line(data, font, &name, y);
+        let scores = self.config.table_high_scores(table);
+        let pages = scores.len().div_ceil(HISCORES_PER_PAGE).max(1);
+        let first = self.hiscore_page % pages * HISCORES_PER_PAGE;
+        for (i, score) in scores
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(HISCORES_PER_PAGE)
+        {
             let mut line = [b' '; 24];
-            line[2] = b'1' + (i as u8);
+            let rank = i as u8 + 1;
+            if rank >= 10 {
+                line[1] = b'0' + rank / 10;
+            }
+            line[2] = b'0' + rank % 10;
             line[3] = b'.';
-            line[5..8].copy_from_slice(&score.name);
-            line[9] = b'-';
-            line[11..23].copy_from_slice(&score.score.to_ascii());
-            self.render_line(data, font, &line, y + (i + 1) * 18);
+            let digits = score.score.to_ascii();
+            let score_start = 11 + digits.iter().take_while(|&&chr| chr == b' ').count();
+            line[score_start..23].copy_from_slice(&digits[score_start - 11..]);
+            let name_len = score.name.len().min(score_start - 8);
+            line[5..5 + name_len].copy_from_slice(&score.name[..name_len]);
+            line[6 + name_len] = b'-';
+            self.render_line(data, font, &line, y + (i - first + 1) * 18);
         }
     }
 
@@ -320,58 +449,147 @@ impl Intro {
             &self.assets.font_hq
         };
         pal[0x10..0x20].copy_from_slice(&font.cmap);
-        let mut lines = [
-            b"OPTIONS MENU".to_vec(),
-            vec![],
-            b"  BALLS:                ".to_vec(),
-            b"  ANGLE:                ".to_vec(),
-            b"  SCROLLING:            ".to_vec(),
-            b"  INGAME MUSIC:         ".to_vec(),
-            b"  RESOLUTION:           ".to_vec(),
-            b"  COLOR MODE:           ".to_vec(),
-            vec![],
-            b"  SAVE AND EXIT         ".to_vec(),
-        ];
-
-        lines[2][16] = b'0' + self.config.options.balls;
-
-        if self.config.options.angle_high {
-            lines[3][16..20].copy_from_slice(b"HIGH");
-        } else {
-            lines[3][16..19].copy_from_slice(b"LOW");
+        let strings = self.config.language.strings();
+        let mut lines = [[b' '; 24]; 15];
+        for (line, label) in lines.iter_mut().zip(strings.options) {
+            put(line, 2, label);
         }
+        let options = &self.config.options;
+        let on_off = |on: bool| if on { strings.on } else { strings.off };
+
+        lines[0][16] = b'0' + options.balls.clamp(MIN_BALLS, MAX_BALLS);
+
+        put(
+            &mut lines[1],
+            16,
+            if options.angle_high {
+                strings.high
+            } else {
+                strings.low
+            },
+        );
 
-        match self.config.options.scroll_speed {
-            ScrollSpeed::Hard => lines[4][16..20].copy_from_slice(b"HARD"),
-            ScrollSpeed::Medium => lines[4][16..22].copy_from_slice(b"MEDIUM"),
-            ScrollSpeed::Soft => lines[4][16..20].copy_from_slice(b"SOFT"),
+        match options.scroll_speed {
+            ScrollSpeed::Hard => put(&mut lines[2], 16, strings.hard),
+            ScrollSpeed::Medium => put(&mut lines[2], 16, strings.medium),
+            ScrollSpeed::Soft => put(&mut lines[2], 16, strings.soft),
+            ScrollSpeed::Custom(_) => {
+                put(&mut lines[2], 16, strings.speed);
+                let x = 17 + strings.speed.len();
+                put_num(&mut lines[2], x, options.scroll_speed.speed().into());
+            }
         }
 
-        if self.config.options.no_music {
-            lines[5][16..19].copy_from_slice(b"OFF");
-        } else {
-            lines[5][16..18].copy_from_slice(b"ON");
+        put(&mut lines[3], 16, on_off(!options.no_music));
+
+        put_num(&mut lines[4], 16, options.music_volume.into());
+        put_num(&mut lines[5], 16, options.sfx_volume.into());
+
+        let resolution = match options.resolution {
+            Resolution::Normal => strings.normal,
+            Resolution::High => strings.high,
+            Resolution::Full => strings.full,
+        };
+        put(&mut lines[6], 16, resolution);
+
+        let color_mode = match options.color_mode {
+            ColorMode::Color => strings.color,
+            ColorMode::Mono => strings.mono,
+            ColorMode::Deuteranopia => "DEUTAN",
+            ColorMode::Protanopia => "PROTAN",
+        };
+        put(&mut lines[7], 16, color_mode);
+
+        let window = match options.window_scale {
+            WindowScale::X1 => "1X",
+            WindowScale::X2 => "2X",
+            WindowScale::X3 => "3X",
+            WindowScale::Fit => strings.fit,
+        };
+        put(&mut lines[8], 16, window);
+
+        put(&mut lines[9], 16, on_off(options.crt_filter));
+
+        let gamma = options.gamma.clamp(MIN_GAMMA, MAX_GAMMA);
+        lines[10][16..19].copy_from_slice(&[b'0' + gamma / 10, b'.', b'0' + gamma % 10]);
+
+        put(
+            &mut lines[11],
+            16,
+            if options.reduce_flashing {
+                strings.reduced
+            } else {
+                strings.normal
+            },
+        );
+
+        match options.replay_score {
+            0 => put(&mut lines[12], 16, strings.off),
+            score if score >= 1_000_000 => {
+                let x = put_num(&mut lines[12], 16, score.min(MAX_REPLAY_SCORE) / 1_000_000);
+                lines[12][x] = b'M';
+            }
+            score => {
+                put_num(&mut lines[12], 16, score);
+            }
         }
 
-        match self.config.options.resolution {
-            Resolution::Normal => lines[6][16..22].copy_from_slice(b"NORMAL"),
-            Resolution::High => lines[6][16..20].copy_from_slice(b"HIGH"),
-            Resolution::Full => lines[6][16..20].copy_from_slice(b"FULL"),
+        if let Some(yes) = self.reset_confirm {
+            let x = 2 + strings.options[13].len();
+            put(&mut lines[13], x, ": ");
+            put(
+                &mut lines[13],
+                x + 2,
+                if yes { strings.yes } else { strings.no },
+            );
         }
 
-        if self.config.options.mono {
-            lines[7][16..20].copy_from_slice(b"MONO");
-        } else {
-            lines[7][16..21].copy_from_slice(b"COLOR");
+        self.render_line(data, font, strings.options_title.as_bytes(), 14);
+        for (ty, line) in lines.iter().enumerate() {
+            self.render_line(data, font, line, 14 + (ty + 1) * OPTIONS_LINE_PITCH);
         }
 
-        for (ty, line) in lines.into_iter().enumerate() {
-            self.render_line(data, font, &line, 14 + ty * 18);
+        if let Some(cursor) = cursor {
+            self.render_char(
+                data,
+                font,
+                b'>',
+                175,
+                14 + (cursor as usize + 1) * OPTIONS_LINE_PITCH,
+            );
         }
+    }
 
+    fn render_select(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)], cursor: Option<u8>) {
+        let font = &self.assets.font_hq;
+        pal[0x10..0x20].copy_from_slice(&font.cmap);
+        let title = self.config.language.strings().select_table;
+        self.render_line(data, font, title.as_bytes(), SELECT_TOP);
+        for (i, &table) in SELECT_TABLES.iter().enumerate() {
+            let mut line = [b' '; 24];
+            let name = table_name(table);
+            line[2..2 + name.len()].copy_from_slice(name);
+            self.render_line(data, font, &line, SELECT_TOP + (i + 2) * SELECT_LINE_PITCH);
+        }
         if let Some(cursor) = cursor {
-            let pos = if cursor == 6 { 9 } else { cursor as usize + 2 };
-            self.render_char(data, font, b'>', 175, 14 + pos * 18);
+            self.render_char(
+                data,
+                font,
+                b'>',
+                175,
+                SELECT_TOP + (cursor as usize + 2) * SELECT_LINE_PITCH,
+            );
+        }
+    }
+
+    fn reset_high_scores(&mut self) {
+        let high_scores = if self.config.tournament {
+            &mut self.config.tournament_high_scores
+        } else {
+            &mut self.config.high_scores
+        };
+        for scores in high_scores.values_mut() {
+            *scores = cleared_high_scores();
         }
     }
 
@@ -383,6 +601,124 @@ impl Intro {
     }
 }
 
+fn glyph_pos(fidx: usize) -> (usize, usize) {
+    (fidx % 20 * 32, fidx / 20 * 14)
+}
+
+// 9x7 masks, leftmost column in bit 8.  Anything else is skipped, except in
+// debug builds, where it gets a box so that missing glyphs show up.
+fn extra_glyph(chr: u8) -> Option<[u16; 7]> {
+    Some(match chr {
+        b',' => [
+            0b000000000,
+            0b000000000,
+            0b000000000,
+            0b000000000,
+            0b000011000,
+            0b000011000,
+            0b000110000,
+        ],
+        b'!' => [
+            0b000011000,
+            0b000011000,
+            0b000011000,
+            0b000011000,
+            0b000011000,
+            0b000000000,
+            0b000011000,
+        ],
+        b'?' => [
+            0b001111100,
+            0b011000110,
+            0b000001100,
+            0b000011000,
+            0b000011000,
+            0b000000000,
+            0b000011000,
+        ],
+        b'/' => [
+            0b000000011,
+            0b000000110,
+            0b000001100,
+            0b000011000,
+            0b000110000,
+            0b001100000,
+            0b011000000,
+        ],
+        b'(' => [
+            0b000011000,
+            0b000110000,
+            0b001100000,
+            0b001100000,
+            0b001100000,
+            0b000110000,
+            0b000011000,
+        ],
+        b')' => [
+            0b000110000,
+            0b000011000,
+            0b000001100,
+            0b000001100,
+            0b000001100,
+            0b000011000,
+            0b000110000,
+        ],
+        _ if cfg!(debug_assertions) => [
+            0b011111110,
+            0b010000010,
+            0b010000010,
+            0b010000010,
+            0b010000010,
+            0b010000010,
+            0b011111110,
+        ],
+        _ => return None,
+    })
+}
+
+// Writes text into a menu line from column x, cutting it off at the end.
+fn put(line: &mut [u8; 24], x: usize, text: &str) {
+    for (dst, &chr) in line[x..].iter_mut().zip(text.as_bytes()) {
+        *dst = chr;
+    }
+}
+
+// Writes num in decimal at x, returning where it ends.
+fn put_num(line: &mut [u8; 24], x: usize, mut num: u64) -> usize {
+    let mut digits = [0; 20];
+    let mut len = 0;
+    loop {
+        digits[len] = b'0' + (num % 10) as u8;
+        len += 1;
+        num /= 10;
+        if num == 0 {
+            break;
+        }
+    }
+    for (dst, &digit) in line[x..].iter_mut().zip(digits[..len].iter().rev()) {
+        *dst = digit;
+    }
+    x + len
+}
+
+fn table_name(table: TableId) -> &'static [u8] {
+    match table {
+        TableId::Table1 => b"PARTY LAND",
+        TableId::Table2 => b"SPEED DEVILS",
+        TableId::Table3 => b"BILLION DOLLAR",
+        TableId::Table4 => b"STONES N BONES",
+    }
+}
+
+// Left and right step a setting down and up, other keys cycle through it.
+fn step(key: KeyPress) -> Step {
+    match key {
+        KeyPress::Left => Step::Down,
+        KeyPress::Right => Step::Up,
+        _ => Step::Cycle,
+    }
+}
+
 fn fade_pal(
     dst: &mut [(u8, u8, u8)],
     src: &[(u8, u8, u8)],
@@ -407,6 +743,7 @@ impl View for Intro {
     }
 
     fn run_frame(&mut self) -> Action {
+        self.player.advance(self.get_fps());
         match self.left_state {
             LeftState::None => {}
             LeftState::Image(ref mut n) => {
@@ -511,10 +848,10 @@ impl View for Intro {
                     self.state = State::Tables(0);
                 }
             }
-            State::TablesFadeOut(ref mut n, action) => {
+            State::TablesFadeOut(ref mut n, ref action) => {
                 self.player.set_master_volume(0x100 * (80 - *n) as u32 / 80);
                 if *n >= 80 {
-                    return action;
+                    return action.clone();
                 }
                 *n += 1;
             }
@@ -559,11 +896,12 @@ impl View for Intro {
                             self.state = State::OptionsGap(0);
                             self.left_is_options = true;
                         }
-                        IntroAction::Table(_) => unreachable!(),
+                        IntroAction::Table(_) | IntroAction::Demo => unreachable!(),
                     }
                 }
             }
             State::TextGap(ref mut n) => {
+                self.hiscore_page = 0;
                 *n += 1;
                 if *n >= 5 {
                     self.state = State::TextFadeIn(0);
@@ -577,6 +915,7 @@ impl View for Intro {
             }
             State::Text(ref mut n) => {
                 *n += 1;
+                self.hiscore_page = usize::from(*n / HISCORE_PAGE_FRAMES);
                 match self.key {
                     KeyPress::Table(table) => {
                         self.state = State::TextFadeOut(0, IntroAction::Table(table));
@@ -589,7 +928,12 @@ impl View for Intro {
                     }
                     _ => {
                         if *n >= 420 {
-                            self.state = State::TextFadeOut(0, IntroAction::SkipToTables);
+                            let action = if self.config.options.attract_demo {
+                                IntroAction::Demo
+                            } else {
+                                IntroAction::SkipToTables
+                            };
+                            self.state = State::TextFadeOut(0, action);
                         }
                     }
                 }
@@ -611,6 +955,10 @@ impl View for Intro {
                         IntroAction::Table(table) => {
                             self.state = State::FadeOut(0, Action::Navigate(Route::Table(table)));
                         }
+                        IntroAction::Demo => {
+                            let route = Route::AttractDemo(self.demo_table);
+                            self.state = State::FadeOut(0, Action::Navigate(route));
+                        }
                         _ => unreachable!(),
                     }
                 }
@@ -627,48 +975,91 @@ impl View for Intro {
                     self.state = State::Options(0);
                 }
             }
+            State::Options(_) if self.reset_confirm.is_some() => {
+                let yes = self.reset_confirm == Some(true);
+                match self.key {
+                    KeyPress::Left | KeyPress::Right => self.reset_confirm = Some(!yes),
+                    KeyPress::Enter | KeyPress::Space => {
+                        self.reset_confirm = None;
+                        if yes {
+                            self.reset_high_scores();
+                            self.key = KeyPress::None;
+                            return Action::ResetHighScores;
+                        }
+                    }
+                    KeyPress::None => {}
+                    _ => self.reset_confirm = None,
+                }
+                self.key = KeyPress::None;
+            }
             State::Options(ref mut cursor) => {
                 match self.key {
                     KeyPress::Enter | KeyPress::Space => match *cursor {
-                        0 => {
-                            if self.config.options.balls == 3 {
-                                self.config.options.balls = 5;
-                            } else {
-                                self.config.options.balls = 3;
-                            }
-                        }
+                        0 => self.config.options.step_balls(step(self.key)),
                         1 => self.config.options.angle_high = !self.config.options.angle_high,
-                        2 => {
-                            self.config.options.scroll_speed =
-                                match self.config.options.scroll_speed {
-                                    ScrollSpeed::Hard => ScrollSpeed::Medium,
-                                    ScrollSpeed::Medium => ScrollSpeed::Soft,
-                                    ScrollSpeed::Soft => ScrollSpeed::Hard,
+                        2 => self.config.options.step_scroll_speed(step(self.key)),
+                        3 => self.config.options.no_music = !self.config.options.no_music,
+                        4 | 5 => {
+                            self.config
+                                .options
+                                .step_volume(*cursor == 4, step(self.key));
+                            self.player
+                                .set_music_volume(self.config.options.music_gain());
+                        }
+                        6 => {
+                            self.config.options.resolution = self.config.options.resolution.next();
+                        }
+                        7 => self.config.options.step_color_mode(),
+                        8 => {
+                            self.config.options.window_scale =
+                                match self.config.options.window_scale {
+                                    WindowScale::X1 => WindowScale::X2,
+                                    WindowScale::X2 => WindowScale::X3,
+                                    WindowScale::X3 => WindowScale::Fit,
+                                    WindowScale::Fit => WindowScale::X1,
                                 }
                         }
-                        3 => self.config.options.no_music = !self.config.options.no_music,
-                        4 => {
-                            self.config.options.resolution = match self.config.options.resolution {
-                                Resolution::Normal => Resolution::High,
-                                Resolution::High => Resolution::Full,
-                                Resolution::Full => Resolution::Normal,
-                            };
+                        9 => self.config.options.crt_filter = !self.config.options.crt_filter,
+                        10 => self.config.options.step_gamma(step(self.key)),
+                        11 => {
+                            self.config.options.reduce_flashing =
+                                !self.config.options.reduce_flashing
                         }
-                        5 => self.config.options.mono = !self.config.options.mono,
+                        12 => self.config.options.step_replay_score(step(self.key)),
+                        OPTION_RESET => self.reset_confirm = Some(false),
                         _ => self.state = State::OptionsFadeOut(0),
                     },
                     KeyPress::Escape => {
                         self.state = State::OptionsFadeOut(0);
                     }
+                    KeyPress::Left | KeyPress::Right if matches!(*cursor, 4 | 5) => {
+                        self.config
+                            .options
+                            .step_volume(*cursor == 4, step(self.key));
+                        self.player
+                            .set_music_volume(self.config.options.music_gain());
+                    }
+                    KeyPress::Left | KeyPress::Right if *cursor == 0 => {
+                        self.config.options.step_balls(step(self.key));
+                    }
+                    KeyPress::Left | KeyPress::Right if *cursor == 2 => {
+                        self.config.options.step_scroll_speed(step(self.key));
+                    }
+                    KeyPress::Left | KeyPress::Right if *cursor == 10 => {
+                        self.config.options.step_gamma(step(self.key));
+                    }
+                    KeyPress::Left | KeyPress::Right if *cursor == 12 => {
+                        self.config.options.step_replay_score(step(self.key));
+                    }
                     KeyPress::Up => {
                         if *cursor == 0 {
-                            *cursor = 6;
+                            *cursor = OPTION_SAVE;
                         } else {
                             *cursor -= 1;
                         }
                     }
                     KeyPress::Down => {
-                        if *cursor == 6 {
+                        if *cursor == OPTION_SAVE {
                             *cursor = 0;
                         } else {
                             *cursor += 1;
@@ -686,13 +1077,32 @@ impl View for Intro {
                     return Action::SaveOptions(self.config.options);
                 }
             }
-            State::FadeOut(ref mut n, action) => {
+            State::FadeOut(ref mut n, ref action) | State::SelectFadeOut(ref mut n, ref action) => {
                 self.player.set_master_volume(0x100 * (80 - *n) as u32 / 80);
                 if *n >= 80 {
-                    return action;
+                    return action.clone();
                 }
                 *n += 1;
             }
+            State::Select(ref mut cursor) => {
+                let last = SELECT_TABLES.len() as u8 - 1;
+                match self.key {
+                    KeyPress::Table(table) => {
+                        self.state = State::SelectFadeOut(0, Action::Navigate(Route::Table(table)));
+                    }
+                    KeyPress::Enter | KeyPress::Space => {
+                        let table = SELECT_TABLES[usize::from(*cursor)];
+                        self.state = State::SelectFadeOut(0, Action::Navigate(Route::Table(table)));
+                    }
+                    KeyPress::Escape => {
+                        self.state = State::SelectFadeOut(0, Action::Exit);
+                    }
+                    KeyPress::Up => *cursor = if *cursor == 0 { last } else { *cursor - 1 },
+                    KeyPress::Down => *cursor = if *cursor == last { 0 } else { *cursor + 1 },
+                    _ => {}
+                }
+                self.key = KeyPress::None;
+            }
         }
         Action::None
     }
@@ -712,11 +1122,30 @@ impl View for Intro {
             VirtualKeyCode::Space => self.key = KeyPress::Space,
             VirtualKeyCode::Down => self.key = KeyPress::Down,
             VirtualKeyCode::Up => self.key = KeyPress::Up,
+            VirtualKeyCode::Left => self.key = KeyPress::Left,
+            VirtualKeyCode::Right => self.key = KeyPress::Right,
+            _ => (),
+        }
+    }
+
+    fn handle_button(&mut self, button: PadButton, state: ElementState) {
+        if state != ElementState::Pressed {
+            return;
+        }
+        match button {
+            PadButton::South => self.key = KeyPress::Enter,
+            PadButton::DPadDown => self.key = KeyPress::Down,
+            PadButton::DPadUp => self.key = KeyPress::Up,
+            PadButton::DPadLeft => self.key = KeyPress::Left,
+            PadButton::DPadRight => self.key = KeyPress::Right,
             _ => (),
         }
     }
 
     fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        // palette entries faded towards a color, done after the gamma so it
+        // doesn't bend the fade
+        let mut fade = None;
         match self.state {
             State::Slide(slide, sstate) => {
                 let slide = &self.assets.slides[slide];
@@ -738,7 +1167,7 @@ impl View for Intro {
                             }
                         }
                     }
-                    _ => panic!("weird width"),
+                    _ => unreachable!("slide sizes are checked when loading"),
                 }
                 match sstate {
                     SlideState::Gap(_) => {
@@ -750,26 +1179,26 @@ impl View for Intro {
                         } else {
                             (0, 0, 0)
                         };
-                        fade_pal(
-                            pal,
-                            &img.cmap,
+                        pal[..img.cmap.len()].copy_from_slice(&img.cmap);
+                        fade = Some((
+                            0..img.cmap.len(),
                             color,
                             num as usize,
                             slide.fade_in_frames as usize,
-                        );
+                        ));
                     }
                     SlideState::Show => {
                         pal[..img.cmap.len()].copy_from_slice(&img.cmap);
                     }
                     SlideState::FadeOut(num) => {
                         let den = slide.fade_out_frames;
-                        fade_pal(
-                            pal,
-                            &img.cmap,
+                        pal[..img.cmap.len()].copy_from_slice(&img.cmap);
+                        fade = Some((
+                            0..img.cmap.len(),
                             (0, 0, 0),
                             (den - num) as usize,
-                            slide.fade_out_frames as usize,
-                        );
+                            den as usize,
+                        ));
                     }
                 }
             }
@@ -809,17 +1238,12 @@ impl View for Intro {
             State::TablesFadeOut(n, _) => {
                 self.render_left(data, pal);
                 self.render_tables(data, pal, |_| true);
-                let opal = pal.to_vec();
-                fade_pal(pal, &opal, (0, 0, 0), (80 - n) as usize, 80);
+                fade = Some((0..pal.len(), (0, 0, 0), (80 - n) as usize, 80));
             }
             State::TextFadeIn(n) => {
                 self.render_left(data, pal);
                 self.render_text(data, pal, true);
-                for pe in &mut pal[0x10..0x20] {
-                    pe.0 = (pe.0 as u32 * (n as u32) / 20) as u8;
-                    pe.1 = (pe.1 as u32 * (n as u32) / 20) as u8;
-                    pe.2 = (pe.2 as u32 * (n as u32) / 20) as u8;
-                }
+                fade = Some((0x10..0x20, (0, 0, 0), n as usize, 20));
             }
             State::Text(_) => {
                 self.render_left(data, pal);
@@ -828,20 +1252,12 @@ impl View for Intro {
             State::TextFadeOut(n, _) => {
                 self.render_left(data, pal);
                 self.render_text(data, pal, true);
-                for pe in &mut pal[0x10..0x20] {
-                    pe.0 = (pe.0 as u32 * (19 - n as u32) / 20) as u8;
-                    pe.1 = (pe.1 as u32 * (19 - n as u32) / 20) as u8;
-                    pe.2 = (pe.2 as u32 * (19 - n as u32) / 20) as u8;
-                }
+                fade = Some((0x10..0x20, (0, 0, 0), (19 - n) as usize, 20));
             }
             State::OptionsFadeIn(n) => {
                 self.render_left(data, pal);
                 self.render_options(data, pal, true, None);
-                for pe in &mut pal[0x10..0x20] {
-                    pe.0 = (pe.0 as u32 * (n as u32) / 40) as u8;
-                    pe.1 = (pe.1 as u32 * (n as u32) / 40) as u8;
-                    pe.2 = (pe.2 as u32 * (n as u32) / 40) as u8;
-                }
+                fade = Some((0x10..0x20, (0, 0, 0), n as usize, 40));
             }
             State::Options(cursor) => {
                 self.render_left(data, pal);
@@ -850,17 +1266,37 @@ impl View for Intro {
             State::OptionsFadeOut(n) => {
                 self.render_left(data, pal);
                 self.render_options(data, pal, true, None);
-                for pe in &mut pal[0x10..0x20] {
-                    pe.0 = (pe.0 as u32 * (39 - n as u32) / 40) as u8;
-                    pe.1 = (pe.1 as u32 * (39 - n as u32) / 40) as u8;
-                    pe.2 = (pe.2 as u32 * (39 - n as u32) / 40) as u8;
-                }
+                fade = Some((0x10..0x20, (0, 0, 0), (39 - n) as usize, 40));
             }
             State::FadeOut(n, _) => {
                 self.render_left(data, pal);
-                let opal = pal.to_vec();
-                fade_pal(pal, &opal, (0, 0, 0), (80 - n) as usize, 80);
+                fade = Some((0..pal.len(), (0, 0, 0), (80 - n) as usize, 80));
+            }
+            State::Select(cursor) => {
+                self.render_left(data, pal);
+                self.render_select(data, pal, Some(cursor));
+            }
+            State::SelectFadeOut(n, _) => {
+                self.render_left(data, pal);
+                self.render_select(data, pal, None);
+                fade = Some((0..pal.len(), (0, 0, 0), (80 - n) as usize, 80));
             }
         }
+        self.config.options.apply_gamma(pal);
+        if let Some((range, color, num, den)) = fade {
+            let mut base = [(0, 0, 0); 256];
+            base[range.clone()].copy_from_slice(&pal[range.clone()]);
+            fade_pal(&mut pal[range.clone()], &base[range], color, num, den);
+        }
+    }
+
+    fn render_frame_stats(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)], text: &[u8]) {
+        let font = &self.assets.font_hq;
+        pal[0x10..0x20].copy_from_slice(&font.cmap);
+        self.config.options.apply_gamma(&mut pal[0x10..0x20]);
+        let sx = 640 - 4 - text.len() * 18;
+        for (tx, &chr) in text.iter().enumerate() {
+            self.render_char(data, font, chr, sx + tx * 18, 2);
+        }
     }
 }
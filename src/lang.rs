@@ -0,0 +1,279 @@
+// The text of the intro's menus in each language.  The intro fonts only have
+// A-Z, 0-9 and a little punctuation, so other letters are spelled without
+// their accents (AE for Ä and so on).
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Swedish,
+}
+
+pub struct Strings {
+    pub options_title: &'static str,
+    // the settings in the order of the options menu; each has to leave the
+    // columns from 16 on free for the value
+    pub options: [&'static str; 15],
+    pub select_table: &'static str,
+    pub on: &'static str,
+    pub off: &'static str,
+    pub yes: &'static str,
+    pub no: &'static str,
+    pub high: &'static str,
+    pub low: &'static str,
+    pub hard: &'static str,
+    pub medium: &'static str,
+    pub soft: &'static str,
+    // followed by the custom scroll speed, so at most 5 letters
+    pub speed: &'static str,
+    pub normal: &'static str,
+    pub full: &'static str,
+    pub color: &'static str,
+    pub mono: &'static str,
+    pub fit: &'static str,
+    pub reduced: &'static str,
+    // the help beside the menu and the options, ten lines of at most 12
+    // letters; None keeps the original game's text
+    pub left_text_menu: Option<[&'static str; 10]>,
+    pub left_text_options: Option<[&'static str; 10]>,
+}
+
+const ENGLISH: Strings = Strings {
+    options_title: "OPTIONS MENU",
+    options: [
+        "BALLS:",
+        "ANGLE:",
+        "SCROLLING:",
+        "INGAME MUSIC:",
+        "MUSIC VOL:",
+        "SFX VOL:",
+        "RESOLUTION:",
+        "COLOR MODE:",
+        "WINDOW:",
+        "CRT FILTER:",
+        "GAMMA:",
+        "FLASHING:",
+        "REPLAY:",
+        "RESET SCORES",
+        "SAVE AND EXIT",
+    ],
+    select_table: "SELECT TABLE",
+    on: "ON",
+    off: "OFF",
+    yes: "YES",
+    no: "NO",
+    high: "HIGH",
+    low: "LOW",
+    hard: "HARD",
+    medium: "MEDIUM",
+    soft: "SOFT",
+    speed: "SPEED",
+    normal: "NORMAL",
+    full: "FULL",
+    color: "COLOR",
+    mono: "MONO",
+    fit: "FIT",
+    reduced: "REDUCED",
+    left_text_menu: None,
+    left_text_options: None,
+};
+
+const GERMAN: Strings = Strings {
+    options_title: "OPTIONEN",
+    options: [
+        "BAELLE:",
+        "WINKEL:",
+        "SCROLLEN:",
+        "SPIELMUSIK:",
+        "MUSIK VOL:",
+        "EFFEKT VOL:",
+        "AUFLOESUNG:",
+        "FARBMODUS:",
+        "FENSTER:",
+        "CRT FILTER:",
+        "GAMMA:",
+        "BLINKEN:",
+        "FREISPIEL:",
+        "REKORDE LOESCHEN",
+        "SPEICHERN UND ENDE",
+    ],
+    select_table: "TISCH WAEHLEN",
+    on: "AN",
+    off: "AUS",
+    yes: "JA",
+    no: "NEIN",
+    high: "HOCH",
+    low: "FLACH",
+    hard: "HART",
+    medium: "MITTEL",
+    soft: "WEICH",
+    speed: "TEMPO",
+    normal: "NORMAL",
+    full: "VOLL",
+    color: "FARBE",
+    mono: "MONO",
+    fit: "ANPASSEN",
+    reduced: "WENIGER",
+    left_text_menu: Some([
+        "F1-F4:",
+        " TISCH",
+        " SPIELEN",
+        "F5:",
+        " OPTIONEN",
+        "LEERTASTE:",
+        " REKORDE",
+        "ESC:",
+        " ENDE",
+        "",
+    ]),
+    left_text_options: Some([
+        "HOCH UND",
+        "RUNTER:",
+        " AUSWAEHLEN",
+        "LINKS UND",
+        "RECHTS:",
+        " AENDERN",
+        "ENTER:",
+        " BESTAETIGEN",
+        "ESC:",
+        " ZURUECK",
+    ]),
+};
+
+const FRENCH: Strings = Strings {
+    options_title: "MENU OPTIONS",
+    options: [
+        "BALLES:",
+        "ANGLE:",
+        "DEFILEMENT:",
+        "MUSIQUE JEU:",
+        "VOL MUSIQUE:",
+        "VOL EFFETS:",
+        "RESOLUTION:",
+        "COULEURS:",
+        "FENETRE:",
+        "FILTRE CRT:",
+        "GAMMA:",
+        "CLIGNOTEMENT:",
+        "REJOUER:",
+        "EFFACER SCORES",
+        "SAUVER ET QUITTER",
+    ],
+    select_table: "CHOIX DE TABLE",
+    on: "OUI",
+    off: "NON",
+    yes: "OUI",
+    no: "NON",
+    high: "HAUT",
+    low: "BAS",
+    hard: "DUR",
+    medium: "MOYEN",
+    soft: "DOUX",
+    speed: "VIT.",
+    normal: "NORMALE",
+    full: "PLEINE",
+    color: "COULEUR",
+    mono: "MONO",
+    fit: "AJUSTE",
+    reduced: "REDUIT",
+    left_text_menu: Some([
+        "F1-F4:",
+        " JOUER UNE",
+        " TABLE",
+        "F5:",
+        " OPTIONS",
+        "ESPACE:",
+        " RECORDS",
+        "ECHAP:",
+        " QUITTER",
+        "",
+    ]),
+    left_text_options: Some([
+        "HAUT ET",
+        "BAS:",
+        " CHOISIR",
+        "GAUCHE ET",
+        "DROITE:",
+        " CHANGER",
+        "ENTREE:",
+        " VALIDER",
+        "ECHAP:",
+        " RETOUR",
+    ]),
+};
+
+const SWEDISH: Strings = Strings {
+    options_title: "INSTALLNINGAR",
+    options: [
+        "BOLLAR:",
+        "VINKEL:",
+        "RULLNING:",
+        "SPELMUSIK:",
+        "MUSIKVOLYM:",
+        "LJUDVOLYM:",
+        "UPPLOSNING:",
+        "FARGLAGE:",
+        "FONSTER:",
+        "CRT FILTER:",
+        "GAMMA:",
+        "BLINKNING:",
+        "EXTRASPEL:",
+        "NOLLSTALL REKORD",
+        "SPARA OCH AVSLUTA",
+    ],
+    select_table: "VALJ BORD",
+    on: "PA",
+    off: "AV",
+    yes: "JA",
+    no: "NEJ",
+    high: "HOG",
+    low: "LAG",
+    hard: "HARD",
+    medium: "MEDEL",
+    soft: "MJUK",
+    speed: "FART",
+    normal: "NORMAL",
+    full: "FULL",
+    color: "FARG",
+    mono: "MONO",
+    fit: "ANPASSA",
+    reduced: "MINDRE",
+    left_text_menu: Some([
+        "F1-F4:",
+        " SPELA ETT",
+        " BORD",
+        "F5:",
+        " INSTALLN.",
+        "MELLANSLAG:",
+        " REKORD",
+        "ESC:",
+        " AVSLUTA",
+        "",
+    ]),
+    left_text_options: Some([
+        "UPP OCH",
+        "NER:",
+        " VALJ",
+        "VANSTER OCH",
+        "HOGER:",
+        " ANDRA",
+        "ENTER:",
+        " BEKRAFTA",
+        "ESC:",
+        " TILLBAKA",
+    ]),
+};
+
+impl Language {
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::English => &ENGLISH,
+            Language::German => &GERMAN,
+            Language::French => &FRENCH,
+            Language::Swedish => &SWEDISH,
+        }
+    }
+}
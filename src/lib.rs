@@ -1,7 +1,17 @@
 pub mod assets;
+pub mod attract;
 pub mod bcd;
 pub mod config;
+pub mod data;
+pub mod demo;
+pub mod error;
+pub mod game;
+pub mod input_log;
 pub mod intro;
+pub mod lang;
+pub mod palette;
+pub mod recorder;
 pub mod sound;
+pub mod spectate;
 pub mod table;
 pub mod view;
@@ -1,38 +1,951 @@
 use game_loop::game_loop;
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::{BufWriter, Cursor},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use clap::Parser;
+use gilrs::{Axis, Button, EventType, Gilrs};
 use pfr::{
-    config::{save_high_scores, Config, TableId},
-    intro::Intro,
-    table::Table,
-    view::{Action, Route, View},
+    assets::verify_data_dir,
+    config::{
+        self, save_high_scores, AudioLatency, Config, TableId, WindowScale, MAX_AUDIO_LATENCY,
+        MAX_FPS_CAP, MIN_AUDIO_LATENCY, MIN_FPS_CAP, TABLES,
+    },
+    data::{open_data_source, DataSource},
+    demo::{Demo, DemoEvent, DemoInput},
+    game::{Game, Input, Key, PointerButton},
+    input_log::InputLog,
+    lang::Language,
+    palette::{blend_rows, expand, mix, rgba_lut},
+    recorder::GifRecorder,
+    sound::{player::output_devices, render_module_to_wav},
+    spectate::{SpectateServer, Spectator, DEFAULT_PORT},
+    table::GameEvent,
+    view::{Action, GameStatus, PadButton, PanelLayout, Route},
 };
-use pixels::{Pixels, SurfaceTexture};
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
 use winit::{
     dpi::PhysicalSize,
-    event::{Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::EventLoop,
-    window::WindowBuilder,
+    window::{Fullscreen, Window, WindowBuilder},
 };
 
-struct Game {
+// The window, input devices and files around the game.
+struct Frontend {
     pixels: Pixels,
-    view: Option<Box<dyn View>>,
-    config: Config,
+    game: Game,
+    config_path: Option<PathBuf>,
     args: Args,
     dims: (u32, u32),
+    panel: Option<PanelLayout>,
+    aspect: (u32, u32),
+    zoom: u32,
+    mouse_pos: (u32, u32),
+    mouse_button: Option<MouseButton>,
+    status: Option<GameStatus>,
+    input_log: Option<InputLog>,
+    display_text: Vec<String>,
+    modifiers: ModifiersState,
+    quick_load: bool,
+    // window input waiting for the next frame, which takes it together with
+    // the gamepad's right before it runs
+    pending_input: Vec<(DemoInput, ElementState)>,
+    gilrs: Option<Gilrs>,
+    stick_nudge: bool,
+    recording: Option<GifRecorder>,
+    frame: u64,
+    demo: DemoMode,
+    frame_stats: Option<FrameStats>,
+    rendered_frame: Option<u64>,
+    // time between presented frames when they are paced by the game rather
+    // than by vsync, and when the next one is due
+    present_interval: Option<Duration>,
+    next_present: Option<Instant>,
+    spectate_server: Option<SpectateServer>,
+    spectated_events: Option<Receiver<GameEvent>>,
+}
+
+struct PanelFrame {
+    width: usize,
+    data: Vec<u8>,
+    pal: [(u8, u8, u8); 256],
+}
+
+struct FrameStats {
+    last: Option<Instant>,
+    frame_time: f32,
+}
+
+enum DemoMode {
+    None,
+    Record(PathBuf, Demo),
+    Play(Demo),
+}
+
+const STICK_DEADZONE: f32 = 0.5;
+const CRT_SCANLINE: u16 = 0x99;
+const QUICK_SAVE: &str = "pfr-quicksave.toml";
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+// The loop runs run_frame at the view's fixed rate from an accumulator of real
+// time, independently of how often the display presents.  After a stall at most
+// this many seconds are added to the accumulator, so the game slows down instead
+// of trying to catch up with a long burst of frames.
+const MAX_FRAME_TIME: f64 = 0.2;
+// Frame pacing sleeps until this long before a frame is due and spins for the
+// rest, as sleeps can overshoot by about that much.
+const PACE_SPIN: Duration = Duration::from_millis(1);
+
+fn timestamped_path(ext: &str) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    PathBuf::from(format!(
+        "pfr-{}{:03}.{ext}",
+        now.as_secs(),
+        now.subsec_millis()
+    ))
+}
+
+// Picks the smallest per-axis pixel repeat that shows the frame at roughly 4:3.
+fn pixel_aspect((w, h): (u32, u32)) -> (u32, u32) {
+    let target = 4.0 * h as f32 / (3.0 * w as f32);
+    (1..=4)
+        .map(|sy| (((target * sy as f32).round() as u32).max(1), sy))
+        .min_by(|&(ax, ay), &(bx, by)| {
+            let ea = (ax as f32 / ay as f32 - target).abs();
+            let eb = (bx as f32 / by as f32 - target).abs();
+            ea.total_cmp(&eb)
+        })
+        .unwrap()
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+    Some(match button {
+        Button::South => PadButton::South,
+        Button::East => PadButton::East,
+        Button::North => PadButton::North,
+        Button::West => PadButton::West,
+        Button::LeftTrigger | Button::LeftTrigger2 => PadButton::LeftShoulder,
+        Button::RightTrigger | Button::RightTrigger2 => PadButton::RightShoulder,
+        Button::Start => PadButton::Start,
+        Button::Select => PadButton::Select,
+        Button::DPadUp => PadButton::DPadUp,
+        Button::DPadDown => PadButton::DPadDown,
+        Button::DPadLeft => PadButton::DPadLeft,
+        Button::DPadRight => PadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+// winit's names for the keys the game knows
+macro_rules! game_keys {
+    ($($key:ident,)*) => {
+        fn game_key(key: VirtualKeyCode) -> Option<Key> {
+            Some(match key {
+                $(VirtualKeyCode::$key => Key::$key,)*
+                _ => return None,
+            })
+        }
+    };
+}
+
+game_keys! {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Up, Down, Left, Right,
+    Return, Escape, Space, Back, Tab,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Comma, Period, Slash, Semicolon, Apostrophe, LBracket, RBracket, Backslash,
+    Minus, Equals, Plus, Grave,
+    NumpadAdd, NumpadSubtract,
+    Insert, Delete, Home, End, PageUp, PageDown,
+}
+
+fn game_input(input: DemoInput) -> Option<Input> {
+    Some(match input {
+        DemoInput::Key(key) => Input::Key(game_key(key)?),
+        DemoInput::Button(button) => Input::Button(button),
+        DemoInput::Mouse(pos, MouseButton::Left) => Input::Pointer(pos, PointerButton::Left),
+        DemoInput::Mouse(pos, MouseButton::Right) => Input::Pointer(pos, PointerButton::Right),
+        DemoInput::Mouse(..) => return None,
+        DemoInput::FocusLost => Input::FocusLost,
+    })
+}
+
+impl Frontend {
+    fn render_indexed(&self) -> (Vec<u8>, [(u8, u8, u8); 256]) {
+        let mut data = vec![0u8; self.dims.0 as usize * self.dims.1 as usize];
+        let mut pal = [(0u8, 0u8, 0u8); 256];
+        self.game.render(&mut data, &mut pal);
+        (data, pal)
+    }
+
+    // What goes on screen, which is a frame ahead with run-ahead on; saved
+    // screenshots and recordings keep to the actual frame.
+    fn render_displayed(&mut self) -> (Vec<u8>, [(u8, u8, u8); 256]) {
+        // demos have to replay exactly, so they don't take the risk
+        if !self.game.config().run_ahead || !matches!(self.demo, DemoMode::None) {
+            return self.render_indexed();
+        }
+        let mut data = vec![0u8; self.dims.0 as usize * self.dims.1 as usize];
+        let mut pal = [(0u8, 0u8, 0u8); 256];
+        self.game.render_ahead(&mut data, &mut pal);
+        (data, pal)
+    }
+
+    fn render_panel(&self) -> Option<PanelFrame> {
+        let panel = self.panel?;
+        let mut data = vec![0u8; panel.width as usize * self.dims.1 as usize];
+        let mut pal = [(0u8, 0u8, 0u8); 256];
+        self.game.render_panel(&mut data, &mut pal);
+        Some(PanelFrame {
+            width: panel.width as usize,
+            data,
+            pal,
+        })
+    }
+
+    // The view's frame with the side panel, if any, next to it.
+    fn frame_dims(&self) -> (u32, u32) {
+        let panel_width = self.panel.map_or(0, |panel| panel.width);
+        (self.dims.0 + panel_width, self.dims.1)
+    }
+
+    fn save_screenshot(&self) -> std::io::Result<PathBuf> {
+        let (data, pal) = self.render_indexed();
+        let path = timestamped_path("png");
+        let w = BufWriter::new(File::create(&path)?);
+        let mut encoder = png::Encoder::new(w, self.dims.0, self.dims.1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let rgb: Vec<u8> = data
+            .iter()
+            .flat_map(|&pixel| {
+                let (r, g, b) = pal[usize::from(pixel)];
+                [r, g, b]
+            })
+            .collect();
+        writer.write_image_data(&rgb)?;
+        writer.finish()?;
+        Ok(path)
+    }
+
+    fn update_dims(&mut self, window: &Window) {
+        let dims = self.game.resolution();
+        self.dims = dims;
+        self.panel = self.game.panel();
+        self.aspect = pixel_aspect(dims);
+        self.apply_window_scale(window);
+    }
+
+    // The table's options menu can change its resolution at any keypress.
+    fn sync_dims(&mut self, window: &Window) {
+        if self.game.resolution() != self.dims {
+            self.update_dims(window);
+        }
+    }
+
+    fn toggle_fullscreen(&mut self, window: &Window) {
+        let fullscreen = !self.game.config().options.fullscreen;
+        self.game.config_mut().options.fullscreen = fullscreen;
+        window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+        self.apply_window_scale(window);
+        // Alt already went to the view as a flipper press, and its release
+        // can get lost while the window changes mode.
+        for key in [VirtualKeyCode::LAlt, VirtualKeyCode::RAlt] {
+            self.queue_input(DemoInput::Key(key), ElementState::Released);
+        }
+        if !matches!(self.demo, DemoMode::Play(_)) {
+            self.save_config();
+        }
+    }
+
+    fn cycle_resolution(&mut self, window: &Window) {
+        if !matches!(self.demo, DemoMode::None) {
+            eprintln!("the resolution cannot be changed while recording or playing a demo");
+            return;
+        }
+        let Some(table) = self.game.table() else {
+            return;
+        };
+        let resolution = self.game.config().options_for(table).resolution.next();
+        if !self.game.set_resolution(resolution) {
+            return;
+        }
+        self.game
+            .config_mut()
+            .table_options
+            .entry(table)
+            .or_default()
+            .resolution = Some(resolution);
+        self.update_dims(window);
+        self.save_config();
+    }
+
+    fn toggle_frame_stats(&mut self) {
+        self.rendered_frame = None;
+        self.frame_stats = match self.frame_stats {
+            Some(_) => None,
+            None => Some(FrameStats {
+                last: None,
+                frame_time: 0.0,
+            }),
+        };
+    }
+
+    fn measure_frame(&mut self) {
+        let Some(ref mut stats) = self.frame_stats else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some(last) = stats.last {
+            let elapsed = now.duration_since(last).as_secs_f32();
+            stats.frame_time = if stats.frame_time == 0.0 {
+                elapsed
+            } else {
+                stats.frame_time + (elapsed - stats.frame_time) * FRAME_TIME_SMOOTHING
+            };
+        }
+        stats.last = Some(now);
+    }
+
+    fn render_frame_stats(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        let Some(ref stats) = self.frame_stats else {
+            return;
+        };
+        if stats.frame_time == 0.0 {
+            return;
+        }
+        let text = format!(
+            "{:.0} FPS {:.1} MS",
+            1.0 / stats.frame_time,
+            stats.frame_time * 1000.0
+        );
+        self.game.render_frame_stats(data, pal, text.as_bytes());
+    }
+
+    // Waits until the next frame is due.  The simulation keeps to its own
+    // rate, as game_loop runs as many updates as the elapsed time calls for.
+    fn pace_frame(&mut self) {
+        let Some(interval) = self.present_interval else {
+            return;
+        };
+        let due = self.next_present.unwrap_or_else(Instant::now);
+        let wait = due.saturating_duration_since(Instant::now());
+        if wait > PACE_SPIN {
+            std::thread::sleep(wait - PACE_SPIN);
+        }
+        while Instant::now() < due {
+            std::hint::spin_loop();
+        }
+        let now = Instant::now();
+        // after a stall, start over instead of rushing to catch up
+        self.next_present = Some(if now > due + interval {
+            now + interval
+        } else {
+            due + interval
+        });
+    }
+
+    fn quick_save(&self) {
+        if !matches!(self.demo, DemoMode::None) {
+            eprintln!("quick save is not available while recording or playing a demo");
+            return;
+        }
+        if self.game.config().tournament {
+            eprintln!("quick save is not available in tournament mode");
+            return;
+        }
+        let Some(state) = self.game.save_state() else {
+            return;
+        };
+        match std::fs::write(QUICK_SAVE, state) {
+            Ok(()) => eprintln!("saved game state to {QUICK_SAVE}"),
+            Err(e) => eprintln!("failed to save game state: {e}"),
+        }
+    }
+
+    fn load_quick_save(&mut self) -> bool {
+        if !matches!(self.demo, DemoMode::None) {
+            eprintln!("quick load is not available while recording or playing a demo");
+            return false;
+        }
+        if self.game.config().tournament {
+            eprintln!("quick load is not available in tournament mode");
+            return false;
+        }
+        let loaded = std::fs::read(QUICK_SAVE).and_then(|state| self.game.load_state(&state));
+        if let Err(e) = loaded {
+            eprintln!("failed to load game state from {QUICK_SAVE}: {e}");
+            return false;
+        }
+        self.save_stored_high_scores();
+        true
+    }
+
+    fn apply_window_scale(&mut self, window: &Window) {
+        if window.fullscreen().is_some() {
+            // pixels letterboxes the frame at the largest integer scale
+            self.resize_buffer(window.inner_size());
+            return;
+        }
+        let (fw, fh) = self.frame_dims();
+        let (w, h) = (fw * self.aspect.0, fh * self.aspect.1);
+        let scale = match self.game.config().options.window_scale {
+            WindowScale::X1 => 1,
+            WindowScale::X2 => 2,
+            WindowScale::X3 => 3,
+            WindowScale::Fit => {
+                window.set_min_inner_size(Some(PhysicalSize::new(w, h)));
+                window.set_resizable(true);
+                self.resize_buffer(window.inner_size());
+                return;
+            }
+        };
+        let size = PhysicalSize::new(w * scale, h * scale);
+        window.set_resizable(false);
+        window.set_min_inner_size(None::<PhysicalSize<u32>>);
+        window.set_inner_size(size);
+        self.resize_buffer(size);
+    }
+
+    // The CRT filter needs several buffer rows per scanline, so it upscales
+    // by the integer window scale itself instead of leaving that to pixels.
+    fn resize_buffer(&mut self, window_size: PhysicalSize<u32>) {
+        let (fw, fh) = self.frame_dims();
+        let (w, h) = (fw * self.aspect.0, fh * self.aspect.1);
+        self.zoom = if self.game.config().options.crt_filter {
+            (window_size.width / w).min(window_size.height / h).max(1)
+        } else {
+            1
+        };
+        self.pixels
+            .resize_buffer(w * self.zoom, h * self.zoom)
+            .unwrap();
+        self.rendered_frame = None;
+    }
+
+    // Maps a window position back to the view's own pixels, undoing aspect repeat and zoom.
+    fn framebuffer_pos(&self, x: f32, y: f32) -> (u32, u32) {
+        let (px, py) = self
+            .pixels
+            .window_pos_to_pixel((x, y))
+            .unwrap_or_else(|pos| self.pixels.clamp_pixel_pos(pos));
+        (
+            (px as u32 / (self.aspect.0 * self.zoom)).min(self.dims.0 - 1),
+            py as u32 / (self.aspect.1 * self.zoom),
+        )
+    }
+
+    fn save_config(&self) {
+        if let Some(ref path) = self.config_path {
+            config::save_to_path(self.game.config(), path);
+        }
+    }
+
+    // Writes out high scores the game has stored in its config.
+    fn save_high_scores(&self, table: TableId) {
+        if matches!(self.demo, DemoMode::Play(_)) {
+            return;
+        }
+        let config = self.game.config();
+        if !config.tournament {
+            save_high_scores(table, &config.high_scores[table], &self.args.data);
+        }
+        self.save_config();
+    }
+
+    // Writes out high scores a table held back until it was left.
+    fn save_stored_high_scores(&mut self) {
+        if let Some(table) = self.game.take_stored_high_scores() {
+            self.save_high_scores(table);
+        }
+    }
+
+    fn save_unsaved_high_scores(&mut self) {
+        self.game.store_unsaved_high_scores();
+        self.save_stored_high_scores();
+    }
+
+    fn finish_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            let path = timestamped_path("gif");
+            match recording.save(&path) {
+                Ok(()) => eprintln!("saved recording to {}", path.display()),
+                Err(e) => eprintln!("failed to save recording: {e}"),
+            }
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        if self.recording.is_some() {
+            self.finish_recording();
+        } else {
+            let fps = self.game.fps();
+            self.recording = Some(GifRecorder::new(self.dims, fps, self.args.gif_frames));
+        }
+    }
+
+    fn record_frame(&mut self) {
+        let Some(ref recording) = self.recording else {
+            return;
+        };
+        if recording.dims() != self.dims {
+            self.finish_recording();
+            return;
+        }
+        let (data, pal) = self.render_indexed();
+        if let Some(ref mut recording) = self.recording {
+            recording.push(&data, &pal);
+        }
+    }
+
+    // Rewrites the status file whenever the table's score or ball state changes.
+    fn poll_status(&mut self) {
+        let Some(ref path) = self.args.status_file else {
+            return;
+        };
+        let status = self.game.status();
+        if status == self.status {
+            return;
+        }
+        self.status = status;
+        if let Some(status) = status {
+            let text = toml::to_string(&status).unwrap();
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("cannot write status to {}: {e}", path.display());
+            }
+        }
+    }
+
+    fn poll_spectate(&self) {
+        let Some(ref server) = self.spectate_server else {
+            return;
+        };
+        if let Some(snapshot) = self.game.snapshot() {
+            server.send(snapshot);
+        }
+    }
+
+    fn poll_spectated_events(&self) {
+        let Some(ref events) = self.spectated_events else {
+            return;
+        };
+        for event in events.try_iter() {
+            println!("{event}");
+        }
+    }
+
+    fn poll_input_log(&mut self) {
+        let Some(ref mut log) = self.input_log else {
+            return;
+        };
+        log.tick(self.frame, self.game.status());
+    }
+
+    // Prints the dot matrix text whenever it changes, for screen readers.
+    fn poll_display_text(&mut self) {
+        if !self.args.print_display_text {
+            return;
+        }
+        let text = self.game.display_text();
+        if text == self.display_text {
+            return;
+        }
+        if !text.is_empty() {
+            println!("{}", text.join(" / "));
+        }
+        self.display_text = text;
+    }
+
+    fn queue_input(&mut self, input: DemoInput, state: ElementState) {
+        self.pending_input.push((input, state));
+    }
+
+    fn drain_input(&mut self) {
+        for (input, state) in std::mem::take(&mut self.pending_input) {
+            self.send_input(input, state);
+        }
+        self.poll_gamepad();
+    }
+
+    fn send_input(&mut self, input: DemoInput, state: ElementState) {
+        match self.demo {
+            DemoMode::None => {}
+            DemoMode::Record(_, ref mut demo) => demo.events.push(DemoEvent {
+                frame: self.frame,
+                input,
+                state,
+            }),
+            DemoMode::Play(_) => return,
+        }
+        self.dispatch_input(input, state);
+    }
+
+    fn dispatch_input(&mut self, input: DemoInput, state: ElementState) {
+        if let Some(ref mut log) = self.input_log {
+            log.input(self.frame, input, state);
+        }
+        if let Some(input) = game_input(input) {
+            self.game.input(input, state == ElementState::Pressed);
+        }
+    }
+
+    fn play_demo_frame(&mut self) {
+        let DemoMode::Play(ref demo) = self.demo else {
+            return;
+        };
+        if demo
+            .events
+            .last()
+            .is_none_or(|event| event.frame < self.frame)
+        {
+            eprintln!("demo playback finished");
+            self.demo = DemoMode::None;
+            self.game.set_in_demo(false);
+            return;
+        }
+        let events: Vec<DemoEvent> = demo.events_at(self.frame).copied().collect();
+        for event in events {
+            self.dispatch_input(event.input, event.state);
+        }
+    }
+
+    fn finish_demo(&mut self) {
+        if let DemoMode::Record(ref path, ref demo) = self.demo {
+            match demo.save(path) {
+                Ok(()) => eprintln!("saved demo to {}", path.display()),
+                Err(e) => eprintln!("failed to save demo: {e}"),
+            }
+        }
+    }
+
+    fn finish_input_log(&mut self) {
+        if let Some(ref mut log) = self.input_log {
+            log.flush();
+        }
+    }
+
+    fn poll_gamepad(&mut self) {
+        let Some(mut gilrs) = self.gilrs.take() else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            let (button, state) = match event.event {
+                EventType::ButtonPressed(button, _) => (pad_button(button), ElementState::Pressed),
+                EventType::ButtonReleased(button, _) => {
+                    (pad_button(button), ElementState::Released)
+                }
+                EventType::AxisChanged(Axis::LeftStickX | Axis::LeftStickY, _, _) => {
+                    let pad = gilrs.gamepad(event.id);
+                    let x = pad.value(Axis::LeftStickX);
+                    let y = pad.value(Axis::LeftStickY);
+                    let nudge = x.abs() > STICK_DEADZONE || y.abs() > STICK_DEADZONE;
+                    if nudge == self.stick_nudge {
+                        continue;
+                    }
+                    self.stick_nudge = nudge;
+                    (
+                        Some(PadButton::Nudge),
+                        if nudge {
+                            ElementState::Pressed
+                        } else {
+                            ElementState::Released
+                        },
+                    )
+                }
+                _ => continue,
+            };
+            if let Some(button) = button {
+                self.send_input(DemoInput::Button(button), state);
+            }
+        }
+        self.gilrs = Some(gilrs);
+    }
 }
 
 #[derive(Parser)]
 struct Args {
     data: PathBuf,
+    #[arg(value_parser = clap::value_parser!(u8).range(1..=4))]
     table: Option<u8>,
+    #[arg(
+        long = "table",
+        value_name = "N",
+        conflicts_with = "table",
+        value_parser = clap::value_parser!(u8).range(1..=4)
+    )]
+    table_flag: Option<u8>,
+    #[arg(long, default_value_t = 600)]
+    gif_frames: usize,
+    #[arg(long)]
+    record: Option<PathBuf>,
+    #[arg(long, conflicts_with = "record")]
+    play: Option<PathBuf>,
+    #[arg(long, conflicts_with = "play")]
+    seed: Option<u64>,
+    #[arg(long)]
+    status_file: Option<PathBuf>,
+    #[arg(long, value_name = "PATH")]
+    log_input: Option<PathBuf>,
+    #[arg(long)]
+    print_display_text: bool,
+    #[arg(long, value_name = "ADDR", value_parser = parse_addr)]
+    serve_spectators: Option<String>,
+    #[arg(
+        long,
+        value_name = "ADDR",
+        value_parser = parse_addr,
+        conflicts_with_all = ["serve_spectators", "record", "play", "table", "table_flag"]
+    )]
+    spectate: Option<String>,
+    #[arg(long, value_name = "NAME")]
+    audio_device: Option<String>,
+    #[arg(long, value_name = "low|medium|high|MS", value_parser = parse_latency)]
+    audio_latency: Option<AudioLatency>,
+    #[arg(long, value_name = "on|off", value_parser = parse_on_off)]
+    vsync: Option<bool>,
+    #[arg(long, value_name = "FPS", value_parser = parse_fps_cap)]
+    fps_cap: Option<u16>,
+    #[arg(long, value_name = "on|off", value_parser = parse_on_off)]
+    run_ahead: Option<bool>,
+    #[arg(long, value_name = "LANG", value_parser = parse_language)]
+    language: Option<Language>,
+    #[arg(long, value_name = "TABLEn=MOD", value_parser = parse_music)]
+    music: Vec<(TableId, PathBuf)>,
+    #[arg(long)]
+    list_audio_devices: bool,
+    #[arg(long, conflicts_with = "audio_device")]
+    no_audio: bool,
+    #[arg(long)]
+    tournament: bool,
+    #[arg(long, value_name = "WAV")]
+    export_audio: Option<PathBuf>,
+    #[arg(long, default_value_t = 180)]
+    export_seconds: u32,
+    #[arg(
+        long,
+        default_value_t = 48000,
+        value_parser = clap::value_parser!(u32).range(8000..=192000)
+    )]
+    sample_rate: u32,
+}
+
+fn parse_music(arg: &str) -> Result<(TableId, PathBuf), String> {
+    let (table, path) = arg.split_once('=').ok_or("expected TABLEn=<module path>")?;
+    let table = match table.to_ascii_uppercase().as_str() {
+        "TABLE1" | "1" => TableId::Table1,
+        "TABLE2" | "2" => TableId::Table2,
+        "TABLE3" | "3" => TableId::Table3,
+        "TABLE4" | "4" => TableId::Table4,
+        _ => return Err(format!("unknown table {table}, expected TABLE1 to TABLE4")),
+    };
+    Ok((table, PathBuf::from(path)))
+}
+
+// HOST or HOST:PORT
+fn parse_addr(arg: &str) -> Result<String, String> {
+    if arg.is_empty() {
+        return Err("expected a host name or address".to_string());
+    }
+    match arg.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => Ok(arg.to_string()),
+        _ => Ok(format!("{arg}:{DEFAULT_PORT}")),
+    }
+}
+
+fn parse_on_off(arg: &str) -> Result<bool, String> {
+    match arg.to_ascii_lowercase().as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err("expected on or off".to_string()),
+    }
+}
+
+fn parse_fps_cap(arg: &str) -> Result<u16, String> {
+    match arg.parse() {
+        Ok(fps) if fps == 0 || (MIN_FPS_CAP..=MAX_FPS_CAP).contains(&fps) => Ok(fps),
+        _ => Err(format!(
+            "expected {MIN_FPS_CAP} to {MAX_FPS_CAP} frames per second, or 0 for no cap"
+        )),
+    }
+}
+
+fn parse_language(arg: &str) -> Result<Language, String> {
+    match arg.to_ascii_lowercase().as_str() {
+        "english" | "en" => Ok(Language::English),
+        "german" | "de" => Ok(Language::German),
+        "french" | "fr" => Ok(Language::French),
+        "swedish" | "sv" => Ok(Language::Swedish),
+        _ => Err("expected english, german, french or swedish".to_string()),
+    }
+}
+
+fn parse_latency(arg: &str) -> Result<AudioLatency, String> {
+    match arg.to_ascii_lowercase().as_str() {
+        "low" => Ok(AudioLatency::Low),
+        "medium" => Ok(AudioLatency::Medium),
+        "high" => Ok(AudioLatency::High),
+        ms => match ms.parse() {
+            Ok(ms) if (MIN_AUDIO_LATENCY..=MAX_AUDIO_LATENCY).contains(&ms) => {
+                Ok(AudioLatency::Custom(ms))
+            }
+            _ => Err(format!(
+                "expected low, medium, high or {MIN_AUDIO_LATENCY} to {MAX_AUDIO_LATENCY} ms"
+            )),
+        },
+    }
+}
+
+fn export_audio(args: &Args, path: &Path) -> std::io::Result<()> {
+    let name = match args.table.or(args.table_flag) {
+        Some(t) => format!("TABLE{t}.MOD"),
+        None => "INTRO.MOD".to_string(),
+    };
+    let data = open_data_source(&args.data)?;
+    let module = pfr::sound::loader::load(&mut Cursor::new(data.read(&name)?))?;
+    render_module_to_wav(module, args.export_seconds, args.sample_rate, path)
 }
 
 fn main() {
     let args = Args::parse();
-    let config = Config::load(&args.data);
+    if args.list_audio_devices {
+        for name in output_devices() {
+            println!("{name}");
+        }
+        return;
+    }
+    if let Some(ref path) = args.export_audio {
+        if let Err(e) = export_audio(&args, path) {
+            eprintln!("cannot export audio to {}: {e}", path.display());
+            std::process::exit(1);
+        }
+        return;
+    }
+    match verify_data_dir(&args.data) {
+        Ok(report) if report.is_complete() => {}
+        Ok(report) => {
+            eprintln!("{report}");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+    let data = open_data_source(&args.data).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    });
+    let config_path = config::config_path();
+    let mut config = match config_path {
+        Some(ref path) if path.exists() => config::load_from_path(path),
+        _ => Config::load(&args.data),
+    };
+    config.tournament = args.tournament;
+    config.no_audio = args.no_audio;
+    // the options given on the command line are remembered in the config file
+    let overridden = args.audio_device.is_some()
+        || args.audio_latency.is_some()
+        || args.vsync.is_some()
+        || args.fps_cap.is_some()
+        || args.run_ahead.is_some()
+        || args.language.is_some()
+        || !args.music.is_empty();
+    if let Some(ref device) = args.audio_device {
+        config.audio_device = Some(device.clone());
+    }
+    if let Some(latency) = args.audio_latency {
+        config.audio_latency = latency;
+    }
+    if let Some(vsync) = args.vsync {
+        config.vsync = vsync;
+    }
+    if let Some(cap) = args.fps_cap {
+        config.fps_cap = (cap != 0).then_some(cap);
+    }
+    if let Some(run_ahead) = args.run_ahead {
+        config.run_ahead = run_ahead;
+    }
+    if let Some(language) = args.language {
+        config.language = language;
+    }
+    if !args.music.is_empty() {
+        config.music.extend(args.music.iter().cloned());
+    }
+    if overridden {
+        if let Some(ref path) = config_path {
+            config::save_to_path(&config, path);
+        }
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let (seed, demo) = if let Some(ref path) = args.play {
+        let demo = Demo::load(path).unwrap_or_else(|e| {
+            eprintln!("cannot load demo {}: {e}", path.display());
+            std::process::exit(1);
+        });
+        config.options = demo.options;
+        (demo.seed, DemoMode::Play(demo))
+    } else if let Some(ref path) = args.record {
+        (
+            seed,
+            DemoMode::Record(path.clone(), Demo::new(seed, config.options)),
+        )
+    } else {
+        (seed, DemoMode::None)
+    };
+    let input_log = args.log_input.as_ref().map(|path| {
+        InputLog::create(path).unwrap_or_else(|e| {
+            eprintln!("cannot create input log {}: {e}", path.display());
+            std::process::exit(1);
+        })
+    });
+    let in_demo = !matches!(demo, DemoMode::None);
+    config.ignore_table_options = in_demo;
+    let spectate_server = args.serve_spectators.as_ref().map(|addr| {
+        SpectateServer::bind(addr).unwrap_or_else(|e| {
+            eprintln!("cannot serve spectators on {addr}: {e}");
+            std::process::exit(1);
+        })
+    });
+    let mut spectated_events = None;
+    let mut game = if let Some(ref addr) = args.spectate {
+        let data: Rc<dyn DataSource> = data.into();
+        let spectated = Box::new(Rc::clone(&data));
+        let spectator = Spectator::connect(addr, spectated, config.clone()).unwrap_or_else(|e| {
+            eprintln!("cannot watch the game at {addr}: {e}");
+            std::process::exit(1);
+        });
+        let (sender, events) = mpsc::channel();
+        spectated_events = Some(events);
+        Game::spectate(Box::new(data), config, spectator.with_events(sender))
+    } else {
+        let route = match args.table.or(args.table_flag) {
+            Some(t) => Route::Table(TABLES[usize::from(t - 1)]),
+            None if config.options.table_select => Route::TableSelect(None),
+            None => Route::Intro(None),
+        };
+        Game::new(data, config, route, seed).unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        })
+    };
+    game.set_in_demo(in_demo);
+    if let Some(ref server) = spectate_server {
+        game.set_event_sender(server.event_sender());
+    }
+    let config = game.config();
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Pinball Fantasies")
@@ -42,87 +955,229 @@ fn main() {
         .build(&event_loop)
         .unwrap();
     window.set_cursor_visible(false);
+    if config.options.fullscreen {
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
     let pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(640, 480, surface_texture).unwrap()
+        PixelsBuilder::new(640, 480, surface_texture)
+            .enable_vsync(config.vsync)
+            .build()
+            .unwrap()
     };
-    let game = Game {
+    // without vsync, frames are paced to the display's refresh rate unless
+    // there's a cap
+    let present_fps = config.fps_cap.map(u32::from).or_else(|| {
+        (!config.vsync).then(|| {
+            window
+                .current_monitor()
+                .and_then(|monitor| monitor.refresh_rate_millihertz())
+                .map_or(60, |millihertz| millihertz.div_ceil(1000))
+        })
+    });
+    let present_interval = present_fps.map(|fps| Duration::from_secs(1) / fps);
+    let fps = game.fps();
+    let mut frontend = Frontend {
         pixels,
+        game,
         args,
-        config,
-        view: None,
+        config_path,
         dims: (640, 480),
+        panel: None,
+        aspect: (1, 1),
+        zoom: 1,
+        mouse_pos: (0, 0),
+        mouse_button: None,
+        status: None,
+        input_log,
+        display_text: vec![],
+        modifiers: ModifiersState::empty(),
+        quick_load: false,
+        gilrs: match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                eprintln!("gamepad support unavailable: {e}");
+                None
+            }
+        },
+        pending_input: vec![],
+        stick_nudge: false,
+        recording: None,
+        frame: 0,
+        frame_stats: None,
+        rendered_frame: None,
+        present_interval,
+        next_present: None,
+        demo,
+        spectate_server,
+        spectated_events,
     };
+    frontend.update_dims(&window);
     game_loop(
         event_loop,
         window,
-        game,
-        60,
-        0.2,
+        frontend,
+        fps,
+        MAX_FRAME_TIME,
         move |g| {
             // update
-            let action = match g.game.view {
-                Some(ref mut view) => view.run_frame(),
-                None => Action::Navigate(match g.game.args.table {
-                    Some(t) => Route::Table(match t {
-                        1 => TableId::Table1,
-                        2 => TableId::Table2,
-                        3 => TableId::Table3,
-                        4 => TableId::Table4,
-                        _ => panic!("weird table"),
-                    }),
-                    None => Route::Intro(None),
-                }),
+            g.game.sync_dims(&g.window);
+            g.game.drain_input();
+            g.game.play_demo_frame();
+            if std::mem::take(&mut g.game.quick_load) && g.game.load_quick_save() {
+                g.set_updates_per_second(g.game.game.fps());
+                g.game.update_dims(&g.window);
+            }
+            let action = match g.game.game.tick() {
+                Ok(action) => action,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    g.game.finish_recording();
+                    g.game.finish_demo();
+                    g.game.finish_input_log();
+                    std::process::exit(1);
+                }
             };
+            g.game.measure_frame();
+            g.game.record_frame();
+            g.game.poll_status();
+            g.game.poll_spectate();
+            g.game.poll_spectated_events();
+            g.game.poll_input_log();
+            g.game.poll_display_text();
+            g.game.frame += 1;
+            let playing = matches!(g.game.demo, DemoMode::Play(_));
             match action {
                 Action::None => {}
-                Action::Navigate(route) => {
-                    let view: Box<dyn View> = match route {
-                        Route::Intro(table) => {
-                            Box::new(Intro::new(&g.game.args.data, g.game.config, table))
-                        }
-                        Route::Table(table) => {
-                            Box::new(Table::new(&g.game.args.data, g.game.config, table))
-                        }
-                    };
-                    g.set_updates_per_second(view.get_fps());
-                    let dims = view.get_resolution();
-                    g.window.set_resizable(true);
-                    // g.window.set_inner_size(PhysicalSize::new(dims.0, dims.1));
-                    g.game.pixels.resize_buffer(dims.0, dims.1).unwrap();
-                    g.game.dims = dims;
-                    g.game.view = Some(view)
-                }
-                Action::Exit => g.exit(),
-                Action::SaveOptions(options) => {
-                    options.save(&g.game.args.data);
-                    g.game.config.options = options;
-                }
-                Action::SaveHighScores(table, high_scores) => {
-                    save_high_scores(table, high_scores, &g.game.args.data);
-                    g.game.config.high_scores[table] = high_scores;
+                Action::Navigate(_) | Action::RewindBall(_) => {
+                    g.game.save_stored_high_scores();
+                    g.set_updates_per_second(g.game.game.fps());
+                    g.game.update_dims(&g.window);
+                }
+                Action::Exit => {
+                    g.game.finish_recording();
+                    g.game.finish_demo();
+                    g.game.finish_input_log();
+                    g.exit();
+                }
+                Action::SaveOptions(_) => {
+                    g.set_updates_per_second(g.game.game.fps());
+                    g.game.apply_window_scale(&g.window);
+                    if !playing {
+                        g.game.game.config().options.save(&g.game.args.data);
+                        g.game.save_config();
+                    }
+                }
+                Action::SaveHighScores(table, _) => g.game.save_high_scores(table),
+                Action::ResetHighScores => {
+                    for table in TABLES {
+                        g.game.save_high_scores(table);
+                    }
                 }
             }
         },
         |g| {
             // render
+            g.game.pace_frame();
+            // Frames are palette-indexed, so there is nothing to interpolate
+            // between simulation steps: when the display refreshes faster than
+            // the game runs, the last frame is presented again as is.
+            if g.game.rendered_frame == Some(g.game.frame) {
+                g.game.pixels.render().unwrap();
+                return;
+            }
+            g.game.rendered_frame = Some(g.game.frame);
+            g.game.sync_dims(&g.window);
+            let (mut data, mut pal) = g.game.render_displayed();
+            g.game.render_frame_stats(&mut data, &mut pal);
+            let panel = g.game.render_panel();
             let frame = g.game.pixels.frame_mut();
             let width = g.game.dims.0 as usize;
             let height = g.game.dims.1 as usize;
-            let mut data = vec![0u8; width * height];
-            let mut pal = [(0u8, 0u8, 0u8); 256];
-            if let Some(ref view) = g.game.view {
-                view.render(&mut data, &mut pal);
+            let panel_width = panel.as_ref().map_or(0, |panel| panel.width);
+            let frame_width = width + panel_width;
+            let zoom = g.game.zoom as usize;
+            let (sx, sy) = (
+                g.game.aspect.0 as usize * zoom,
+                g.game.aspect.1 as usize * zoom,
+            );
+            let stride = frame_width * sx;
+            let crt = g.game.game.config().options.crt_filter;
+            // with smooth scrolling, the board rows are blended into the ones
+            // below to show the fractional scroll position
+            let (blend_rows_end, frac) = g.game.game.scroll_blend().unwrap_or((0, 0));
+            let blended = |y: usize| y + 1 < blend_rows_end;
+            if !crt {
+                let lut = rgba_lut(&pal);
+                let panel_lut = panel.as_ref().map(|panel| rgba_lut(&panel.pal));
+                let mut line = vec![0u8; frame_width * 4];
+                let mut next = vec![0u8; width * 4];
+                for y in 0..height {
+                    let (view_line, panel_line) = line.split_at_mut(width * 4);
+                    expand(&lut, &data[y * width..(y + 1) * width], view_line);
+                    if blended(y) {
+                        expand(&lut, &data[(y + 1) * width..(y + 2) * width], &mut next);
+                        blend_rows(view_line, &next, frac);
+                    }
+                    if let (Some(panel), Some(panel_lut)) = (&panel, &panel_lut) {
+                        let src = &panel.data[y * panel_width..(y + 1) * panel_width];
+                        expand(panel_lut, src, panel_line);
+                    }
+                    for dy in 0..sy {
+                        let row = &mut frame[(y * sy + dy) * stride * 4..][..stride * 4];
+                        if sx == 1 {
+                            row.copy_from_slice(&line);
+                        } else {
+                            for (out, rgba) in
+                                row.chunks_exact_mut(sx * 4).zip(line.chunks_exact(4))
+                            {
+                                for out in out.chunks_exact_mut(4) {
+                                    out.copy_from_slice(rgba);
+                                }
+                            }
+                        }
+                    }
+                }
+                g.game.pixels.render().unwrap();
+                return;
             }
+            let color = |x: usize, y: usize| {
+                if let Some(ref panel) = panel {
+                    if x >= width {
+                        return panel.pal[usize::from(panel.data[y * panel_width + x - width])];
+                    }
+                }
+                let color = pal[usize::from(data[y * width + x])];
+                if blended(y) {
+                    mix(color, pal[usize::from(data[(y + 1) * width + x])], frac)
+                } else {
+                    color
+                }
+            };
             for y in 0..height {
-                for x in 0..width {
-                    let pidx = y * width + x;
-                    let pixel = usize::from(data[pidx]);
-                    frame[pidx * 4] = pal[pixel].0;
-                    frame[pidx * 4 + 1] = pal[pixel].1;
-                    frame[pidx * 4 + 2] = pal[pixel].2;
-                    frame[pidx * 4 + 3] = 0xff;
+                for x in 0..frame_width {
+                    let (red, green, blue) = color(x, y);
+                    let mut rgba = [red, green, blue, 0xff];
+                    if x > 0 {
+                        // phosphor bleed from the pixel on the left
+                        let left = color(x - 1, y);
+                        for (c, l) in rgba.iter_mut().zip([left.0, left.1, left.2]) {
+                            *c = ((u16::from(*c) * 7 + u16::from(l)) / 8) as u8;
+                        }
+                    }
+                    for dy in 0..sy {
+                        let mut rgba = rgba;
+                        if sy > 1 && dy >= sy - (sy / 3).max(1) {
+                            for c in &mut rgba[..3] {
+                                *c = (u16::from(*c) * CRT_SCANLINE / 0x100) as u8;
+                            }
+                        }
+                        for dx in 0..sx {
+                            let pidx = (y * sy + dy) * stride + x * sx + dx;
+                            frame[pidx * 4..pidx * 4 + 4].copy_from_slice(&rgba);
+                        }
+                    }
                 }
             }
             g.game.pixels.render().unwrap();
@@ -134,6 +1189,10 @@ fn main() {
                     event: WindowEvent::CloseRequested,
                     ..
                 } => {
+                    g.game.save_unsaved_high_scores();
+                    g.game.finish_recording();
+                    g.game.finish_demo();
+                    g.game.finish_input_log();
                     g.exit();
                 }
                 Event::WindowEvent {
@@ -144,6 +1203,7 @@ fn main() {
                         .pixels
                         .resize_surface(size.width, size.height)
                         .unwrap();
+                    g.game.resize_buffer(*size);
                 }
                 Event::WindowEvent {
                     event:
@@ -158,23 +1218,82 @@ fn main() {
                         },
                     ..
                 } => {
-                    if let Some(ref mut view) = g.game.view {
-                        view.handle_key(*key, *state);
+                    if g.game.modifiers.ctrl()
+                        && matches!(key, VirtualKeyCode::F5 | VirtualKeyCode::F9)
+                    {
+                        if *state == ElementState::Pressed {
+                            if *key == VirtualKeyCode::F5 {
+                                g.game.quick_save();
+                            } else {
+                                g.game.quick_load = true;
+                            }
+                        }
+                    } else if g.game.modifiers.alt() && *key == VirtualKeyCode::Return {
+                        if *state == ElementState::Pressed {
+                            g.game.toggle_fullscreen(&g.window);
+                        }
+                    } else if g.game.modifiers.ctrl() && *key == VirtualKeyCode::F6 {
+                        if *state == ElementState::Pressed {
+                            g.game.cycle_resolution(&g.window);
+                        }
+                    } else if g.game.modifiers.ctrl() && *key == VirtualKeyCode::F12 {
+                        if *state == ElementState::Pressed {
+                            g.game.toggle_frame_stats();
+                        }
+                    } else if *key == VirtualKeyCode::Scroll {
+                        if *state == ElementState::Pressed {
+                            g.game.toggle_recording();
+                        }
+                    } else if *key == VirtualKeyCode::Snapshot {
+                        // PrintScreen only reports releases on some platforms.
+                        if *state == ElementState::Released {
+                            match g.game.save_screenshot() {
+                                Ok(path) => eprintln!("saved screenshot to {}", path.display()),
+                                Err(e) => eprintln!("failed to save screenshot: {e}"),
+                            }
+                        }
+                    } else {
+                        g.game.queue_input(DemoInput::Key(*key), *state);
                     }
                 }
                 Event::WindowEvent {
-                    event: WindowEvent::MouseInput { button, state, .. },
+                    event: WindowEvent::ModifiersChanged(modifiers),
+                    ..
+                } => g.game.modifiers = *modifiers,
+                Event::WindowEvent {
+                    event: WindowEvent::Focused(false),
                     ..
                 } => {
-                    if let Some(ref mut view) = g.game.view {
-                        if &MouseButton::Left == button {
-                            view.handle_key(VirtualKeyCode::LShift, *state);
-                        }
-                        if &MouseButton::Right == button {
-                            view.handle_key(VirtualKeyCode::RShift, *state);
+                    g.game.modifiers = ModifiersState::empty();
+                    g.game.mouse_button = None;
+                    g.game
+                        .queue_input(DemoInput::FocusLost, ElementState::Released);
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } => {
+                    let pos = g.game.framebuffer_pos(position.x as f32, position.y as f32);
+                    if pos != g.game.mouse_pos {
+                        g.game.mouse_pos = pos;
+                        // while a button is held, motion is reported as a repeated press
+                        if let Some(button) = g.game.mouse_button {
+                            g.game
+                                .queue_input(DemoInput::Mouse(pos, button), ElementState::Pressed);
                         }
                     }
                 }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseInput { button, state, .. },
+                    ..
+                } => {
+                    g.game.mouse_button = match state {
+                        ElementState::Pressed => Some(*button),
+                        ElementState::Released => None,
+                    };
+                    let pos = g.game.mouse_pos;
+                    g.game.queue_input(DemoInput::Mouse(pos, *button), *state);
+                }
 
                 _ => {}
             }
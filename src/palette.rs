@@ -0,0 +1,97 @@
+// Expansion of palette-indexed frames to the RGBA bytes presented on screen.
+
+pub type RgbaLut = [u32; 256];
+
+pub fn rgba_lut(pal: &[(u8, u8, u8)]) -> RgbaLut {
+    let mut lut = [0; 256];
+    for (entry, &(red, green, blue)) in lut.iter_mut().zip(pal) {
+        *entry = u32::from_ne_bytes([red, green, blue, 0xff]);
+    }
+    lut
+}
+
+pub fn expand_naive(pal: &[(u8, u8, u8)], src: &[u8], dst: &mut [u8]) {
+    for (&pixel, out) in src.iter().zip(dst.chunks_exact_mut(4)) {
+        let (red, green, blue) = pal[usize::from(pixel)];
+        out.copy_from_slice(&[red, green, blue, 0xff]);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub fn expand(lut: &RgbaLut, src: &[u8], dst: &mut [u8]) {
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU has AVX2
+        unsafe { expand_avx2(lut, src, dst) }
+    } else {
+        expand_scalar(lut, src, dst);
+    }
+}
+
+// Looks up eight pixels at once with a single gather from the table.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn expand_avx2(lut: &RgbaLut, src: &[u8], dst: &mut [u8]) {
+    use std::arch::x86_64::{
+        _mm256_cvtepu8_epi32, _mm256_i32gather_epi32, _mm256_storeu_si256, _mm_loadl_epi64,
+    };
+    let len = src.len().min(dst.len() / 4);
+    let src_chunks = src[..len].chunks_exact(8);
+    let src_rest = src_chunks.remainder();
+    let mut dst_chunks = dst[..len * 4].chunks_exact_mut(32);
+    for (pixels, out) in src_chunks.zip(&mut dst_chunks) {
+        // the loads and stores are unaligned and stay within the 8 source
+        // and 32 destination bytes of the chunks; the indices are below 256
+        let idx = _mm256_cvtepu8_epi32(_mm_loadl_epi64(pixels.as_ptr().cast()));
+        let rgba = _mm256_i32gather_epi32::<4>(lut.as_ptr().cast(), idx);
+        _mm256_storeu_si256(out.as_mut_ptr().cast(), rgba);
+    }
+    expand_scalar(lut, src_rest, dst_chunks.into_remainder());
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+pub fn expand(lut: &RgbaLut, src: &[u8], dst: &mut [u8]) {
+    expand_scalar(lut, src, dst);
+}
+
+pub fn nearest_color(
+    pal: &[(u8, u8, u8)],
+    color: (u8, u8, u8),
+    usable: impl Fn(usize) -> bool,
+) -> u8 {
+    let dist = |&(red, green, blue): &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(red, color.0) + d(green, color.1) + d(blue, color.2)
+    };
+    pal.iter()
+        .enumerate()
+        .filter(|&(idx, _)| usable(idx))
+        .min_by_key(|(_, entry)| dist(entry))
+        .map_or(0, |(idx, _)| idx as u8)
+}
+
+// Weights in sixteenths, for fractional scroll positions.
+pub fn mix(a: (u8, u8, u8), b: (u8, u8, u8), frac: u8) -> (u8, u8, u8) {
+    (
+        mix_channel(a.0, b.0, frac),
+        mix_channel(a.1, b.1, frac),
+        mix_channel(a.2, b.2, frac),
+    )
+}
+
+pub fn blend_rows(dst: &mut [u8], next: &[u8], frac: u8) {
+    for (out, rgba) in dst.chunks_exact_mut(4).zip(next.chunks_exact(4)) {
+        for (c, &n) in out[..3].iter_mut().zip(&rgba[..3]) {
+            *c = mix_channel(*c, n, frac);
+        }
+    }
+}
+
+fn mix_channel(a: u8, b: u8, frac: u8) -> u8 {
+    ((u16::from(a) * u16::from(16 - frac) + u16::from(b) * u16::from(frac)) / 16) as u8
+}
+
+fn expand_scalar(lut: &RgbaLut, src: &[u8], dst: &mut [u8]) {
+    for (&pixel, out) in src.iter().zip(dst.chunks_exact_mut(4)) {
+        out.copy_from_slice(&lut[usize::from(pixel)].to_ne_bytes());
+    }
+}
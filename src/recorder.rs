@@ -0,0 +1,65 @@
+use std::{borrow::Cow, fs::File, io::BufWriter, path::Path};
+
+pub struct GifRecorder {
+    dims: (u32, u32),
+    fps: u32,
+    budget: usize,
+    stride: u32,
+    skipped: u32,
+    frames: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl GifRecorder {
+    pub fn new(dims: (u32, u32), fps: u32, budget: usize) -> Self {
+        GifRecorder {
+            dims,
+            fps,
+            budget: budget.max(2),
+            stride: 1,
+            skipped: 0,
+            frames: vec![],
+        }
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        self.dims
+    }
+
+    pub fn push(&mut self, data: &[u8], pal: &[(u8, u8, u8)]) {
+        if self.skipped + 1 < self.stride {
+            self.skipped += 1;
+            return;
+        }
+        self.skipped = 0;
+        if self.frames.len() >= self.budget {
+            let mut i = 0;
+            self.frames.retain(|_| {
+                i += 1;
+                i % 2 == 1
+            });
+            self.stride *= 2;
+        }
+        let pal = pal.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+        self.frames.push((data.to_vec(), pal));
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), gif::EncodingError> {
+        let width = self.dims.0 as u16;
+        let height = self.dims.1 as u16;
+        let delay = ((100 * self.stride + self.fps / 2) / self.fps).max(1) as u16;
+        let w = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(w, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for (data, pal) in &self.frames {
+            encoder.write_frame(&gif::Frame {
+                delay,
+                width,
+                height,
+                palette: Some(pal.clone()),
+                buffer: Cow::Borrowed(data),
+                ..Default::default()
+            })?;
+        }
+        Ok(())
+    }
+}
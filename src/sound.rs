@@ -2,7 +2,7 @@ pub mod controller;
 pub mod loader;
 pub mod player;
 
-use std::{fmt::Display, num::NonZeroU8};
+use std::{fmt::Display, io, num::NonZeroU8, path::Path};
 
 pub const PERIODS: [[u16; 36]; 16] = [
     [
@@ -198,3 +198,35 @@ impl Display for Note {
         Ok(())
     }
 }
+
+pub fn render_module_to_wav(
+    module: Mod,
+    seconds: u32,
+    sample_rate: u32,
+    path: &Path,
+) -> io::Result<()> {
+    // four bytes per stereo frame, and the RIFF header's size field counts 36
+    // more
+    let frames = u64::from(seconds) * u64::from(sample_rate);
+    if frames * 4 > u64::from(u32::MAX) - 36 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{seconds} seconds at {sample_rate} Hz is more than a WAV file can hold"),
+        ));
+    }
+    let samples = player::render(module, sample_rate, frames as usize);
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let wav_error = io::Error::other;
+    let mut writer = hound::WavWriter::create(path, spec).map_err(wav_error)?;
+    for sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)
+}
@@ -7,8 +7,15 @@ use super::{MiscEffect, Mod, Note, ToneEffect, VolumeEffect};
 pub struct Controller {
     ticks: AtomicU32,
     volume: AtomicU32,
+    music_volume: AtomicU32,
+    sfx_volume: AtomicU32,
     sfx: AtomicU32,
+    sfx_muted: AtomicBool,
     paused: AtomicBool,
+    tempo_scale: AtomicU32,
+    pitch_scale: AtomicU32,
+    buffer_frames: AtomicU32,
+    underruns: AtomicU32,
 }
 
 impl Controller {
@@ -16,8 +23,15 @@ impl Controller {
         Self {
             ticks: AtomicU32::new(0),
             sfx: AtomicU32::new(0),
+            sfx_muted: AtomicBool::new(false),
             volume: AtomicU32::new(0x100),
+            music_volume: AtomicU32::new(0x100),
+            sfx_volume: AtomicU32::new(0x100),
             paused: AtomicBool::new(false),
+            tempo_scale: AtomicU32::new(1.0f32.to_bits()),
+            pitch_scale: AtomicU32::new(1.0f32.to_bits()),
+            buffer_frames: AtomicU32::new(0),
+            underruns: AtomicU32::new(0),
         }
     }
 
@@ -39,6 +53,24 @@ impl Controller {
         self.volume.load(Ordering::Relaxed)
     }
 
+    pub fn set_music_volume(&self, volume: u32) {
+        assert!(volume <= 0x100);
+        self.music_volume.store(volume, Ordering::Relaxed);
+    }
+
+    pub fn music_volume(&self) -> u32 {
+        self.music_volume.load(Ordering::Relaxed)
+    }
+
+    pub fn set_sfx_volume(&self, volume: u32) {
+        assert!(volume <= 0x100);
+        self.sfx_volume.store(volume, Ordering::Relaxed);
+    }
+
+    pub fn sfx_volume(&self) -> u32 {
+        self.sfx_volume.load(Ordering::Relaxed)
+    }
+
     pub fn pause(&self) {
         self.paused.store(true, Ordering::Relaxed);
     }
@@ -51,7 +83,45 @@ impl Controller {
         self.paused.load(Ordering::Relaxed)
     }
 
+    pub fn set_tempo_scale(&self, scale: f32) {
+        assert!(scale > 0.0 && scale.is_finite());
+        self.tempo_scale.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn tempo_scale(&self) -> f32 {
+        f32::from_bits(self.tempo_scale.load(Ordering::Relaxed))
+    }
+
+    pub fn set_pitch_scale(&self, scale: f32) {
+        assert!(scale > 0.0 && scale.is_finite());
+        self.pitch_scale.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn pitch_scale(&self) -> f32 {
+        f32::from_bits(self.pitch_scale.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn set_buffer_frames(&self, frames: u32) {
+        self.buffer_frames.store(frames, Ordering::Relaxed);
+    }
+
+    // the size of the last buffer the output asked for, in stereo frames
+    pub fn buffer_frames(&self) -> u32 {
+        self.buffer_frames.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn incr_underruns(&self) {
+        self.underruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn underruns(&self) -> u32 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
     pub fn play_sfx(&self, sfx: Sfx, volume: u8) {
+        if self.sfx_muted.load(Ordering::Relaxed) {
+            return;
+        }
         let val = (sfx.period as u32)
             | (sfx.sample as u32) << 8
             | (volume as u32) << 16
@@ -59,6 +129,11 @@ impl Controller {
         self.sfx.store(val, Ordering::Relaxed);
     }
 
+    // for frames that are run and then undone
+    pub fn set_sfx_muted(&self, muted: bool) {
+        self.sfx_muted.store(muted, Ordering::Relaxed);
+    }
+
     pub(super) fn get_sfx(&self) -> Option<(usize, Note)> {
         let sfx = self.sfx.swap(0, Ordering::Relaxed);
         if sfx != 0 {
@@ -127,6 +202,10 @@ impl Sequencer for SimpleSequencer {
 
 pub struct TableSequencer {
     state: AtomicU32,
+    // what the game sees while running a frame ahead, so that the player
+    // doesn't hear of it
+    ahead_state: AtomicU32,
+    running_ahead: AtomicBool,
     position_jingle_start: u8,
     position_silence: u8,
 }
@@ -186,14 +265,35 @@ impl TableSequencer {
                 }
                 .into(),
             ),
+            ahead_state: AtomicU32::new(0),
+            running_ahead: AtomicBool::new(false),
             position_jingle_start,
             position_silence,
         }
     }
 
+    fn game_state(&self) -> &AtomicU32 {
+        if self.running_ahead.load(Ordering::Relaxed) {
+            &self.ahead_state
+        } else {
+            &self.state
+        }
+    }
+
+    // Until end_ahead, changes only go to a copy of the state.
+    pub fn begin_ahead(&self) {
+        self.ahead_state
+            .store(self.state.load(Ordering::Acquire), Ordering::Relaxed);
+        self.running_ahead.store(true, Ordering::Relaxed);
+    }
+
+    pub fn end_ahead(&self) {
+        self.running_ahead.store(false, Ordering::Relaxed);
+    }
+
     pub fn play_jingle(&self, jingle: Jingle, force: bool, music: Option<u8>) -> bool {
         assert!(jingle.position < 0x80);
-        let mut val = self.state.load(Ordering::Acquire);
+        let mut val = self.game_state().load(Ordering::Acquire);
         loop {
             let mut state = State::from(val);
             if jingle.priority < state.priority && !force {
@@ -210,7 +310,7 @@ impl TableSequencer {
                 assert!(music < 0x80);
                 state.music = music;
             }
-            match self.state.compare_exchange(
+            match self.game_state().compare_exchange(
                 val,
                 state.into(),
                 Ordering::Release,
@@ -224,11 +324,11 @@ impl TableSequencer {
 
     pub fn set_music(&self, position: u8) {
         assert!(position < 0x80);
-        let mut val = self.state.load(Ordering::Acquire);
+        let mut val = self.game_state().load(Ordering::Acquire);
         loop {
             let mut state = State::from(val);
             state.music = position;
-            match self.state.compare_exchange(
+            match self.game_state().compare_exchange(
                 val,
                 state.into(),
                 Ordering::Release,
@@ -241,11 +341,11 @@ impl TableSequencer {
     }
 
     pub fn reset_priority(&self) {
-        let mut val = self.state.load(Ordering::Acquire);
+        let mut val = self.game_state().load(Ordering::Acquire);
         loop {
             let mut state = State::from(val);
             state.priority = 0;
-            match self.state.compare_exchange(
+            match self.game_state().compare_exchange(
                 val,
                 state.into(),
                 Ordering::Release,
@@ -258,11 +358,11 @@ impl TableSequencer {
     }
 
     pub fn set_no_music(&self, flag: bool) {
-        let mut val = self.state.load(Ordering::Acquire);
+        let mut val = self.game_state().load(Ordering::Acquire);
         loop {
             let mut state = State::from(val);
             state.no_music = flag;
-            match self.state.compare_exchange(
+            match self.game_state().compare_exchange(
                 val,
                 state.into(),
                 Ordering::Release,
@@ -275,14 +375,14 @@ impl TableSequencer {
     }
 
     pub fn force_end_loop(&self) {
-        let mut val = self.state.load(Ordering::Acquire);
+        let mut val = self.game_state().load(Ordering::Acquire);
         loop {
             let mut state = State::from(val);
             if state.repeat != 0 {
                 return;
             }
             state.repeat = 1;
-            match self.state.compare_exchange(
+            match self.game_state().compare_exchange(
                 val,
                 state.into(),
                 Ordering::Release,
@@ -294,16 +394,36 @@ impl TableSequencer {
         }
     }
 
+    pub fn save(&self) -> u32 {
+        self.game_state().load(Ordering::Acquire)
+    }
+
+    // Puts the sequencer back into a saved state and makes the player jump to
+    // the saved position.
+    pub fn restore(&self, saved: u32) {
+        let mut state = State::from(saved);
+        state.interrupt = true;
+        self.game_state().store(state.into(), Ordering::Release);
+    }
+
+    pub fn position(&self) -> u8 {
+        State::from(self.game_state().load(Ordering::Acquire)).position
+    }
+
+    pub fn repeat(&self) -> u8 {
+        State::from(self.game_state().load(Ordering::Acquire)).repeat
+    }
+
     pub fn music(&self) -> u8 {
-        State::from(self.state.load(Ordering::Acquire)).music
+        State::from(self.game_state().load(Ordering::Acquire)).music
     }
 
     pub fn priority(&self) -> u8 {
-        State::from(self.state.load(Ordering::Acquire)).priority
+        State::from(self.game_state().load(Ordering::Acquire)).priority
     }
 
     pub fn jingle_playing(&self) -> bool {
-        State::from(self.state.load(Ordering::Acquire)).repeat != 0
+        State::from(self.game_state().load(Ordering::Acquire)).repeat != 0
     }
 }
 
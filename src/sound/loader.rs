@@ -2,22 +2,42 @@ use std::{
     array,
     io::{self, Read, Seek},
     num::NonZeroU8,
-    str,
 };
 
 use arrayref::array_ref;
 
 use super::{MiscEffect, Mod, Note, Sample, ToneEffect, VolumeEffect, PERIODS};
 
-impl From<u32> for Note {
-    fn from(value: u32) -> Self {
+const NUM_SAMPLES: usize = 31;
+
+fn bad(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn name_from_bytes(buf: &[u8]) -> String {
+    String::from_utf8_lossy(buf)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+impl TryFrom<u32> for Note {
+    type Error = io::Error;
+
+    fn try_from(value: u32) -> io::Result<Self> {
         let period = (value >> 16 & 0xfff) as u16;
         let mut period = if period == 0 {
             None
         } else {
-            Some(PERIODS[0].iter().position(|&x| x == period).unwrap() as u8)
+            let pos = PERIODS[0]
+                .iter()
+                .position(|&x| x == period)
+                .ok_or_else(|| bad(format!("unknown period {period}")))?;
+            Some(pos as u8)
         };
         let sample = (value >> 24 & 0xf0 | value >> 12 & 0xf) as u8;
+        if usize::from(sample) > NUM_SAMPLES {
+            return Err(bad(format!("no sample {sample}")));
+        }
         let mut sample = if sample == 0 { None } else { Some(sample) };
         let effect = value & 0xfff;
         let effect_arg = (effect & 0xff) as u8;
@@ -109,25 +129,22 @@ impl From<u32> for Note {
             0xd => misc_effect = MiscEffect::PatternBreak(effect_arg),
             0xe if effect_arg_hi == 9 => misc_effect = MiscEffect::RetrigNote(effect_arg_lo),
             0xf => misc_effect = MiscEffect::SetSpeed(effect_arg),
-            _ => panic!("unknown effect {effect:03x}"),
+            _ => return Err(bad(format!("unknown effect {effect:03x}"))),
         }
-        Note {
+        Ok(Note {
             period,
             sample,
             tone_effect,
             volume_effect,
             misc_effect,
-        }
+        })
     }
 }
 
 pub fn load(f: &mut (impl Read + Seek)) -> io::Result<Mod> {
     let mut name = [0; 20];
     f.read_exact(&mut name)?;
-    let name = str::from_utf8(&name)
-        .unwrap()
-        .trim_end_matches('\0')
-        .to_string();
+    let name = name_from_bytes(&name);
     let mut sample_lens = vec![0];
     let mut samples = vec![Sample {
         name: "".into(),
@@ -136,23 +153,29 @@ pub fn load(f: &mut (impl Read + Seek)) -> io::Result<Mod> {
         volume: 0,
         repeat: None,
     }];
-    for _ in 0..31 {
+    for i in 1..=NUM_SAMPLES {
         let mut buf = [0; 30];
         f.read_exact(&mut buf)?;
-        sample_lens.push(u16::from_be_bytes(*array_ref![buf, 22, 2]) as usize * 2);
-        assert_eq!(buf[24] & 0xf0, 0);
+        // samples this short are skipped below
+        let len = match u16::from_be_bytes(*array_ref![buf, 22, 2]) as usize * 2 {
+            len if len <= 2 => 0,
+            len => len,
+        };
+        sample_lens.push(len);
+        if buf[24] & 0xf0 != 0 {
+            return Err(bad(format!("sample {i} has finetune {}", buf[24])));
+        }
         let rep_pos = u16::from_be_bytes(*array_ref![buf, 26, 2]) as usize * 2;
         let rep_len = u16::from_be_bytes(*array_ref![buf, 28, 2]) as usize * 2;
-        let repeat = if rep_pos == 0 && rep_len == 2 {
+        // loops running past the end of the sample are cut short, as other
+        // players do
+        let repeat = if rep_pos == 0 && rep_len == 2 || rep_len == 0 || rep_pos >= len {
             None
         } else {
-            Some((rep_pos, rep_len))
+            Some((rep_pos, rep_len.min(len - rep_pos)))
         };
         samples.push(Sample {
-            name: str::from_utf8(&buf[..22])
-                .unwrap()
-                .trim_end_matches('\0')
-                .to_string(),
+            name: name_from_bytes(&buf[..22]),
             data: vec![],
             finetune: buf[24],
             volume: buf[25],
@@ -163,9 +186,14 @@ pub fn load(f: &mut (impl Read + Seek)) -> io::Result<Mod> {
     f.read_exact(&mut buf)?;
     let song_len = buf[0];
     let pos_restart = if buf[1] == 127 { 0 } else { buf[1] };
-    assert!(song_len <= 128);
-    assert!(song_len != 0);
-    assert!(pos_restart < song_len);
+    if song_len == 0 || song_len > 128 {
+        return Err(bad(format!("song length {song_len}")));
+    }
+    if pos_restart >= song_len {
+        return Err(bad(format!(
+            "restart position {pos_restart} past the song end"
+        )));
+    }
     let positions = &buf[2..130];
     let num_patterns = positions.iter().copied().max().unwrap() as usize + 1;
     let positions = positions[..song_len as usize].to_vec();
@@ -173,11 +201,12 @@ pub fn load(f: &mut (impl Read + Seek)) -> io::Result<Mod> {
     for _ in 0..num_patterns {
         let mut buf = [0; 0x400];
         f.read_exact(&mut buf)?;
+        let notes = buf
+            .chunks_exact(4)
+            .map(|note| Note::try_from(u32::from_be_bytes(*array_ref![note, 0, 4])))
+            .collect::<io::Result<Vec<_>>>()?;
         patterns.push(array::from_fn(|pat| {
-            array::from_fn(|ch| {
-                let pos = pat << 4 | ch << 2;
-                Note::from(u32::from_be_bytes(*array_ref![buf, pos, 4]))
-            })
+            array::from_fn(|ch| notes[pat << 2 | ch])
         }));
     }
     for (sample, len) in samples.iter_mut().zip(sample_lens) {
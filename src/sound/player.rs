@@ -1,8 +1,16 @@
-use std::{ops::Deref, sync::Arc};
+use std::{
+    ops::Deref,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+#[cfg(feature = "native-audio")]
+use std::time::Instant;
 
+#[cfg(feature = "native-audio")]
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    BufferSize, SampleRate, Stream, StreamConfig,
+    BufferSize, SampleRate, Stream, StreamConfig, SupportedBufferSize,
 };
 
 use super::{
@@ -24,6 +32,7 @@ struct PlayerState {
     ticks_left: u8,
     samples_left: u32,
     samples_in_tick: u32,
+    pitch_scale: u64,
     position: usize,
     row: usize,
     channels: [ChannelState; 4],
@@ -63,11 +72,84 @@ struct ChannelState {
     volume_slide_speed: i8,
     retrig_period: u8,
     retrig_left: u8,
+    sfx: bool,
 }
 
+pub const DEFAULT_BUFFER_MS: u32 = 20;
+const SAMPLE_RATE: u32 = 48000;
+// Warnings are repeated each time the count doubles.
+const FIRST_UNDERRUN_WARNING: u32 = 8;
+
 pub struct Player {
-    _stream: Stream,
+    output: Output,
     controller: Arc<Controller>,
+    buffer_ms: u32,
+    next_underrun_warning: u32,
+}
+
+enum Output {
+    #[cfg(feature = "native-audio")]
+    Device {
+        _stream: Stream,
+    },
+    Sink {
+        _sink: Box<dyn AudioSink>,
+    },
+    Null(Box<PlayerState>, Vec<f32>),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Device,
+    // whatever set_audio_sink() registered
+    Sink,
+    Null,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        if AUDIO_SINK.get().is_some() {
+            Backend::Sink
+        } else {
+            Backend::Device
+        }
+    }
+}
+
+// Audio output provided by the host rather than cpal, such as Web Audio in a
+// browser.  start() hands over the mixer, which the sink then pulls samples
+// from in its own callback.
+pub trait AudioSink: Send {
+    fn start(&mut self, source: AudioSource);
+}
+
+pub struct AudioSource(Box<PlayerState>);
+
+impl AudioSource {
+    pub fn sample_rate(&self) -> u32 {
+        self.0.sample_rate
+    }
+
+    // interleaved stereo
+    pub fn fill(&mut self, data: &mut [f32]) {
+        self.0.controller.set_buffer_frames((data.len() / 2) as u32);
+        self.0.make_samples(data);
+    }
+
+    // for sinks that notice they were starved
+    pub fn report_underrun(&self) {
+        self.0.controller.incr_underruns();
+    }
+}
+
+type SinkFactory = Box<dyn Fn() -> Box<dyn AudioSink> + Send + Sync>;
+
+static AUDIO_SINK: OnceLock<SinkFactory> = OnceLock::new();
+
+// Makes Backend::default() play through a new sink from the factory for each
+// player.  It can only be set once; false means it already was.
+pub fn set_audio_sink(factory: impl Fn() -> Box<dyn AudioSink> + Send + Sync + 'static) -> bool {
+    AUDIO_SINK.set(Box::new(factory)).is_ok()
 }
 
 impl Deref for Player {
@@ -78,76 +160,246 @@ impl Deref for Player {
     }
 }
 
-pub fn play(module: Mod, sequencer: Option<Arc<dyn Sequencer>>) -> Player {
+impl Player {
+    pub fn advance(&mut self, fps: u32) {
+        if let Output::Null(ref mut state, ref mut buf) = self.output {
+            buf.resize((state.sample_rate / fps * 2) as usize, 0.0);
+            self.controller.set_buffer_frames(state.sample_rate / fps);
+            state.make_samples(buf);
+        }
+        let underruns = self.controller.underruns();
+        if underruns >= self.next_underrun_warning {
+            eprintln!(
+                "warning: {underruns} audio buffer underruns with {} ms buffers, \
+                 try a higher --audio-latency",
+                self.latency().as_millis()
+            );
+            self.next_underrun_warning = underruns * 2;
+        }
+    }
+
+    // what the output actually uses, which may differ from the requested
+    // latency if the device doesn't support it
+    pub fn latency(&self) -> Duration {
+        let frames = match self.controller.buffer_frames() {
+            0 => SAMPLE_RATE * self.buffer_ms / 1000,
+            frames => frames,
+        };
+        Duration::from_secs_f64(f64::from(frames) / f64::from(SAMPLE_RATE))
+    }
+}
+
+pub fn play(module: Mod, sequencer: Option<Arc<dyn Sequencer>>, device: Option<&str>) -> Player {
+    play_with_backend(
+        module,
+        sequencer,
+        Backend::default(),
+        device,
+        DEFAULT_BUFFER_MS,
+    )
+}
+
+#[cfg(not(feature = "native-audio"))]
+pub fn output_devices() -> Vec<String> {
+    vec![]
+}
+
+#[cfg(feature = "native-audio")]
+pub fn output_devices() -> Vec<String> {
+    let host = cpal::default_host();
+    match host.output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+pub fn play_with_backend(
+    module: Mod,
+    sequencer: Option<Arc<dyn Sequencer>>,
+    backend: Backend,
+    device: Option<&str>,
+    buffer_ms: u32,
+) -> Player {
+    let sequencer = sequencer.unwrap_or_else(|| Arc::new(SimpleSequencer::new(&module)));
+    let controller = Arc::new(Controller::new());
+    let state = PlayerState::new(module, sequencer, controller.clone(), SAMPLE_RATE);
+    let output = match backend {
+        #[cfg(feature = "native-audio")]
+        Backend::Device => Output::Device {
+            _stream: open_stream(state, device, buffer_ms),
+        },
+        #[cfg(not(feature = "native-audio"))]
+        Backend::Device => {
+            let _ = device;
+            eprintln!("warning: built without native audio, playing silently");
+            Output::Null(Box::new(state), vec![])
+        }
+        Backend::Sink => match AUDIO_SINK.get() {
+            Some(factory) => {
+                let mut sink = factory();
+                sink.start(AudioSource(Box::new(state)));
+                Output::Sink { _sink: sink }
+            }
+            None => Output::Null(Box::new(state), vec![]),
+        },
+        Backend::Null => Output::Null(Box::new(state), vec![]),
+    };
+    Player {
+        output,
+        controller,
+        buffer_ms,
+        next_underrun_warning: FIRST_UNDERRUN_WARNING,
+    }
+}
+
+// Mixes the module from its start without a realtime device, returning
+// interleaved stereo samples.
+pub fn render(module: Mod, sample_rate: u32, frames: usize) -> Vec<f32> {
+    let sequencer = Arc::new(SimpleSequencer::new(&module));
+    let mut state = PlayerState::new(module, sequencer, Arc::new(Controller::new()), sample_rate);
+    let mut buf = vec![0.0; frames * 2];
+    for chunk in buf.chunks_mut((sample_rate / 50 * 2).max(2) as usize) {
+        state.make_samples(chunk);
+    }
+    buf
+}
+
+#[cfg(feature = "native-audio")]
+fn find_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    host.output_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+}
+
+#[cfg(feature = "native-audio")]
+fn clamp_buffer_frames(device: &cpal::Device, frames: u32) -> u32 {
+    let Ok(mut configs) = device.supported_output_configs() else {
+        return frames;
+    };
+    configs
+        .find_map(|config| match *config.buffer_size() {
+            SupportedBufferSize::Range { min, max } if config.channels() == 2 => {
+                Some(frames.clamp(min, max))
+            }
+            _ => None,
+        })
+        .unwrap_or(frames)
+}
+
+#[cfg(feature = "native-audio")]
+fn open_stream(mut state: PlayerState, device: Option<&str>, buffer_ms: u32) -> Stream {
     let host = cpal::default_host();
-    let device = host
-        .default_output_device()
-        .expect("no output device available");
+    let device = match device.map(|name| (name, find_device(&host, name))) {
+        Some((_, Some(device))) => device,
+        Some((name, None)) => {
+            eprintln!("warning: audio device {name:?} not found, using the default one");
+            eprintln!("available devices: {:?}", output_devices());
+            host.default_output_device()
+                .expect("no output device available")
+        }
+        None => host
+            .default_output_device()
+            .expect("no output device available"),
+    };
     /*let supported_configs_range = device
         .supported_output_configs()
         .expect("error while querying configs");
     for cfg in supported_configs_range {
         println!("{cfg:#?}");
     }*/
-    let sample_rate = 48000;
-    let sequencer = sequencer.unwrap_or_else(|| Arc::new(SimpleSequencer::new(&module)));
-    let position = sequencer.next_position() as usize;
-    let controller = Arc::new(Controller::new());
-    let mut state = PlayerState {
-        module,
-        speed: 6,
-        ticks_left: 0,
-        samples_left: 0,
-        sequencer,
-        controller: controller.clone(),
-        samples_in_tick: sample_rate / 50,
-        position,
-        row: 0,
-        channels: std::array::from_fn(|_| ChannelState {
-            volume: 0x40,
-            sample: 0,
-            sample_pos: 0,
-            sample_bytes_per_frame: 0,
-            sample_pos_reload: 0,
-            period: 0,
-            vibrato_phase: 0,
-            tone_effect: ChannelToneEffect::None,
-            arpeggio_periods: [0, 0],
-            portamento_target: 0,
-            portamento_speed: 0,
-            vibrato_rate: 0,
-            vibrato_depth: 0,
-            volume_effect: ChannelVolumeEffect::None,
-            volume_slide_speed: 0,
-            retrig_period: 0,
-            retrig_left: 0,
-            xperiod: 0,
-        }),
-        sample_rate,
-        pattern_break: None,
-        jump: None,
-    };
+    let sample_rate = state.sample_rate;
+    let wanted = sample_rate * buffer_ms / 1000;
+    let frames = clamp_buffer_frames(&device, wanted);
+    if frames != wanted {
+        eprintln!(
+            "warning: audio buffers of {buffer_ms} ms are not supported, using {} ms",
+            frames * 1000 / sample_rate
+        );
+    }
     let config = StreamConfig {
         channels: 2,
         sample_rate: SampleRate(sample_rate),
-        buffer_size: BufferSize::Fixed(sample_rate / 50),
+        buffer_size: BufferSize::Fixed(frames),
     };
+    let error_controller = state.controller.clone();
+    // A callback arriving well after the previous buffer should have run
+    // out means the device was starved.
+    let mut last_callback: Option<(Instant, Duration)> = None;
     let stream = device
         .build_output_stream(
             &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| state.make_samples(data),
-            move |err| eprintln!("audio error: {err:?}"),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let now = Instant::now();
+                if let Some((last, length)) = last_callback {
+                    if now - last > length * 2 {
+                        state.controller.incr_underruns();
+                    }
+                }
+                let frames = (data.len() / 2) as u32;
+                state.controller.set_buffer_frames(frames);
+                last_callback = Some((
+                    now,
+                    Duration::from_secs_f64(f64::from(frames) / f64::from(sample_rate)),
+                ));
+                state.make_samples(data)
+            },
+            move |err| {
+                error_controller.incr_underruns();
+                eprintln!("audio error: {err:?}")
+            },
             None, // None=blocking, Some(Duration)=timeout
         )
         .expect("failed to make stream");
     stream.play().unwrap();
-    Player {
-        _stream: stream,
-        controller,
-    }
+    stream
 }
 
 impl PlayerState {
+    fn new(
+        module: Mod,
+        sequencer: Arc<dyn Sequencer>,
+        controller: Arc<Controller>,
+        sample_rate: u32,
+    ) -> Self {
+        let position = sequencer.next_position() as usize;
+        PlayerState {
+            module,
+            speed: 6,
+            ticks_left: 0,
+            samples_left: 0,
+            sequencer,
+            controller,
+            samples_in_tick: sample_rate / 50,
+            pitch_scale: 0x10000,
+            position,
+            row: 0,
+            channels: std::array::from_fn(|_| ChannelState {
+                volume: 0x40,
+                sample: 0,
+                sample_pos: 0,
+                sample_bytes_per_frame: 0,
+                sample_pos_reload: 0,
+                period: 0,
+                vibrato_phase: 0,
+                tone_effect: ChannelToneEffect::None,
+                arpeggio_periods: [0, 0],
+                portamento_target: 0,
+                portamento_speed: 0,
+                vibrato_rate: 0,
+                vibrato_depth: 0,
+                volume_effect: ChannelVolumeEffect::None,
+                volume_slide_speed: 0,
+                retrig_period: 0,
+                retrig_left: 0,
+                xperiod: 0,
+                sfx: false,
+            }),
+            sample_rate,
+            pattern_break: None,
+            jump: None,
+        }
+    }
+
     fn make_samples(&mut self, data: &mut [f32]) {
         if self.controller.paused() {
             for v in data {
@@ -156,9 +408,16 @@ impl PlayerState {
             return;
         }
         let master_volume = self.controller.master_volume() as i32;
+        let music_volume = master_volume * self.controller.music_volume() as i32 / 0x100;
+        let sfx_volume = master_volume * self.controller.sfx_volume() as i32 / 0x100;
+        // ticks stay in musical time, only their length in samples changes
+        let samples_in_tick =
+            ((self.samples_in_tick as f32 / self.controller.tempo_scale()) as u32).max(1);
+        self.pitch_scale = (self.controller.pitch_scale() * 65536.0) as u64;
         self.process_interrupt();
         if let Some((channel, note)) = self.controller.get_sfx() {
             self.play_note(channel, note);
+            self.channels[channel].sfx = true;
         }
         let mut pos = 0;
         while pos < data.len() {
@@ -170,14 +429,21 @@ impl PlayerState {
                     self.ticks_left -= 1;
                     self.play_effects();
                 }
-                self.samples_left = self.samples_in_tick;
+                self.samples_left = samples_in_tick;
                 self.controller.incr_tick();
             }
-            data[pos] = ((self.play_channel(0) + self.play_channel(1)) / 0x100 * master_volume)
-                as f32
+            let volumes = self.channels.each_ref().map(|channel| {
+                if channel.sfx {
+                    sfx_volume
+                } else {
+                    music_volume
+                }
+            });
+            data[pos] = (self.play_channel(0) / 0x100 * volumes[0]
+                + self.play_channel(1) / 0x100 * volumes[1]) as f32
                 / (0x80000000u32 as f32);
-            data[pos + 1] = ((self.play_channel(2) + self.play_channel(3)) / 0x100 * master_volume)
-                as f32
+            data[pos + 1] = (self.play_channel(2) / 0x100 * volumes[2]
+                + self.play_channel(3) / 0x100 * volumes[3]) as f32
                 / (0x80000000u32 as f32);
             pos += 2;
             self.samples_left -= 1;
@@ -202,6 +468,9 @@ impl PlayerState {
         //     r = self.row
         // );
         for (i, &note) in row.iter().enumerate() {
+            if note.sample.is_some() || note.period.is_some() {
+                self.channels[i].sfx = false;
+            }
             self.play_note(i, note);
             // print!("   {note}");
         }
@@ -397,7 +666,7 @@ impl PlayerState {
         } else if pos >= sample.data.len() {
             return 0;
         }
-        channel.sample_pos += channel.sample_bytes_per_frame;
+        channel.sample_pos += (channel.sample_bytes_per_frame * self.pitch_scale) >> 16;
         let mut val = sample.data[pos] as i32;
         if val >= 0x80 {
             val -= 0x100;
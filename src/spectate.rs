@@ -0,0 +1,320 @@
+// Watching a game over the network.  The server sends a snapshot of the table
+// after each frame to everyone connected, and the spectator draws the board
+// with the ball and the dot matrix from the latest one it got.
+//
+// A connection starts with MAGIC and the protocol version as a little endian
+// u16, followed by one message per frame: its length as a u32 and the
+// snapshot in bincode.
+
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, Sender, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, VirtualKeyCode};
+
+use crate::{
+    assets::table::physics::Layer,
+    config::{is_bound, Config, TableId},
+    data::DataSource,
+    table::{GameEvent, Table},
+    view::{Action, GameStatus, PanelLayout, View},
+};
+
+pub const DEFAULT_PORT: u16 = 7651;
+const MAGIC: &[u8; 4] = b"PFRS";
+pub const PROTOCOL_VERSION: u16 = 1;
+const MAX_MESSAGE_LEN: u32 = 0x10000;
+// messages a slow spectator can fall behind by before it misses frames
+const CLIENT_BACKLOG: usize = 8;
+
+type Clients = Mutex<Vec<SyncSender<Arc<[u8]>>>>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub table: TableId,
+    pub status: GameStatus,
+    pub ball_pos: (i16, i16),
+    pub ball_layer: Layer,
+    #[serde(with = "dm_bits")]
+    pub dm: [[bool; 160]; 16],
+    // false while a blinking dot matrix is dark
+    pub dm_lit: bool,
+    // what happened since the previous snapshot
+    pub events: Vec<GameEvent>,
+}
+
+// The dot matrix packed eight dots to a byte, as serde has no arrays this long.
+mod dm_bits {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(dm: &[[bool; 160]; 16], s: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = dm
+            .iter()
+            .flat_map(|row| row.chunks(8))
+            .map(|dots| dots.iter().fold(0, |byte, &dot| byte << 1 | u8::from(dot)))
+            .collect();
+        bytes.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[[bool; 160]; 16], D::Error> {
+        let bytes = Vec::<u8>::deserialize(d)?;
+        if bytes.len() != 16 * 20 {
+            return Err(D::Error::custom("dot matrix must be 16 rows of 160 dots"));
+        }
+        let mut dm = [[false; 160]; 16];
+        for (dots, byte) in dm.iter_mut().flat_map(|row| row.chunks_mut(8)).zip(bytes) {
+            for (i, dot) in dots.iter_mut().enumerate() {
+                *dot = byte & 0x80 >> i != 0;
+            }
+        }
+        Ok(dm)
+    }
+}
+
+fn bad_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub fn write_header(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&PROTOCOL_VERSION.to_le_bytes())
+}
+
+pub fn read_header(input: &mut impl Read) -> io::Result<()> {
+    let mut header = [0; 6];
+    input.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(bad_data("not a pfr game"));
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != PROTOCOL_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the game uses protocol version {version}, this one {PROTOCOL_VERSION}"),
+        ));
+    }
+    Ok(())
+}
+
+pub fn write_snapshot(out: &mut impl Write, snapshot: &Snapshot) -> io::Result<()> {
+    out.write_all(&message(snapshot))
+}
+
+fn message(snapshot: &Snapshot) -> Vec<u8> {
+    let body = bincode::serialize(snapshot).unwrap();
+    let mut message = (body.len() as u32).to_le_bytes().to_vec();
+    message.extend(body);
+    message
+}
+
+pub fn read_snapshot(input: &mut impl Read) -> io::Result<Snapshot> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(bad_data("snapshot too long"));
+    }
+    let mut body = vec![0; len as usize];
+    input.read_exact(&mut body)?;
+    bincode::deserialize(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub struct SpectateServer {
+    clients: Arc<Clients>,
+    event_sender: Sender<GameEvent>,
+    events: Receiver<GameEvent>,
+}
+
+impl SpectateServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Arc::new(Mutex::new(vec![]));
+        let accepted = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => accept(stream, &accepted),
+                    Err(e) => eprintln!("spectator failed to connect: {e}"),
+                }
+            }
+        });
+        let (event_sender, events) = mpsc::channel();
+        Ok(SpectateServer {
+            clients,
+            event_sender,
+            events,
+        })
+    }
+
+    // for the tables to report their events to
+    pub fn event_sender(&self) -> Sender<GameEvent> {
+        self.event_sender.clone()
+    }
+
+    pub fn send(&self, mut snapshot: Snapshot) {
+        snapshot.events.extend(self.events.try_iter());
+        let message: Arc<[u8]> = message(&snapshot).into();
+        self.clients.lock().unwrap().retain(|client| {
+            !matches!(
+                client.try_send(message.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+}
+
+fn accept(mut stream: TcpStream, clients: &Clients) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "?".to_string(), |addr| addr.to_string());
+    eprintln!("spectator {peer} connected");
+    let (sender, messages) = mpsc::sync_channel::<Arc<[u8]>>(CLIENT_BACKLOG);
+    clients.lock().unwrap().push(sender);
+    std::thread::spawn(move || {
+        let _ = stream.set_nodelay(true);
+        let sent = write_header(&mut stream)
+            .and_then(|()| messages.iter().try_for_each(|msg| stream.write_all(&msg)));
+        if let Err(e) = sent {
+            eprintln!("spectator {peer} left: {e}");
+        }
+    });
+}
+
+// A read-only view of a game served elsewhere, drawn on the local copy of the
+// table.  Only the ball and the dot matrix follow the game; the lights and
+// flippers stay as they are at startup.
+pub struct Spectator {
+    data: Box<dyn DataSource>,
+    config: Config,
+    table: Option<Table>,
+    snapshots: Receiver<io::Result<Snapshot>>,
+    events: Option<Sender<GameEvent>>,
+    quit: bool,
+}
+
+impl Spectator {
+    pub fn connect(
+        addr: impl ToSocketAddrs,
+        data: Box<dyn DataSource>,
+        mut config: Config,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let mut stream = BufReader::new(stream);
+        read_header(&mut stream)?;
+        let (sender, snapshots) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let snapshot = read_snapshot(&mut stream);
+            let failed = snapshot.is_err();
+            if sender.send(snapshot).is_err() || failed {
+                break;
+            }
+        });
+        config.no_audio = true;
+        config.tournament = false;
+        Ok(Spectator {
+            data,
+            config,
+            table: None,
+            snapshots,
+            events: None,
+            quit: false,
+        })
+    }
+
+    // for the host to hear what happens in the game watched
+    pub fn with_events(mut self, sender: Sender<GameEvent>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+}
+
+impl View for Spectator {
+    fn get_resolution(&self) -> (u32, u32) {
+        self.table
+            .as_ref()
+            .map_or((640, 480), |table| table.get_resolution())
+    }
+
+    fn get_fps(&self) -> u32 {
+        60
+    }
+
+    fn run_frame(&mut self) -> Action {
+        if self.quit {
+            return Action::Exit;
+        }
+        let mut latest = None;
+        for snapshot in self.snapshots.try_iter() {
+            match snapshot {
+                Ok(snapshot) => {
+                    if let Some(ref events) = self.events {
+                        for &event in &snapshot.events {
+                            let _ = events.send(event);
+                        }
+                    }
+                    latest = Some(snapshot);
+                }
+                Err(e) => {
+                    eprintln!("lost the game: {e}");
+                    return Action::Exit;
+                }
+            }
+        }
+        let Some(snapshot) = latest else {
+            return Action::None;
+        };
+        if self
+            .table
+            .as_ref()
+            .is_none_or(|table| table.table_id() != snapshot.table)
+        {
+            match Table::new(&*self.data, self.config.clone(), snapshot.table, 0) {
+                Ok(table) => self.table = Some(table),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Action::Exit;
+                }
+            }
+        }
+        if let Some(ref mut table) = self.table {
+            table.show_snapshot(&snapshot);
+        }
+        Action::None
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode, state: ElementState) {
+        if state == ElementState::Pressed && is_bound(&self.config.key_bindings.quit, key) {
+            self.quit = true;
+        }
+    }
+
+    fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        match self.table {
+            Some(ref table) => table.render(data, pal),
+            None => data.fill(0),
+        }
+    }
+
+    fn get_extra_panel(&self) -> Option<PanelLayout> {
+        self.table
+            .as_ref()
+            .and_then(|table| table.get_extra_panel())
+    }
+
+    fn render_panel(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        if let Some(ref table) = self.table {
+            table.render_panel(data, pal);
+        }
+    }
+
+    fn scroll_blend(&self) -> Option<(usize, u8)> {
+        self.table.as_ref().and_then(|table| table.scroll_blend())
+    }
+}
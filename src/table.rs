@@ -1,33 +1,51 @@
-use std::{fs::File, path::Path, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc},
+};
 
 use arrayvec::ArrayVec;
 use enum_map::{enum_map, EnumMap};
 use ndarray::Array2;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use unnamed_entity::EntityVec;
-use winit::event::{ElementState, VirtualKeyCode};
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
 
 use crate::{
     assets::table::{
         dm::DmFont,
         flippers::{FlipperId, FlipperSide},
         physics::{BumperId, Layer, Material, RollTrigger},
-        script::{DmCoord, ScriptBind},
-        sound::{JingleBind, SfxBind},
+        script::{DmCoord, EffectSound, ScriptBind},
+        sound::{Jingle, JingleBind, SfxBind},
         Assets,
     },
     bcd::Bcd,
-    config::{Config, HighScore, Options, Resolution, TableId},
-    sound::{controller::TableSequencer, player::Player},
-    view::{Action, Route, View},
+    config::{
+        is_bound, BallColor, BallStyle, Config, HighScore, HighScoreName, HighScoreSaving,
+        KeyBindings, Options, Resolution, TableId, TiltSensitivity, MAX_BALLS, MIN_BALLS,
+    },
+    data::{DataSource, DirSource},
+    error::PfrError,
+    palette::nearest_color,
+    sound::{controller::TableSequencer, player::Player, Mod},
+    spectate::Snapshot,
+    view::{Action, GameStatus, PadButton, PanelLayout, Route, View},
 };
 
 use self::{
-    ball::BallState,
+    ball::{trail_shades, BallState},
     cheat::CheatState,
     dm::DotMatrix,
     lights::Lights,
+    multiball::AuxBall,
     party::PartyState,
-    physics::{prep_materials, speed_fix, FlipperState, PushState},
+    physics::{
+        prep_materials, speed_fix, FlipperState, NudgeDir, PushState, BUMPER_SPEED_BOOST,
+        KICKER_SPEED_BOOST,
+    },
     player::PlayerState,
     script::ScriptState,
     scroll::ScrollState,
@@ -35,6 +53,7 @@ use self::{
     speed::SpeedState,
     stones::StonesState,
     tasks::{Task, TaskKind},
+    tuning::Tuning,
 };
 
 pub struct Table {
@@ -42,7 +61,11 @@ pub struct Table {
     sequencer: Arc<TableSequencer>,
     assets: Assets,
     options: Options,
-    high_scores: [HighScore; 4],
+    tournament: bool,
+    high_scores: Vec<HighScore>,
+    events: Option<Sender<GameEvent>>,
+    // while a frame is run ahead to be undone
+    running_ahead: bool,
     hifps: bool,
     scroll: ScrollState,
     lights: Lights,
@@ -52,6 +75,7 @@ pub struct Table {
     script: ScriptState,
     tasks: Vec<Task>,
     ball: BallState,
+    rng: StdRng,
     cheat: CheatState,
     flippers: EntityVec<FlipperId, FlipperState>,
     physmaps: EnumMap<Layer, Array2<u8>>,
@@ -59,6 +83,7 @@ pub struct Table {
     kicker_speed_threshold: i16,
     kicker_speed_boost: i16,
     bumper_speed_boost: i16,
+    tuning: Tuning,
     match_timing: [u16; 36],
 
     in_attract: bool,
@@ -74,12 +99,26 @@ pub struct Table {
     ball_scored_points: bool,
     tilted: bool,
     tilt_counter: u16,
+    tilt_nudge_increment: u16,
+    tilt_warn_threshold: u16,
+    tilt_limit: u16,
     silence_effect: bool,
     timer_stop: bool,
     block_drain: bool,
     got_high_score: bool,
     flush_high_scores: bool,
-    name_buf: ArrayVec<u8, 3>,
+    cheated: bool,
+    got_replay: bool,
+    flush_options: bool,
+    // in practice mode, the state from when the current player's ball was
+    // first issued
+    ball_snapshot: Option<((u8, u8), Vec<u8>)>,
+    snapshot_pending: bool,
+    rewind: bool,
+    name_buf: HighScoreName,
+    name_confirmed: bool,
+    pause_sel: usize,
+    options_sel: usize,
 
     in_mode: bool,
     in_mode_hit: bool,
@@ -91,18 +130,31 @@ pub struct Table {
     mode_timeout_secs: u8,
 
     kbd_state: KbdState,
+    key_bindings: KeyBindings,
     flipper_state: EnumMap<FlipperSide, bool>,
     flipper_pressed: bool,
     flippers_enabled: bool,
-    space_state: bool,
-    space_pressed: bool,
+    nudge_state: EnumMap<NudgeDir, bool>,
+    nudge_pressed: EnumMap<NudgeDir, bool>,
     spring_down_state: bool,
     spring_released: bool,
+    plunger_drag: Option<u32>,
+    dm_overlay: bool,
+    ball_save_frames: u16,
+    ball_saved_msg: u16,
+    replay_msg: u16,
     start_keys_active: bool,
     start_key: Option<u8>,
 
     quitting: bool,
     fade: u16,
+    attract_idle: u32,
+    board_cache: RefCell<BoardCache>,
+    // the caption of the last sound played and the frames it has left
+    caption: Cell<Option<(&'static str, u16)>>,
+    trail_shades: Vec<[u8; 256]>,
+    ball_colors: EnumMap<BallColor, u8>,
+    aim_guide: Vec<(i16, i16)>,
 
     cur_player: u8,
     total_players: u8,
@@ -129,6 +181,9 @@ pub struct Table {
     hit_bumper: Option<BumperId>,
     roll_trigger: Option<RollTrigger>,
     prev_roll_trigger: Option<RollTrigger>,
+    aux_balls: Vec<AuxBall>,
+    // set while an extra ball is swapped into ball
+    on_aux_ball: bool,
 
     party: PartyState,
     speed: SpeedState,
@@ -136,57 +191,323 @@ pub struct Table {
     stones: StonesState,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum KbdState {
     Main,
     ConfirmQuit,
     Paused,
     PausedConfirmQuit,
+    PausedOptions,
     GetName,
+    GameSummary,
+}
+
+const PAUSE_MENU: [&[u8]; 5] = [
+    b"RESUME",
+    b"TOGGLE MUSIC",
+    b"TOGGLE FPS",
+    b"OPTIONS",
+    b"QUIT",
+];
+
+const HIFPS: u32 = 70;
+
+const BALL_SAVED_MSG_FRAMES: u16 = 120;
+const REPLAY_MSG_FRAMES: u16 = 120;
+
+const TOURNAMENT_BALLS: u8 = 3;
+
+#[cfg(feature = "parallel")]
+const BOARD_ROWS_PER_TASK: usize = 32;
+
+// mouse drag (in framebuffer pixels) that pulls the plunger all the way
+const PLUNGER_DRAG_RANGE: u32 = 64;
+const PLUNGER_MOUSE_X: u32 = 240;
+
+// Copies the static board into rows of the frame, starting at board row top.
+// Everything drawn over the board is composited afterwards on one thread.
+#[cfg(feature = "parallel")]
+fn render_board_rows(board: &Array2<u8>, rows: &mut [u8], top: usize) {
+    use rayon::prelude::*;
+    rows.par_chunks_mut(320 * BOARD_ROWS_PER_TASK)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            render_board_rows_serial(board, chunk, top + i * BOARD_ROWS_PER_TASK)
+        });
+}
+
+#[cfg(not(feature = "parallel"))]
+fn render_board_rows(board: &Array2<u8>, rows: &mut [u8], top: usize) {
+    render_board_rows_serial(board, rows, top);
+}
+
+fn render_board_rows_serial(board: &Array2<u8>, rows: &mut [u8], top: usize) {
+    for (y, row) in rows.chunks_exact_mut(320).enumerate() {
+        let sy = top + y;
+        if sy >= 576 {
+            row.fill(0);
+        } else {
+            for (x, pix) in row.iter_mut().enumerate() {
+                *pix = board[(x, sy)];
+            }
+        }
+    }
+}
+
+// The board rows last drawn, starting at board row top.  Lights only change the
+// palette, never the pixels, so the cache only has to follow the scroll position.
+#[derive(Default)]
+struct BoardCache {
+    top: usize,
+    rows: Vec<u8>,
 }
 
+impl BoardCache {
+    fn update(&mut self, board: &Array2<u8>, height: usize, top: usize) {
+        let len = height * 320;
+        let shift = top.abs_diff(self.top);
+        if self.rows.len() != len || shift >= height {
+            self.rows.resize(len, 0);
+            render_board_rows(board, &mut self.rows, top);
+        } else if top > self.top {
+            self.rows.copy_within(shift * 320.., 0);
+            render_board_rows(
+                board,
+                &mut self.rows[(height - shift) * 320..],
+                top + height - shift,
+            );
+        } else if top < self.top {
+            self.rows.copy_within(..len - shift * 320, shift * 320);
+            render_board_rows(board, &mut self.rows[..shift * 320], top);
+        }
+        self.top = top;
+    }
+}
+
+fn match_timing(hifps: bool) -> [u16; 36] {
+    if hifps {
+        [
+            22, 28, 25, 25, 22, 19, 18, 15, 13, 11, 9, 9, 8, 8, 7, 7, 6, 6, 6, 6, 6, 5, 5, 5, 5, 5,
+            5, 4, 4, 4, 4, 4, 4, 4, 3, 3,
+        ]
+    } else {
+        [
+            24, 23, 21, 21, 18, 16, 15, 13, 11, 9, 8, 7, 7, 6, 6, 6, 5, 5, 5, 5, 5, 4, 4, 4, 4, 4,
+            4, 4, 4, 4, 3, 3, 3, 3, 3, 3,
+        ]
+    }
+}
+
+mod autoplay;
 mod ball;
 mod cheat;
 mod dm;
+mod events;
 mod flippers;
 mod game;
+#[cfg(feature = "headless")]
+mod headless;
 mod lights;
 mod mode;
+mod multiball;
+mod options_menu;
+mod panel;
 mod party;
 mod physics;
 mod player;
+mod run_ahead;
 mod script;
 mod scroll;
 mod show;
 mod sound;
+mod spectate;
 mod speed;
+mod state;
 mod stones;
 mod tasks;
 mod triggers;
+mod tuning;
+
+pub use self::{autoplay::Autoplay, ball::BallDebug, events::GameEvent};
+
+const GENERAL_JINGLES: [JingleBind; 12] = [
+    JingleBind::Silence,
+    JingleBind::GameStart,
+    JingleBind::Plunger,
+    JingleBind::Main,
+    JingleBind::Attract,
+    JingleBind::WarnTilt,
+    JingleBind::Tilt,
+    JingleBind::GameOverSad,
+    JingleBind::GameOverHighScore,
+    JingleBind::Drained,
+    JingleBind::MatchStart,
+    JingleBind::MatchWin,
+];
+
+fn required_jingle(assets: &Assets, bind: JingleBind) -> Result<Jingle, PfrError> {
+    assets.jingle_binds[bind].ok_or(PfrError::MissingJingleBind(bind))
+}
+
+// A module set in the config replaces the stock one, if it has all the song
+// positions the table's jingles start at and the samples its sound effects
+// use.
+fn load_module(
+    data: &dyn DataSource,
+    stock: &str,
+    custom: Option<&Path>,
+    assets: &Assets,
+) -> Result<Mod, PfrError> {
+    if let Some(path) = custom {
+        match load_custom_module(path, assets) {
+            Ok(module) => return Ok(module),
+            Err(e) => eprintln!(
+                "warning: cannot play {} with the table, using {stock}: {e}",
+                path.display()
+            ),
+        }
+    }
+    crate::sound::loader::load(&mut Cursor::new(data.read(stock)?))
+        .map_err(|e| PfrError::from_io(&data.path(stock), e))
+}
+
+fn load_custom_module(path: &Path, assets: &Assets) -> io::Result<Mod> {
+    let dir = DirSource::new(path.parent().unwrap_or(Path::new("")));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let module = crate::sound::loader::load(&mut Cursor::new(dir.read(&name)?))?;
+    let jingles = assets.jingle_binds.values().flatten().copied().chain(
+        assets
+            .effects
+            .values()
+            .flatten()
+            .filter_map(|effect| match effect.sound {
+                EffectSound::Jingle(jingle) => Some(jingle),
+                EffectSound::Silent(_) => None,
+            }),
+    );
+    let missing_position = jingles
+        .map(|jingle| jingle.position)
+        .chain([assets.position_jingle_start])
+        .find(|&pos| usize::from(pos) >= module.positions.len());
+    if let Some(pos) = missing_position {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("it has no song position {pos}"),
+        ));
+    }
+    let missing_sample = assets
+        .sfx_binds
+        .values()
+        .flatten()
+        .map(|sfx| sfx.sample)
+        .find(|&sample| usize::from(sample) >= module.samples.len());
+    if let Some(sample) = missing_sample {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("it has no sample {sample}"),
+        ));
+    }
+    Ok(module)
+}
 
 impl Table {
-    pub fn new(data: &Path, config: Config, table: TableId) -> Table {
-        let options = config.options;
-        let high_scores = config.high_scores[table];
+    pub fn new(
+        data: &dyn DataSource,
+        config: Config,
+        table: TableId,
+        seed: u64,
+    ) -> Result<Table, PfrError> {
+        let options = config.options_for(table);
+        let high_scores = config.table_high_scores(table).clone();
+        let audio_backend = config.audio_backend();
+        let key_bindings = config.key_bindings;
+        let tournament = config.tournament;
         let (prg, module) = match table {
             TableId::Table1 => ("TABLE1.PRG", "TABLE1.MOD"),
             TableId::Table2 => ("TABLE2.PRG", "TABLE2.MOD"),
             TableId::Table3 => ("TABLE3.PRG", "TABLE3.MOD"),
             TableId::Table4 => ("TABLE4.PRG", "TABLE4.MOD"),
         };
-        let mut f = File::open(data.join(module)).unwrap();
-        let assets = Assets::load(data.join(prg), table).unwrap();
-        let module = crate::sound::loader::load(&mut f).unwrap();
+        let mut assets = Assets::load(data, prg, table)?;
+        if let Some(ref dir) = config.dm_font_dir {
+            if let Err(e) = assets.load_dm_fonts(&DirSource::new(dir)) {
+                eprintln!(
+                    "warning: cannot load dot matrix fonts from {}: {e}",
+                    dir.display()
+                );
+            }
+        }
+        if let Some(path) = config.attract_lights.get(&table) {
+            let dir = DirSource::new(path.parent().unwrap_or(Path::new("")));
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if let Err(e) = assets.load_attract_lights(&dir, &name) {
+                eprintln!(
+                    "warning: cannot load attract lights from {}: {e}",
+                    path.display()
+                );
+            }
+        }
+        // the jingles every table has, which are played without checking
+        for bind in GENERAL_JINGLES {
+            required_jingle(&assets, bind)?;
+        }
+        let module = load_module(
+            data,
+            module,
+            config.music.get(&table).map(PathBuf::as_path),
+            &assets,
+        )?;
         let sequencer = Arc::new(TableSequencer::new(
-            assets.jingle_binds[JingleBind::Attract].unwrap().position,
+            required_jingle(&assets, JingleBind::Attract)?.position,
             assets.position_jingle_start,
-            assets.jingle_binds[JingleBind::Silence].unwrap().position,
+            required_jingle(&assets, JingleBind::Silence)?.position,
             options.no_music,
         ));
-        let player = crate::sound::player::play(module, Some(sequencer.clone()));
+        let player = crate::sound::player::play_with_backend(
+            module,
+            Some(sequencer.clone()),
+            audio_backend,
+            config.audio_device.as_deref(),
+            config.audio_latency.buffer_ms(),
+        );
+        player.set_music_volume(options.music_gain());
+        player.set_sfx_volume(options.sfx_gain());
 
-        let hifps = false;
+        let hifps = options.hifps;
+        let tilt_sensitivity = if tournament {
+            TiltSensitivity::Normal
+        } else {
+            options.tilt_sensitivity
+        };
+        let (tilt_nudge_increment, tilt_warn_threshold, tilt_limit) = match tilt_sensitivity {
+            TiltSensitivity::Low => (60, 90, 180),
+            TiltSensitivity::Normal => (60, 60, 120),
+            TiltSensitivity::High => (60, 30, 60),
+        };
         let scroll = ScrollState::new(&options);
+        // the palette entries that lights and the dot matrix don't change
+        let mut usable = [true; 256];
+        for light in assets.lights.values() {
+            let base = usize::from(light.base_index);
+            for flag in usable.iter_mut().skip(base).take(light.colors.len()) {
+                *flag = false;
+            }
+        }
+        usable[usize::from(assets.dm_palette.index_on)] = false;
+        usable[usize::from(assets.dm_palette.index_off)] = false;
+        let trail_shades = if options.ball_trail {
+            trail_shades(
+                &assets.main_board.cmap,
+                options.ball_trail_length.into(),
+                options.ball_trail_fade,
+                |idx| usable[idx],
+            )
+        } else {
+            vec![]
+        };
+        let ball_colors = EnumMap::from_fn(|color: BallColor| {
+            nearest_color(&assets.main_board.cmap, color.rgb(), |idx| usable[idx])
+        });
         let lights = Lights::new(&assets);
         let flippers = assets
             .flippers
@@ -199,7 +520,10 @@ impl Table {
             sequencer,
             assets,
             options,
+            tournament,
             high_scores,
+            events: None,
+            running_ahead: false,
             hifps,
             scroll,
             lights,
@@ -209,24 +533,16 @@ impl Table {
             script: ScriptState::new(),
             tasks: vec![],
             ball: BallState::new(hifps),
+            rng: StdRng::seed_from_u64(seed),
             cheat: CheatState::new(),
             flippers,
             physmaps,
             materials,
             kicker_speed_threshold: speed_fix(300, hifps),
-            kicker_speed_boost: speed_fix(2000, hifps),
-            bumper_speed_boost: speed_fix(7000, hifps),
-            match_timing: if hifps {
-                [
-                    22, 28, 25, 25, 22, 19, 18, 15, 13, 11, 9, 9, 8, 8, 7, 7, 6, 6, 6, 6, 6, 5, 5,
-                    5, 5, 5, 5, 4, 4, 4, 4, 4, 4, 4, 3, 3,
-                ]
-            } else {
-                [
-                    24, 23, 21, 21, 18, 16, 15, 13, 11, 9, 8, 7, 7, 6, 6, 6, 5, 5, 5, 5, 5, 4, 4,
-                    4, 4, 4, 4, 4, 4, 4, 3, 3, 3, 3, 3, 3,
-                ]
-            },
+            kicker_speed_boost: speed_fix(KICKER_SPEED_BOOST, hifps),
+            bumper_speed_boost: speed_fix(BUMPER_SPEED_BOOST, hifps),
+            tuning: Tuning::stock(),
+            match_timing: match_timing(hifps),
 
             in_attract: true,
             in_plunger: true,
@@ -236,6 +552,12 @@ impl Table {
             got_top_score: false,
             got_high_score: false,
             flush_high_scores: false,
+            cheated: false,
+            got_replay: false,
+            flush_options: false,
+            ball_snapshot: None,
+            snapshot_pending: false,
+            rewind: false,
             in_game_start: true,
             party_on: false,
             special_plunger_event: false,
@@ -243,10 +565,16 @@ impl Table {
             ball_scored_points: false,
             tilted: false,
             tilt_counter: 0,
+            tilt_nudge_increment,
+            tilt_warn_threshold,
+            tilt_limit,
             silence_effect: false,
             timer_stop: false,
             block_drain: false,
             name_buf: ArrayVec::new(),
+            name_confirmed: false,
+            pause_sel: 0,
+            options_sel: 0,
 
             in_mode: false,
             in_mode_hit: false,
@@ -258,22 +586,39 @@ impl Table {
             mode_timeout_frames: 0,
 
             kbd_state: KbdState::Main,
+            key_bindings,
             flipper_state: enum_map! { _ => false},
             flipper_pressed: false,
             flippers_enabled: false,
-            space_state: false,
-            space_pressed: false,
+            nudge_state: EnumMap::default(),
+            nudge_pressed: EnumMap::default(),
             spring_down_state: false,
             spring_released: false,
+            plunger_drag: None,
+            dm_overlay: false,
+            ball_save_frames: 0,
+            ball_saved_msg: 0,
+            replay_msg: 0,
             start_keys_active: true,
             start_key: None,
             quitting: false,
             fade: 0x100,
+            attract_idle: 0,
+            board_cache: RefCell::new(BoardCache::default()),
+            caption: Cell::new(None),
+            trail_shades,
+            ball_colors,
+            aim_guide: vec![],
 
             cur_player: 1,
             total_players: 1,
             cur_ball: 1,
-            total_balls: config.options.balls,
+            // the ball number is shown as a single digit
+            total_balls: if tournament {
+                TOURNAMENT_BALLS
+            } else {
+                options.balls.clamp(MIN_BALLS, MAX_BALLS)
+            },
             extra_balls: 0,
             bonus_mult_early: 1,
             bonus_mult_late: 1,
@@ -295,6 +640,8 @@ impl Table {
             hit_bumper: None,
             roll_trigger: None,
             prev_roll_trigger: None,
+            aux_balls: vec![],
+            on_aux_ball: false,
 
             party: PartyState::new(),
             speed: SpeedState::new(),
@@ -304,24 +651,363 @@ impl Table {
         res.ball.set_pos((280, 525));
         res.start_script(ScriptBind::Init);
         res.flippers_physmap_update();
-        res
+        Ok(res)
+    }
+
+    // Counts frames spent idle in attract mode, fading the music out over the
+    // last second.  Returns true once the configured timeout is reached.
+    fn attract_idle_frame(&mut self) -> bool {
+        if self.options.attract_timeout == 0 {
+            return false;
+        }
+        let fps = self.get_fps();
+        let timeout = u32::from(self.options.attract_timeout) * fps;
+        self.attract_idle += 1;
+        let left = timeout.saturating_sub(self.attract_idle);
+        if left < fps {
+            self.player.set_master_volume(0x100 * left / fps);
+        }
+        left == 0
+    }
+
+    fn reset_attract_idle(&mut self) {
+        if self.attract_idle != 0 {
+            self.attract_idle = 0;
+            self.player.set_master_volume(0x100);
+        }
     }
 
     pub fn pause(&mut self) {
+        // held keys would otherwise act on the first frame after unpausing
+        self.release_inputs();
+        self.clear_dm_overlay();
         self.dm.save();
-        self.dm.clear();
         self.dm.set_state(true);
-        self.dm_puts(DmFont::H13, DmCoord { x: 36, y: 1 }, b"GAME PAUSED");
+        self.pause_sel = 0;
+        self.dm_pause_menu();
         self.kbd_state = KbdState::Paused;
         self.player.pause();
     }
 
+    // Nothing that runs on time advances while paused: run_frame returns
+    // before any of it and the player stops counting ticks.
+    fn paused(&self) -> bool {
+        matches!(
+            self.kbd_state,
+            KbdState::Paused | KbdState::PausedConfirmQuit | KbdState::PausedOptions
+        )
+    }
+
+    fn paused_menu(&self) -> bool {
+        matches!(self.kbd_state, KbdState::Paused | KbdState::PausedOptions)
+    }
+
+    fn dm_pause_menu(&mut self) {
+        self.dm.clear();
+        self.dm_puts(DmFont::H5, DmCoord { x: 36, y: 1 }, b"GAME PAUSED");
+        let item = PAUSE_MENU[self.pause_sel];
+        let x = 80 - (item.len() as i16 + 4) * 4;
+        self.dm_puts(DmFont::H8, DmCoord { x, y: 8 }, b"-");
+        self.dm_puts(DmFont::H8, DmCoord { x: x + 16, y: 8 }, item);
+        self.dm_puts(
+            DmFont::H8,
+            DmCoord {
+                x: x + 24 + item.len() as i16 * 8,
+                y: 8,
+            },
+            b"-",
+        );
+    }
+
+    fn pause_confirm_quit(&mut self) {
+        self.dm.clear();
+        self.dm_puts(DmFont::H13, DmCoord { x: 0, y: 1 }, b"REALLY QUIT (Y OR N)");
+        self.kbd_state = KbdState::PausedConfirmQuit;
+    }
+
+    // The plunger meter and the ball saved and replay messages are drawn over a saved copy
+    // of the DM after each frame and taken off again before the next one, so
+    // the score display underneath stays intact.
+    fn clear_dm_overlay(&mut self) {
+        if self.dm_overlay {
+            self.dm_overlay = false;
+            self.dm.restore();
+        }
+    }
+
+    fn dm_overlay(&mut self) {
+        let meter = !self.in_attract && (self.spring_down_state || self.plunger_drag.is_some());
+        if !meter && self.ball_saved_msg == 0 && self.replay_msg == 0 {
+            return;
+        }
+        self.dm.save();
+        self.dm_overlay = true;
+        if self.replay_msg != 0 {
+            self.replay_msg -= 1;
+            if self.options.reduce_flashing || self.replay_msg % 30 >= 10 {
+                self.dm.clear();
+                self.dm_puts(DmFont::H8, DmCoord { x: 56, y: 4 }, b"REPLAY");
+            }
+        } else if self.ball_saved_msg != 0 {
+            self.ball_saved_msg -= 1;
+            if self.options.reduce_flashing || self.ball_saved_msg % 30 >= 10 {
+                self.dm.clear();
+                self.dm_puts(DmFont::H8, DmCoord { x: 40, y: 4 }, b"BALL SAVED");
+            }
+        }
+        if meter {
+            let len = self.spring_pos as usize * 160 / 0x20;
+            for line in &mut self.dm.pixels[14..16] {
+                for (x, pixel) in line.iter_mut().enumerate() {
+                    *pixel = x < len;
+                }
+            }
+        }
+    }
+
+    // Called when the ball leaves the plunger lane for the first time.
+    pub fn start_ball_save(&mut self) {
+        if self.tournament {
+            return;
+        }
+        self.ball_save_frames = u16::from(self.options.ball_save) * self.get_fps() as u16;
+    }
+
+    fn ball_save(&mut self) {
+        self.drained = false;
+        self.ball_save_frames = 0;
+        self.ball_saved_msg = BALL_SAVED_MSG_FRAMES;
+        self.at_spring = true;
+        self.ball
+            .teleport_freeze(Layer::Ground, self.assets.issue_ball_pos);
+        self.add_task(TaskKind::IssueBallSfx);
+        self.add_task(TaskKind::IssueBallRelease);
+        self.add_task(TaskKind::BallSaveLaunch);
+    }
+
     pub fn unpause(&mut self) {
         self.dm.restore();
         self.kbd_state = KbdState::Main;
         self.player.unpause();
     }
 
+    pub fn rebuild_physics(&mut self, hifps: bool) {
+        if hifps == self.hifps {
+            return;
+        }
+        self.hifps = hifps;
+        for (id, flipper) in &mut self.flippers {
+            let fresh = FlipperState::new(&self.assets.flippers[id], hifps);
+            flipper.accel_press = fresh.accel_press;
+            flipper.accel_release = fresh.accel_release;
+            flipper.speed_press_start = fresh.speed_press_start;
+        }
+        self.push.set_hifps(hifps);
+        self.ball.max_speed = speed_fix(4100, hifps);
+        let (num, den) = if hifps { (6, 5) } else { (5, 6) };
+        self.ball.speed = (
+            (i32::from(self.ball.speed.0) * num / den) as i16,
+            (i32::from(self.ball.speed.1) * num / den) as i16,
+        );
+        self.materials = prep_materials(hifps);
+        self.kicker_speed_threshold = speed_fix(300, hifps);
+        self.kicker_speed_boost = speed_fix(KICKER_SPEED_BOOST, hifps);
+        self.bumper_speed_boost = speed_fix(BUMPER_SPEED_BOOST, hifps);
+        self.apply_tuning();
+        self.match_timing = match_timing(hifps);
+        let wheel_timing = ShowState::new(hifps).wheel_timing;
+        self.show.wheel_cycle = self.show.wheel_cycle.min(wheel_timing.len() - 1);
+        self.show.wheel_timing = wheel_timing;
+    }
+
+    // Runs the game for a number of frames, stopping early if it ends.
+    fn advance_game(&mut self, ticks: u8) {
+        for _ in 0..ticks {
+            self.game_frame();
+            self.script_frame();
+            if self.in_attract {
+                break;
+            }
+        }
+    }
+
+    fn game_frame(&mut self) {
+        self.scroll.update(self.ball.pos().1);
+        if let Some(players) = self.start_key {
+            self.start_key = None;
+            self.total_players = players;
+            self.players = vec![PlayerState::new(self.assets.table); players as usize];
+            self.start_script(ScriptBind::GameStartPlayers);
+            self.play_sfx_bind(SfxBind::GameStart);
+            self.add_task(TaskKind::SetStartKeysActive);
+        }
+        for _ in self.take_stepped_physics()..self.physics_frames() {
+            self.physics_frame();
+        }
+        if !self.trail_shades.is_empty() {
+            self.ball.record_trail(self.trail_shades.len());
+        }
+        if self.tilt_counter != 0 {
+            self.tilt_counter -= 1;
+        }
+        if self.ball_save_frames != 0 {
+            self.ball_save_frames -= 1;
+        }
+        self.for_each_ball(|table| {
+            table.score_bumper();
+            table.ball_gravity();
+            table.check_transitions();
+        });
+        self.retire_drained_balls();
+        if self.drained
+            && !self.in_drain
+            && self.ball_save_frames != 0
+            && !self.tilted
+            && !self.block_drain
+        {
+            self.ball_save();
+        }
+        if self.drained && !self.in_drain {
+            self.ball.teleport_freeze(Layer::Ground, (280, 525));
+            self.flippers_enabled = false;
+            self.in_mode = false;
+            self.in_mode_hit = false;
+            self.in_mode_ramp = false;
+            if !self.block_drain {
+                self.in_drain = true;
+                self.emit(GameEvent::BallDrained {
+                    player: self.cur_player,
+                    ball: self.cur_ball,
+                });
+                if self.cheat.practice {
+                    self.practice_drained();
+                } else {
+                    match self.assets.table {
+                        TableId::Table1 => self.party_drained(),
+                        TableId::Table2 => self.speed_drained(),
+                        TableId::Table3 => self.show_drained(),
+                        TableId::Table4 => self.stones_drained(),
+                    }
+                }
+            }
+        }
+        match self.assets.table {
+            TableId::Table1 => self.party_frame(),
+            TableId::Table2 => self.speed_frame(),
+            TableId::Table3 => self.show_frame(),
+            TableId::Table4 => self.stones_frame(),
+        };
+        self.for_each_ball(|table| {
+            table.do_roll_triggers();
+            table.do_hit_triggers();
+        });
+        if self.flipper_pressed {
+            self.flipper_pressed = false;
+            match self.assets.table {
+                TableId::Table1 => self.party_flipper_pressed(),
+                TableId::Table2 => self.speed_flipper_pressed(),
+                TableId::Table3 => self.show_flipper_pressed(),
+                TableId::Table4 => self.stones_flipper_pressed(),
+            }
+        }
+        for (dir, pressed) in self.nudge_pressed {
+            if !pressed {
+                continue;
+            }
+            self.nudge_pressed[dir] = false;
+            if dir != NudgeDir::Up && !self.ball.frozen {
+                let impulse = if dir == NudgeDir::Left {
+                    -self.push.side_impulse
+                } else {
+                    self.push.side_impulse
+                };
+                self.ball.speed.0 =
+                    (self.ball.speed.0 + impulse).clamp(-self.ball.max_speed, self.ball.max_speed);
+            }
+            if !self.cheat.no_tilt && !self.in_plunger && !self.drained && !self.tilted {
+                self.tilt_counter += self.tilt_nudge_increment;
+                if self.tilt_counter > self.tilt_limit {
+                    self.tilted = true;
+                    self.emit(GameEvent::Tilt);
+                    self.flippers_enabled = false;
+                    self.play_jingle_bind_silence(JingleBind::Tilt);
+                    self.start_script(ScriptBind::Tilt);
+                    self.lights.tilt();
+                    self.party.secret_drop_release = true;
+                } else if self.tilt_counter > self.tilt_warn_threshold {
+                    self.play_jingle_bind(JingleBind::WarnTilt);
+                }
+            }
+        }
+        self.dm.blink_frame();
+        self.tasks_frame();
+        self.check_replay();
+        self.lights.blink_frame();
+        if self.spring_released && self.spring_pos != 0 {
+            self.spring_release();
+            self.spring_released = false;
+        } else if self.spring_down_state && self.spring_pos < 0x20 {
+            self.spring_pos += 1;
+        }
+        self.aim_guide =
+            if self.options.aim_guide && !self.tournament && self.at_spring && self.spring_pos != 0
+            {
+                self.predict_launch()
+            } else {
+                vec![]
+            };
+    }
+
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        self.options.resolution = resolution;
+        self.scroll.set_resolution(resolution);
+    }
+
+    fn take_high_scores(&mut self) -> Option<Action> {
+        if !std::mem::take(&mut self.flush_high_scores)
+            || self.cheated
+            || self.cheat.speed_used
+            || self.cheat.practice
+        {
+            return None;
+        }
+        Some(Action::SaveHighScores(
+            self.assets.table,
+            self.high_scores.clone(),
+        ))
+    }
+
+    pub fn table_id(&self) -> TableId {
+        self.assets.table
+    }
+
+    // Where leaving the table goes back to.
+    fn exit_route(&self) -> Route {
+        if self.options.table_select {
+            Route::TableSelect(Some(self.assets.table))
+        } else {
+            Route::Intro(Some(self.assets.table))
+        }
+    }
+
+    pub fn status(&self) -> GameStatus {
+        GameStatus {
+            score: self.score_main.to_u64(),
+            cur_player: self.cur_player,
+            total_players: self.total_players,
+            cur_ball: self.cur_ball,
+            total_balls: self.total_balls,
+            extra_balls: self.extra_balls,
+            tilted: self.tilted,
+            in_mode: self.in_mode,
+            in_attract: self.in_attract,
+        }
+    }
+
+    pub fn ball_debug(&self) -> BallDebug {
+        self.ball.debug()
+    }
+
     pub fn toggle_music(&mut self) {
         if self.options.no_music {
             self.options.no_music = false;
@@ -339,6 +1025,92 @@ impl Table {
         }
         self.sequencer.set_no_music(self.options.no_music);
     }
+
+    fn flipper_key(&mut self, side: FlipperSide, state: ElementState) {
+        if state == ElementState::Pressed && self.flippers_enabled && !self.flipper_state[side] {
+            self.flipper_pressed = true;
+            self.play_sfx_bind(SfxBind::FlipperPress);
+        }
+        self.flipper_state[side] = state == ElementState::Pressed;
+    }
+
+    fn nudge_key(&mut self, dir: NudgeDir, state: ElementState) {
+        if state == ElementState::Pressed && !self.nudge_state[dir] {
+            self.nudge_pressed[dir] = true;
+        }
+        self.nudge_state[dir] = state == ElementState::Pressed;
+    }
+
+    fn render_ball_sprite(
+        &self,
+        data: &mut [u8],
+        height: usize,
+        top: usize,
+        (bx, by): (i16, i16),
+        layer: Layer,
+        shade: Option<&[u8; 256]>,
+    ) {
+        let color = (self.options.ball_style != BallStyle::Sprite)
+            .then(|| self.ball_colors[self.options.ball_color]);
+        let sprite = &self.assets.ball.data;
+        // a pixel on the edge of the sprite has a transparent neighbour
+        let edge = |x: i16, y: i16| {
+            [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                let (x, y) = (x + dx, y + dy);
+                !(0..15).contains(&x)
+                    || !(0..15).contains(&y)
+                    || sprite[(x as usize, y as usize)] == 0
+            })
+        };
+        for ball_y in 0..15 {
+            let sy = by + ball_y;
+            if sy < top as i16 || sy >= (top + height) as i16 {
+                continue;
+            }
+            let (sy, y) = (sy as usize, sy as usize - top);
+            for ball_x in 0..15 {
+                let mut pix = sprite[(ball_x as usize, ball_y as usize)];
+                if pix == 0 {
+                    continue;
+                }
+                if let Some(color) = color {
+                    if self.options.ball_style == BallStyle::Solid || edge(ball_x, ball_y) {
+                        pix = color;
+                    }
+                }
+                let x = ball_x + bx;
+                if !(0..320).contains(&x) {
+                    continue;
+                }
+                if sy < 576 && self.assets.occmaps[layer][(x as usize, sy)] != 0 {
+                    continue;
+                }
+                data[y * 320 + x as usize] = shade.map_or(pix, |shade| shade[usize::from(pix)]);
+            }
+        }
+    }
+
+    fn board_height(&self) -> usize {
+        match self.options.resolution {
+            Resolution::Normal => 240 - 33,
+            Resolution::High => 350 - 33,
+            Resolution::Full => 576,
+        }
+    }
+
+    fn release_inputs(&mut self) {
+        self.flipper_state = enum_map! { _ => false };
+        self.nudge_state = EnumMap::default();
+        self.spring_down_state = false;
+        self.plunger_drag = None;
+    }
+
+    fn plunger_key(&mut self, state: ElementState) {
+        self.spring_down_state = state == ElementState::Pressed;
+        if state == ElementState::Released {
+            self.spring_released = true;
+        }
+    }
 }
 
 impl View for Table {
@@ -354,31 +1126,52 @@ impl View for Table {
     }
 
     fn get_fps(&self) -> u32 {
-        60
+        if self.hifps {
+            HIFPS
+        } else {
+            60
+        }
     }
 
     fn run_frame(&mut self) -> Action {
-        if matches!(
-            self.kbd_state,
-            KbdState::Paused | KbdState::PausedConfirmQuit
-        ) {
+        self.player.advance(self.get_fps());
+        if self.flush_options {
+            self.flush_options = false;
+            Action::SaveOptions(self.options)
+        } else if self.paused() {
             Action::None
         } else if self.quitting {
+            if self.fade == 0 {
+                // the last frame went to saving the high scores
+                return Action::Navigate(self.exit_route());
+            }
             self.fade -= 2;
             self.player.set_master_volume(self.fade.into());
             if self.fade == 0 {
-                Action::Navigate(Route::Intro(Some(self.assets.table)))
+                self.take_high_scores()
+                    .unwrap_or_else(|| Action::Navigate(self.exit_route()))
             } else {
                 Action::None
             }
+        } else if let Some(state) = self.take_rewind() {
+            Action::RewindBall(state)
         } else {
             if self.in_attract {
+                if self.attract_idle_frame() {
+                    return self
+                        .take_high_scores()
+                        .unwrap_or_else(|| Action::Navigate(self.exit_route()));
+                }
                 self.scroll.attract_frame();
                 self.lights.attract_frame(&self.assets);
                 self.dm.blink_frame();
                 if let Some(players) = self.start_key {
                     self.start_key = None;
                     self.total_players = players;
+                    // the ball count may have been changed in the options since
+                    if !self.tournament && !self.cheat.extra_balls {
+                        self.total_balls = self.options.balls.clamp(MIN_BALLS, MAX_BALLS);
+                    }
                     self.players = vec![PlayerState::new(self.assets.table); players as usize];
                     self.start_script(ScriptBind::GameStart);
                     self.play_sfx_bind(SfxBind::GameStart);
@@ -393,94 +1186,39 @@ impl View for Table {
                     .unwrap();
                     self.sequencer
                         .play_jingle(jingle, true, Some(plunger.position));
+                    self.caption_jingle(jingle);
                     self.issue_ball();
                     self.add_task(TaskKind::SetStartKeysActive);
                 }
+                self.script_frame();
             } else {
-                self.scroll.update(self.ball.pos().1);
-                if let Some(players) = self.start_key {
-                    self.start_key = None;
-                    self.total_players = players;
-                    self.players = vec![PlayerState::new(self.assets.table); players as usize];
-                    self.start_script(ScriptBind::GameStartPlayers);
-                    self.play_sfx_bind(SfxBind::GameStart);
-                    self.add_task(TaskKind::SetStartKeysActive);
-                }
-                if !self.cheat.slowdown {
-                    self.physics_frame();
-                }
-                self.physics_frame();
-                self.physics_frame();
-                self.physics_frame();
-                if self.tilt_counter != 0 {
-                    self.tilt_counter -= 1;
-                }
-                self.score_bumper();
-                self.ball_gravity();
-                self.check_transitions();
-                if self.drained && !self.in_drain {
-                    self.ball.teleport_freeze(Layer::Ground, (280, 525));
-                    self.flippers_enabled = false;
-                    self.in_mode = false;
-                    self.in_mode_hit = false;
-                    self.in_mode_ramp = false;
-                    if !self.block_drain {
-                        self.in_drain = true;
-                        match self.assets.table {
-                            TableId::Table1 => self.party_drained(),
-                            TableId::Table2 => self.speed_drained(),
-                            TableId::Table3 => self.show_drained(),
-                            TableId::Table4 => self.stones_drained(),
-                        }
-                    }
-                }
-                match self.assets.table {
-                    TableId::Table1 => self.party_frame(),
-                    TableId::Table2 => self.speed_frame(),
-                    TableId::Table3 => self.show_frame(),
-                    TableId::Table4 => self.stones_frame(),
+                self.clear_dm_overlay();
+                let ticks = if self.cheat.frame_step {
+                    self.frame_step_ticks()
+                } else {
+                    self.cheat.speed_ticks()
                 };
-                self.do_roll_triggers();
-                self.do_hit_triggers();
-                if self.flipper_pressed {
-                    self.flipper_pressed = false;
-                    match self.assets.table {
-                        TableId::Table1 => self.party_flipper_pressed(),
-                        TableId::Table2 => self.speed_flipper_pressed(),
-                        TableId::Table3 => self.show_flipper_pressed(),
-                        TableId::Table4 => self.stones_flipper_pressed(),
-                    }
-                }
-                if self.space_pressed {
-                    self.space_pressed = false;
-                    if !self.cheat.no_tilt && !self.in_plunger && !self.drained && !self.tilted {
-                        self.tilt_counter += 60;
-                        if self.tilt_counter > 120 {
-                            self.tilted = true;
-                            self.flippers_enabled = false;
-                            self.play_jingle_bind_silence(JingleBind::Tilt);
-                            self.start_script(ScriptBind::Tilt);
-                            self.lights.tilt();
-                            self.party.secret_drop_release = true;
-                        } else if self.tilt_counter > 60 {
-                            self.play_jingle_bind(JingleBind::WarnTilt);
-                        }
-                    }
-                }
-                self.dm.blink_frame();
-                self.tasks_frame();
-                self.lights.blink_frame();
-                if self.spring_released && self.spring_pos != 0 {
-                    self.spring_release();
-                    self.spring_released = false;
-                } else if self.spring_down_state && self.spring_pos < 0x20 {
-                    self.spring_pos += 1;
-                }
+                self.advance_game(ticks);
+                self.dm_overlay();
             }
-            self.script_frame();
-            if self.flush_high_scores {
-                self.flush_high_scores = false;
-                Action::SaveHighScores(self.assets.table, self.high_scores)
+            if self.cheat.ball_overlay && !self.in_attract {
+                self.dm_ball_debug();
+            }
+            if self.cheat.speed_control && !self.in_attract {
+                self.dm_speed();
+            }
+            if self.cheat.practice && !self.in_attract {
+                self.dm_practice();
+            }
+            if std::mem::take(&mut self.snapshot_pending) {
+                self.snapshot_ball();
+            }
+            self.caption_frame();
+            if self.cheat.frame_step && !self.in_attract {
+                self.dm_frame_step();
+            }
+            if self.options.high_score_saving == HighScoreSaving::Immediate {
+                self.take_high_scores().unwrap_or(Action::None)
             } else {
                 Action::None
             }
@@ -488,49 +1226,28 @@ impl View for Table {
     }
 
     fn handle_key(&mut self, key: VirtualKeyCode, state: ElementState) {
-        if matches!(
-            key,
-            VirtualKeyCode::LShift | VirtualKeyCode::LControl | VirtualKeyCode::LAlt
-        ) {
-            if state == ElementState::Pressed
-                && self.flippers_enabled
-                && !self.flipper_state[FlipperSide::Left]
-            {
-                self.flipper_pressed = true;
-                self.play_sfx_bind(SfxBind::FlipperPress);
-            }
-            self.flipper_state[FlipperSide::Left] = state == ElementState::Pressed;
-        }
-        if matches!(
-            key,
-            VirtualKeyCode::RShift | VirtualKeyCode::RControl | VirtualKeyCode::RAlt
-        ) {
-            if state == ElementState::Pressed
-                && self.flippers_enabled
-                && !self.flipper_state[FlipperSide::Right]
-            {
-                self.flipper_pressed = true;
-                self.play_sfx_bind(SfxBind::FlipperPress);
+        self.reset_attract_idle();
+        if !self.paused() {
+            if is_bound(&self.key_bindings.flipper_left, key) {
+                self.flipper_key(FlipperSide::Left, state);
             }
-            self.flipper_state[FlipperSide::Right] = state == ElementState::Pressed;
-        }
-
-        if key == VirtualKeyCode::Space {
-            if state == ElementState::Pressed && !self.space_state {
-                self.space_pressed = true;
+            if is_bound(&self.key_bindings.flipper_right, key) {
+                self.flipper_key(FlipperSide::Right, state);
             }
-            self.space_state = state == ElementState::Pressed;
-        }
 
-        if key == VirtualKeyCode::Down {
-            self.spring_down_state = state == ElementState::Pressed;
-            if state == ElementState::Released {
-                self.spring_released = true;
+            if is_bound(&self.key_bindings.nudge, key) {
+                self.nudge_key(NudgeDir::Up, state);
+            }
+            if is_bound(&self.key_bindings.nudge_left, key) {
+                self.nudge_key(NudgeDir::Left, state);
+            }
+            if is_bound(&self.key_bindings.nudge_right, key) {
+                self.nudge_key(NudgeDir::Right, state);
             }
-        }
 
-        if state != ElementState::Pressed {
-            return;
+            if is_bound(&self.key_bindings.plunger, key) {
+                self.plunger_key(state);
+            }
         }
 
         let chr = match key {
@@ -564,9 +1281,19 @@ impl View for Table {
             _ => None,
         };
 
+        if state != ElementState::Pressed {
+            if let Some(chr) = chr {
+                self.cheat.key_up(chr);
+            }
+            return;
+        }
+        let new_chr = chr.filter(|&chr| self.cheat.key_down(chr));
+
         match self.kbd_state {
             KbdState::Main => {
                 match key {
+                    _ if self.tournament => (),
+                    // debug scroll speeds, until the next ball
                     VirtualKeyCode::F9 => self.scroll.set_speed(9),
                     VirtualKeyCode::F10 => self.scroll.set_speed(11),
                     VirtualKeyCode::F11 => self.scroll.set_speed(20),
@@ -598,23 +1325,46 @@ impl View for Table {
                     }
                 }
 
+                if key == VirtualKeyCode::Tab {
+                    self.toggle_cheat_menu();
+                }
+                if key == VirtualKeyCode::Back && self.cheat.practice && !self.in_attract {
+                    self.rewind = self.ball_snapshot.is_some();
+                }
+                if !self.cheat_menu_key(key) && !self.tuning_key(key) && self.cheat.sound_overlay {
+                    self.sound_debug_key(key);
+                }
+
                 if self.in_attract {
-                    if let Some(chr) = chr {
+                    if let Some(chr) = new_chr {
                         self.handle_cheat(chr);
                     }
-                    if key == VirtualKeyCode::Escape {
+                    if is_bound(&self.key_bindings.quit, key) {
                         self.kbd_state = KbdState::ConfirmQuit;
                         self.start_script(ScriptBind::ConfirmQuit);
                     }
                 } else if !self.in_drain {
                     match key {
-                        VirtualKeyCode::Escape if self.at_spring => self.abort_game(),
-                        VirtualKeyCode::M => self.toggle_music(),
-                        VirtualKeyCode::P => self.pause(),
-                        // VirtualKeyCode::W => self.ball.speed = (0, -1000),
-                        // VirtualKeyCode::S => self.ball.speed = (0, 1000),
-                        // VirtualKeyCode::A => self.ball.speed = (-1000, 0),
-                        // VirtualKeyCode::D => self.ball.speed = (1000, 0),
+                        _ if is_bound(&self.key_bindings.quit, key) && self.at_spring => {
+                            self.abort_game()
+                        }
+                        _ if is_bound(&self.key_bindings.pause, key) => self.pause(),
+                        VirtualKeyCode::M if !self.tournament => self.toggle_music(),
+                        VirtualKeyCode::W if self.cheat.flyball => self.ball.speed = (0, -1000),
+                        VirtualKeyCode::S if self.cheat.flyball => self.ball.speed = (0, 1000),
+                        VirtualKeyCode::A if self.cheat.flyball => self.ball.speed = (-1000, 0),
+                        VirtualKeyCode::D if self.cheat.flyball => self.ball.speed = (1000, 0),
+                        VirtualKeyCode::Plus
+                        | VirtualKeyCode::Equals
+                        | VirtualKeyCode::NumpadAdd => self.cheat.speed_up(),
+                        VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                            self.cheat.speed_down()
+                        }
+                        VirtualKeyCode::Period => self.cheat.step_game_frame(),
+                        VirtualKeyCode::Comma => self.cheat.step_physics_frame(),
+                        VirtualKeyCode::L if self.cheat.physmap_overlay => {
+                            self.cycle_physmap_layer()
+                        }
                         _ => (),
                     }
                 }
@@ -636,12 +1386,36 @@ impl View for Table {
                 if state != ElementState::Pressed {
                     return;
                 }
-                if key == VirtualKeyCode::Escape {
-                    self.dm.clear();
-                    self.dm_puts(DmFont::H13, DmCoord { x: 0, y: 1 }, b"REALLY QUIT (Y OR N)");
-                    self.kbd_state = KbdState::PausedConfirmQuit;
-                } else {
-                    self.unpause();
+                match key {
+                    _ if is_bound(&self.key_bindings.quit, key) => self.pause_confirm_quit(),
+                    _ if is_bound(&self.key_bindings.pause, key) => self.unpause(),
+                    VirtualKeyCode::F5 if !self.tournament => self.open_options(),
+                    VirtualKeyCode::Up => {
+                        self.pause_sel = (self.pause_sel + PAUSE_MENU.len() - 1) % PAUSE_MENU.len();
+                        self.dm_pause_menu();
+                    }
+                    VirtualKeyCode::Down => {
+                        self.pause_sel = (self.pause_sel + 1) % PAUSE_MENU.len();
+                        self.dm_pause_menu();
+                    }
+                    VirtualKeyCode::Return => match self.pause_sel {
+                        0 => self.unpause(),
+                        1..=3 if self.tournament => (),
+                        1 => self.toggle_music(),
+                        2 => {
+                            self.rebuild_physics(!self.hifps);
+                            self.options.hifps = self.hifps;
+                            self.flush_options = true;
+                        }
+                        3 => self.open_options(),
+                        _ => self.pause_confirm_quit(),
+                    },
+                    _ => (),
+                }
+            }
+            KbdState::PausedOptions => {
+                if state == ElementState::Pressed {
+                    self.options_key(key);
                 }
             }
             KbdState::PausedConfirmQuit => {
@@ -656,53 +1430,136 @@ impl View for Table {
                     self.unpause();
                 }
             }
-            KbdState::GetName => {
-                if let Some(chr) = chr {
-                    let _ = self.name_buf.try_push(chr);
+            KbdState::GetName => match key {
+                VirtualKeyCode::Back => {
+                    if self.name_buf.pop().is_some() {
+                        self.play_sfx_bind(SfxBind::TickBonus);
+                    }
+                }
+                VirtualKeyCode::Return => self.name_confirmed = true,
+                _ => {
+                    if let Some(chr) = chr {
+                        if !self.name_buf.is_full() {
+                            self.name_buf.push(chr);
+                            self.play_sfx_bind(SfxBind::TickBonus);
+                        }
+                    }
                 }
+            },
+            KbdState::GameSummary => self.kbd_state = KbdState::Main,
+        }
+    }
+
+    fn handle_button(&mut self, button: PadButton, state: ElementState) {
+        self.reset_attract_idle();
+        match button {
+            PadButton::LeftShoulder
+            | PadButton::RightShoulder
+            | PadButton::South
+            | PadButton::Nudge
+                if self.paused() => {}
+            PadButton::LeftShoulder => self.flipper_key(FlipperSide::Left, state),
+            PadButton::RightShoulder => self.flipper_key(FlipperSide::Right, state),
+            PadButton::South => self.plunger_key(state),
+            PadButton::Nudge => self.nudge_key(NudgeDir::Up, state),
+            PadButton::Start => self.handle_key(VirtualKeyCode::Return, state),
+            PadButton::DPadUp if self.paused_menu() => self.handle_key(VirtualKeyCode::Up, state),
+            PadButton::DPadDown if self.paused_menu() => {
+                self.handle_key(VirtualKeyCode::Down, state)
+            }
+            PadButton::DPadLeft if self.kbd_state == KbdState::PausedOptions => {
+                self.handle_key(VirtualKeyCode::Left, state)
+            }
+            PadButton::DPadRight if self.kbd_state == KbdState::PausedOptions => {
+                self.handle_key(VirtualKeyCode::Right, state)
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_mouse(
+        &mut self,
+        (x, y): (u32, u32),
+        button: MouseButton,
+        state: ElementState,
+    ) -> bool {
+        self.reset_attract_idle();
+        if button != MouseButton::Left {
+            return false;
+        }
+        match (self.plunger_drag, state) {
+            (None, ElementState::Pressed) => {
+                let height = self.board_height() as u32;
+                if self.kbd_state != KbdState::Main
+                    || x < PLUNGER_MOUSE_X
+                    || !(height / 2..height).contains(&y)
+                {
+                    return false;
+                }
+                self.plunger_drag = Some(y);
+                self.spring_released = false;
+            }
+            (Some(start), ElementState::Pressed) => {
+                let pull = y.saturating_sub(start) * 0x20 / PLUNGER_DRAG_RANGE;
+                self.spring_pos = pull.min(0x20) as u8;
+            }
+            (Some(_), ElementState::Released) => {
+                self.plunger_drag = None;
+                self.spring_released = true;
             }
+            (None, ElementState::Released) => return false,
         }
+        true
+    }
+
+    fn on_focus_lost(&mut self) {
+        self.release_inputs();
+        self.cheat.release_keys();
+    }
+
+    fn render_ahead(&mut self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.render_ahead_frame(data, pal);
     }
 
     fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
         pal.copy_from_slice(&self.assets.main_board.cmap);
+        // in eighths of the full color; with reduced flashing, blinking lights
+        // stay steady and the rest swing less between lit and unlit
+        let reduce_flashing = self.options.reduce_flashing;
         for (lid, light) in &self.assets.lights {
-            if self.lights.is_lit(lid) {
-                for (i, color) in light.colors.iter().enumerate() {
-                    pal[light.base_index as usize + i] = *color;
-                }
+            let level = if reduce_flashing && self.lights.is_blinking(lid) {
+                7
+            } else if self.lights.is_lit(lid) {
+                8
+            } else if reduce_flashing {
+                6
             } else {
-                for (i, color) in light.colors.iter().enumerate() {
-                    pal[light.base_index as usize + i] = (color.0 / 2, color.1 / 2, color.2 / 2);
-                }
+                4
+            };
+            for (i, color) in light.colors.iter().enumerate() {
+                let dim = |c: u8| (u16::from(c) * level / 8) as u8;
+                pal[light.base_index as usize + i] = (dim(color.0), dim(color.1), dim(color.2));
             }
         }
-        pal[self.assets.dm_palette.index_on as usize] = if self.dm.state() {
-            self.assets.dm_palette.color_on
-        } else {
-            self.assets.dm_palette.color_off
-        };
-        let height = match self.options.resolution {
-            Resolution::Normal => 240 - 33,
-            Resolution::High => 350 - 33,
-            Resolution::Full => 576,
-        };
+        pal[self.assets.dm_palette.index_on as usize] =
+            if self.dm.state() || (reduce_flashing && self.dm.is_blinking()) {
+                self.assets.dm_palette.color_on
+            } else {
+                self.assets.dm_palette.color_off
+            };
+        let height = self.board_height();
         let spring_pos = self.spring_pos as usize / 2;
         let (bx, mut by) = self.ball.pos();
         if !self.ball.frozen {
             by += self.push.offset();
         }
+        let top = self.scroll.pos() as usize + self.push.offset() as usize;
+        self.board_cache
+            .borrow_mut()
+            .update(&self.assets.main_board.data, height, top);
+        data[..height * 320].copy_from_slice(&self.board_cache.borrow().rows);
         for y in 0..height {
-            let sy = y + self.scroll.pos() as usize + self.push.offset() as usize;
-            if sy >= 576 {
-                for x in 0..320 {
-                    data[y * 320 + x] = 0;
-                }
-            } else {
-                for x in 0..320 {
-                    data[y * 320 + x] = self.assets.main_board.data[(x, sy)];
-                }
-            }
+            let sy = y + top;
             if (556..556 + 17).contains(&sy) {
                 let spring_y = sy - 553;
                 if spring_y >= spring_pos {
@@ -725,21 +1582,60 @@ impl View for Table {
                     }
                 }
             }
-            if (by..by + 15).contains(&(sy as i16)) {
-                let ball_y = sy as i16 - by;
-                for ball_x in 0..15 {
-                    let pix = self.assets.ball.data[(ball_x as usize, ball_y as usize)];
-                    if pix == 0 {
-                        continue;
-                    }
-                    let x = ball_x + bx;
-                    if !(0..320).contains(&x) {
-                        continue;
-                    }
-                    if sy < 576 && self.assets.occmaps[self.ball.layer][(x as usize, sy)] != 0 {
-                        continue;
-                    }
-                    data[y * 320 + x as usize] = pix;
+        }
+        if self.cheat.physmap_overlay {
+            self.render_physmap(data, pal, height, top);
+        }
+        // oldest first, so newer sprites and the ball itself end up on top
+        for (&((tx, ty), layer), shade) in
+            self.ball.trail.iter().skip(1).zip(&self.trail_shades).rev()
+        {
+            let ty = ty + self.push.offset();
+            self.render_ball_sprite(data, height, top, (tx, ty), layer, Some(shade));
+        }
+        for (pos, layer) in self.aux_ball_sprites() {
+            self.render_ball_sprite(data, height, top, pos, layer, None);
+        }
+        self.render_ball_sprite(data, height, top, (bx, by), self.ball.layer, None);
+        // every other predicted position, as a dotted line
+        for &(x, y) in self.aim_guide.iter().skip(1).step_by(2) {
+            let y = y as isize - top as isize;
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (x, y) = (x as isize + dx, y + dy);
+                if (0..320).contains(&x) && (0..height as isize).contains(&y) {
+                    data[y as usize * 320 + x as usize] = self.assets.dm_palette.index_on;
+                }
+            }
+        }
+        if self.cheat.ball_overlay {
+            self.render_ball_debug(data, height);
+        }
+        if self.cheat.sound_overlay {
+            self.render_sound_debug(data, height);
+        }
+        if self.cheat.menu {
+            self.render_cheat_menu(data, height);
+        }
+        if self.cheat.tuning {
+            self.render_tuning(data, height);
+        }
+        self.render_caption(data, height);
+        if self.tournament && self.in_attract {
+            for row in data[..10 * 320].chunks_exact_mut(320) {
+                row[..84].fill(0);
+            }
+            self.render_board_text(data, 2, 2, b"TOURNAMENT");
+        }
+        let lean = self.push.offset_x();
+        if lean != 0 {
+            let shift = lean.unsigned_abs() as usize;
+            for row in data[..height * 320].chunks_exact_mut(320) {
+                if lean > 0 {
+                    row.copy_within(..320 - shift, shift);
+                    row[..shift].fill(0);
+                } else {
+                    row.copy_within(shift.., 0);
+                    row[320 - shift..].fill(0);
                 }
             }
         }
@@ -755,12 +1651,8 @@ impl View for Table {
             }
         }
 
-        if self.options.mono {
-            for color in &mut pal[..] {
-                let mono = ((color.0 as u16 + color.1 as u16 + color.2 as u16) / 3) as u8;
-                *color = (mono, mono, mono);
-            }
-        }
+        self.options.color_mode.apply(pal);
+        self.options.apply_gamma(pal);
 
         if self.fade != 0x100 {
             for color in pal {
@@ -770,4 +1662,53 @@ impl View for Table {
             }
         }
     }
+
+    fn render_frame_stats(&self, data: &mut [u8], _pal: &mut [(u8, u8, u8)], text: &[u8]) {
+        let x = 320 - 2 - text.len() * 8;
+        for row in data[..10 * 320].chunks_exact_mut(320) {
+            row[x - 2..].fill(0);
+        }
+        self.render_board_text(data, x, 2, text);
+    }
+
+    fn game_status(&self) -> Option<GameStatus> {
+        Some(self.status())
+    }
+
+    fn set_resolution(&mut self, resolution: Resolution) -> bool {
+        Table::set_resolution(self, resolution);
+        true
+    }
+
+    fn take_unsaved_high_scores(&mut self) -> Option<(TableId, Vec<HighScore>)> {
+        match self.take_high_scores() {
+            Some(Action::SaveHighScores(table, high_scores)) => Some((table, high_scores)),
+            _ => None,
+        }
+    }
+
+    fn get_extra_panel(&self) -> Option<PanelLayout> {
+        self.panel_layout()
+    }
+
+    fn render_panel(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        Table::render_panel(self, data, pal);
+    }
+
+    fn scroll_blend(&self) -> Option<(usize, u8)> {
+        let frac = self.scroll.frac();
+        (self.options.smooth_scroll && frac != 0).then(|| (self.board_height(), frac))
+    }
+
+    fn display_text(&self) -> Vec<String> {
+        self.dm.text_lines()
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(Table::save_state(self))
+    }
+
+    fn snapshot(&self) -> Option<Snapshot> {
+        Some(Table::snapshot(self))
+    }
 }
@@ -0,0 +1,79 @@
+// A player for the attract demo: it flips when the ball comes down onto a
+// flipper and launches each ball from the spring.
+
+use enum_map::EnumMap;
+use winit::event::ElementState;
+
+use crate::{
+    assets::table::{flippers::FlipperSide, physics::Rect},
+    view::View,
+};
+
+use super::Table;
+
+// in frames at 60 fps
+const FLIP_FRAMES: u32 = 10;
+const FLIP_REST_FRAMES: u32 = 8;
+const PLUNGE_FRAMES: u32 = 45;
+// how far outside a flipper's reach the ball still gets it flipped
+const FLIP_MARGIN: i16 = 6;
+
+#[derive(Default)]
+pub struct Autoplay {
+    // frames left until each flipper may fire again; it is held for the
+    // first FLIP_FRAMES of them
+    flips: EnumMap<FlipperSide, u32>,
+    plunge: u32,
+}
+
+impl Table {
+    pub fn in_attract(&self) -> bool {
+        self.in_attract
+    }
+
+    pub fn autoplay_frame(&mut self, autoplay: &mut Autoplay) {
+        let scale = self.get_fps() / 60;
+        let ball = self.ball.pos_center();
+        let falling = self.ball.speed.1 > 0 && !self.ball.frozen;
+        let mut reached = EnumMap::<FlipperSide, bool>::default();
+        for (_, flipper) in &self.assets.flippers {
+            let reach = Rect {
+                xy_min: (
+                    flipper.ball_bbox.xy_min.0 - FLIP_MARGIN,
+                    flipper.ball_bbox.xy_min.1 - FLIP_MARGIN,
+                ),
+                xy_max: (
+                    flipper.ball_bbox.xy_max.0 + FLIP_MARGIN,
+                    flipper.ball_bbox.xy_max.1 + FLIP_MARGIN,
+                ),
+            };
+            reached[flipper.side] |= falling && reach.contains(ball);
+        }
+        for (side, reached) in reached {
+            let left = autoplay.flips[side];
+            if left == 0 {
+                if reached && !self.in_drain {
+                    autoplay.flips[side] = (FLIP_FRAMES + FLIP_REST_FRAMES) * scale;
+                    self.flipper_key(side, ElementState::Pressed);
+                }
+                continue;
+            }
+            if left == FLIP_REST_FRAMES * scale {
+                self.flipper_key(side, ElementState::Released);
+            }
+            autoplay.flips[side] = left - 1;
+        }
+
+        if !self.at_spring || self.in_attract {
+            autoplay.plunge = 0;
+            return;
+        }
+        autoplay.plunge += 1;
+        if autoplay.plunge == 1 {
+            self.plunger_key(ElementState::Pressed);
+        } else if autoplay.plunge >= PLUNGE_FRAMES * scale {
+            self.plunger_key(ElementState::Released);
+            autoplay.plunge = 0;
+        }
+    }
+}
@@ -1,9 +1,21 @@
+use std::collections::VecDeque;
+
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::assets::table::physics::Layer;
 
 use super::physics::speed_fix;
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BallDebug {
+    pub pos: (i16, i16),
+    pub speed: (i16, i16),
+    pub layer: Layer,
+    pub frozen: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BallState {
     pub layer: Layer,
     pub pos_hires: (i32, i32),
@@ -12,6 +24,9 @@ pub struct BallState {
     pub frozen: bool,
     pub rotation: i16,
     pub max_speed: i16,
+    // recent positions, newest first, for the ball trail effect
+    #[serde(skip)]
+    pub trail: VecDeque<((i16, i16), Layer)>,
 }
 
 impl BallState {
@@ -24,9 +39,19 @@ impl BallState {
             frozen: true,
             rotation: 0,
             max_speed: speed_fix(4100, hifps),
+            trail: VecDeque::new(),
         }
     }
 
+    pub fn record_trail(&mut self, len: usize) {
+        if self.frozen {
+            self.trail.clear();
+            return;
+        }
+        self.trail.push_front((self.pos(), self.layer));
+        self.trail.truncate(len + 1);
+    }
+
     pub fn pos(&self) -> (i16, i16) {
         (
             (self.pos_hires.0 >> 10) as i16,
@@ -41,6 +66,27 @@ impl BallState {
         )
     }
 
+    pub fn speed(&self) -> (i16, i16) {
+        self.speed
+    }
+
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn debug(&self) -> BallDebug {
+        BallDebug {
+            pos: self.pos(),
+            speed: self.speed(),
+            layer: self.layer(),
+            frozen: self.frozen(),
+        }
+    }
+
     pub fn set_pos(&mut self, pos: (i16, i16)) {
         self.pos_hires = (((pos.0 as i32) << 10), ((pos.1 as i32) << 10));
     }
@@ -50,14 +96,22 @@ impl BallState {
         self.set_pos(pos);
         self.speed = (0, 0);
         self.frozen = true;
+        self.trail.clear();
     }
 
-    pub fn teleport(&mut self, layer: Layer, pos: (i16, i16), speed: (i16, i16)) {
+    pub fn teleport(
+        &mut self,
+        layer: Layer,
+        pos: (i16, i16),
+        speed: (i16, i16),
+        rng: &mut impl Rng,
+    ) {
         self.layer = layer;
         self.set_pos(pos);
         self.speed = speed;
         self.frozen = false;
-        let random: i16 = rand::thread_rng().gen_range(0..0x400);
+        self.trail.clear();
+        let random: i16 = rng.gen_range(0..0x400);
         if (random & 1) != 0 {
             self.rotation = -random;
         } else {
@@ -65,3 +119,37 @@ impl BallState {
         }
     }
 }
+
+// For each trail sprite, a map from palette index to the index closest to
+// that color dimmed by fade percent once more.  Only indices in usable are
+// picked, so the lights blinking can't change the trail's colors.
+pub fn trail_shades(
+    pal: &[(u8, u8, u8)],
+    len: usize,
+    fade: u8,
+    usable: impl Fn(usize) -> bool,
+) -> Vec<[u8; 256]> {
+    let candidates: Vec<usize> = (0..pal.len()).filter(|&idx| usable(idx)).collect();
+    let mut brightness = 100u32;
+    (0..len)
+        .map(|_| {
+            brightness = brightness * u32::from(fade.min(100)) / 100;
+            std::array::from_fn(|idx| {
+                let (red, green, blue) = pal.get(idx).copied().unwrap_or_default();
+                let target = [red, green, blue].map(|c| u32::from(c) * brightness / 100);
+                candidates
+                    .iter()
+                    .copied()
+                    .min_by_key(|&cand| {
+                        let (red, green, blue) = pal[cand];
+                        [red, green, blue]
+                            .into_iter()
+                            .zip(target)
+                            .map(|(c, t)| u32::from(c).abs_diff(t).pow(2))
+                            .sum::<u32>()
+                    })
+                    .unwrap_or(0) as u8
+            })
+        })
+        .collect()
+}
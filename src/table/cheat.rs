@@ -1,26 +1,277 @@
-use crate::assets::table::script::CheatEffect;
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
 
-use super::Table;
+use crate::{
+    assets::table::{
+        dm::DmFont,
+        physics::Layer,
+        script::{CheatEffect, DmCoord},
+        sound::{JingleBind, SfxBind},
+    },
+    config::{MAX_BALLS, MIN_BALLS},
+    palette::nearest_color,
+};
 
-#[derive(Debug)]
+use super::{tasks::TaskKind, Table};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CheatState {
     pub no_tilt: bool,
     pub slowdown: bool,
+    pub ball_overlay: bool,
+    pub flyball: bool,
+    pub speed_control: bool,
+    pub speed_used: bool,
+    pub sound_overlay: bool,
+    sound_page: usize,
+    pub practice: bool,
+    #[serde(default)]
+    pub extra_balls: bool,
+    #[serde(default)]
+    pub frame_step: bool,
+    // game frames stepped since frame stepping was switched on, and physics
+    // frames stepped on their own since the last game frame
+    #[serde(default)]
+    step_frames: u32,
+    #[serde(default)]
+    step_physics: u8,
+    #[serde(skip)]
+    step_request: Option<FrameStep>,
+    #[serde(default)]
+    pub physmap_overlay: bool,
+    // None follows the ball's layer
+    #[serde(default)]
+    physmap_layer: Option<Layer>,
+    // the tuned values aren't saved either
+    #[serde(skip)]
+    pub tuning: bool,
+    speed_level: usize,
+    speed_phase: u8,
     pub buf: Vec<u8>,
+    #[serde(default)]
+    pub menu: bool,
+    // letters and space held down, so key repeat doesn't type a code twice
+    #[serde(skip)]
+    keys_held: u32,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum DebugCheat {
+    BallOverlay,
+    FlyBall,
+    SpeedControl,
+    SoundOverlay,
+    Practice,
+    FrameStep,
+    PhysmapOverlay,
+    Tuning,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum FrameStep {
+    Game,
+    Physics,
+}
+
+const DEBUG_CHEATS: [(&[u8], DebugCheat); 8] = [
+    (b"BALLDEBUG", DebugCheat::BallOverlay),
+    (b"FLYBALL", DebugCheat::FlyBall),
+    (b"GAMESPEED", DebugCheat::SpeedControl),
+    (b"SOUNDDEBUG", DebugCheat::SoundOverlay),
+    (b"PRACTICE", DebugCheat::Practice),
+    (b"FRAMESTEP", DebugCheat::FrameStep),
+    (b"PHYSMAP", DebugCheat::PhysmapOverlay),
+    (b"TUNING", DebugCheat::Tuning),
+];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum MenuCheat {
+    Table(CheatEffect),
+    Debug(DebugCheat),
+}
+
+// The cheat menu, toggled with the number keys in this order.  Tuning is left
+// out, there are no number keys left for it.
+const CHEAT_MENU: [(&str, MenuCheat); 10] = [
+    ("NO TILT", MenuCheat::Table(CheatEffect::Tilt)),
+    ("SLOWDOWN", MenuCheat::Table(CheatEffect::Slowdown)),
+    ("5 BALLS", MenuCheat::Table(CheatEffect::Balls)),
+    ("BALL DEBUG", MenuCheat::Debug(DebugCheat::BallOverlay)),
+    ("FLY BALL", MenuCheat::Debug(DebugCheat::FlyBall)),
+    ("GAME SPEED", MenuCheat::Debug(DebugCheat::SpeedControl)),
+    ("SOUND DEBUG", MenuCheat::Debug(DebugCheat::SoundOverlay)),
+    ("PRACTICE", MenuCheat::Debug(DebugCheat::Practice)),
+    ("FRAME STEP", MenuCheat::Debug(DebugCheat::FrameStep)),
+    ("PHYSMAP", MenuCheat::Debug(DebugCheat::PhysmapOverlay)),
+];
+
+#[derive(Copy, Clone, Debug)]
+enum SoundEntry {
+    Jingle(JingleBind),
+    Sfx(SfxBind),
 }
 
+const SOUND_PAGE_LEN: usize = 10;
+const SOUND_KEYS: [VirtualKeyCode; SOUND_PAGE_LEN] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+    VirtualKeyCode::Key0,
+];
+
+// (game ticks, per this many frames)
+const SPEED_LEVELS: [(u8, u8); 10] = [
+    (1, 4),
+    (1, 2),
+    (1, 1),
+    (2, 1),
+    (3, 1),
+    (4, 1),
+    (5, 1),
+    (6, 1),
+    (7, 1),
+    (8, 1),
+];
+
+const SPEED_NORMAL: usize = 2;
+
+// Wall tints in the physmap overlay, by material.
+const PHYSMAP_COLORS: [(u8, u8, u8); 8] = [
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0x00, 0x00, 0xff),
+    (0xff, 0xff, 0x00),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0x80, 0x00),
+    (0xff, 0xff, 0xff),
+];
+
 impl CheatState {
     pub fn new() -> Self {
         CheatState {
             no_tilt: false,
             slowdown: false,
+            ball_overlay: false,
+            flyball: false,
+            speed_control: false,
+            speed_used: false,
+            sound_overlay: false,
+            sound_page: 0,
+            practice: false,
+            extra_balls: false,
+            frame_step: false,
+            step_frames: 0,
+            step_physics: 0,
+            step_request: None,
+            physmap_overlay: false,
+            physmap_layer: None,
+            tuning: false,
+            speed_level: SPEED_NORMAL,
+            speed_phase: 0,
             buf: vec![],
+            menu: false,
+            keys_held: 0,
+        }
+    }
+
+    pub fn active_cheats(&self) -> Vec<&'static str> {
+        [
+            (self.no_tilt, "NO TILT"),
+            (self.slowdown, "SLOWDOWN"),
+            (self.extra_balls, "5 BALLS"),
+            (self.ball_overlay, "BALL DEBUG"),
+            (self.flyball, "FLY BALL"),
+            (self.speed_control, "GAME SPEED"),
+            (self.sound_overlay, "SOUND DEBUG"),
+            (self.practice, "PRACTICE"),
+            (self.frame_step, "FRAME STEP"),
+            (self.physmap_overlay, "PHYSMAP"),
+            (self.tuning, "TUNING"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect()
+    }
+
+    fn key_bit(chr: u8) -> u32 {
+        if chr == b' ' {
+            1 << 26
+        } else {
+            1 << (chr - b'A')
+        }
+    }
+
+    // Returns false for repeats of a key that is still held.
+    pub fn key_down(&mut self, chr: u8) -> bool {
+        let bit = Self::key_bit(chr);
+        let new = self.keys_held & bit == 0;
+        self.keys_held |= bit;
+        new
+    }
+
+    pub fn key_up(&mut self, chr: u8) {
+        self.keys_held &= !Self::key_bit(chr);
+    }
+
+    pub fn release_keys(&mut self) {
+        self.keys_held = 0;
+    }
+
+    pub fn speed_ticks(&mut self) -> u8 {
+        if self.speed_level != SPEED_NORMAL {
+            self.speed_used = true;
+        }
+        let (ticks, frames) = SPEED_LEVELS[self.speed_level];
+        self.speed_phase += 1;
+        if self.speed_phase < frames {
+            return 0;
+        }
+        self.speed_phase = 0;
+        ticks
+    }
+
+    pub fn step_game_frame(&mut self) {
+        if self.frame_step {
+            self.step_request = Some(FrameStep::Game);
+        }
+    }
+
+    pub fn step_physics_frame(&mut self) {
+        if self.frame_step {
+            self.step_request = Some(FrameStep::Physics);
+        }
+    }
+
+    pub fn speed_up(&mut self) {
+        if self.speed_control && self.speed_level < SPEED_LEVELS.len() - 1 {
+            self.speed_level += 1;
         }
     }
+
+    pub fn speed_down(&mut self) {
+        if self.speed_control && self.speed_level > 0 {
+            self.speed_level -= 1;
+        }
+    }
+
+    fn speed_percent(&self) -> u16 {
+        let (ticks, frames) = SPEED_LEVELS[self.speed_level];
+        u16::from(ticks) * 100 / u16::from(frames)
+    }
 }
 
 impl Table {
     pub fn handle_cheat(&mut self, chr: u8) {
+        if self.tournament {
+            return;
+        }
         self.cheat.buf.push(chr);
         let mut found_prefix = false;
         for cheat in &self.assets.cheats {
@@ -30,22 +281,476 @@ impl Table {
                     CheatEffect::None => (),
                     CheatEffect::Tilt => self.cheat.no_tilt = true,
                     CheatEffect::Slowdown => self.cheat.slowdown = true,
-                    CheatEffect::Balls => self.total_balls = 5,
+                    CheatEffect::Balls => {
+                        self.total_balls = 5;
+                        self.cheat.extra_balls = true;
+                    }
                     CheatEffect::Reset => {
                         self.cheat.no_tilt = false;
                         self.cheat.slowdown = false;
+                        self.cheat.ball_overlay = false;
+                        self.cheat.flyball = false;
+                        self.cheat.speed_control = false;
+                        self.cheat.speed_level = SPEED_NORMAL;
+                        self.cheat.sound_overlay = false;
+                        self.cheat.practice = false;
+                        self.cheat.frame_step = false;
+                        self.cheat.physmap_overlay = false;
+                        self.cheat.tuning = false;
+                        self.cheat.extra_balls = false;
                         self.total_balls = 3;
                     }
                 }
                 self.start_script_raw(cheat.script);
+                if !self.cheat.tuning {
+                    self.reset_tuning();
+                }
                 self.script.enter_attract = true;
+                self.note_cheats();
                 return;
             } else if cheat.keys.starts_with(&self.cheat.buf) {
                 found_prefix = true;
             }
         }
+        for (keys, cheat) in DEBUG_CHEATS {
+            if self.cheat.buf[..] == keys[..] {
+                self.cheat.buf.clear();
+                self.toggle_debug_cheat(cheat);
+                self.note_cheats();
+                return;
+            } else if keys.starts_with(&self.cheat.buf) {
+                found_prefix = true;
+            }
+        }
         if !found_prefix {
             self.cheat.buf = vec![chr];
         }
     }
+
+    // Once a cheat is on, scores from the game can't get into the high score
+    // table, even if the cheat is switched off again.
+    pub fn note_cheats(&mut self) {
+        if !self.cheat.active_cheats().is_empty() {
+            self.cheated = true;
+        }
+    }
+
+    fn toggle_debug_cheat(&mut self, cheat: DebugCheat) {
+        match cheat {
+            DebugCheat::BallOverlay => self.cheat.ball_overlay = !self.cheat.ball_overlay,
+            DebugCheat::FlyBall => self.cheat.flyball = !self.cheat.flyball,
+            DebugCheat::SpeedControl => {
+                self.cheat.speed_control = !self.cheat.speed_control;
+                self.cheat.speed_level = SPEED_NORMAL;
+            }
+            DebugCheat::SoundOverlay => {
+                self.cheat.sound_overlay = !self.cheat.sound_overlay;
+                self.cheat.sound_page = 0;
+            }
+            DebugCheat::Practice => self.cheat.practice = !self.cheat.practice,
+            DebugCheat::FrameStep => {
+                self.cheat.frame_step = !self.cheat.frame_step;
+                self.cheat.step_frames = 0;
+                self.cheat.step_physics = 0;
+                self.cheat.step_request = None;
+            }
+            DebugCheat::PhysmapOverlay => {
+                self.cheat.physmap_overlay = !self.cheat.physmap_overlay;
+                self.cheat.physmap_layer = None;
+            }
+            DebugCheat::Tuning => {
+                self.cheat.tuning = !self.cheat.tuning;
+                self.reset_tuning();
+            }
+        }
+    }
+
+    fn menu_cheat_on(&self, cheat: MenuCheat) -> bool {
+        match cheat {
+            MenuCheat::Table(CheatEffect::Tilt) => self.cheat.no_tilt,
+            MenuCheat::Table(CheatEffect::Slowdown) => self.cheat.slowdown,
+            MenuCheat::Table(CheatEffect::Balls) => self.cheat.extra_balls,
+            MenuCheat::Table(_) => false,
+            MenuCheat::Debug(DebugCheat::BallOverlay) => self.cheat.ball_overlay,
+            MenuCheat::Debug(DebugCheat::FlyBall) => self.cheat.flyball,
+            MenuCheat::Debug(DebugCheat::SpeedControl) => self.cheat.speed_control,
+            MenuCheat::Debug(DebugCheat::SoundOverlay) => self.cheat.sound_overlay,
+            MenuCheat::Debug(DebugCheat::Practice) => self.cheat.practice,
+            MenuCheat::Debug(DebugCheat::FrameStep) => self.cheat.frame_step,
+            MenuCheat::Debug(DebugCheat::PhysmapOverlay) => self.cheat.physmap_overlay,
+            MenuCheat::Debug(DebugCheat::Tuning) => self.cheat.tuning,
+        }
+    }
+
+    fn menu_cheat_code(&self, cheat: MenuCheat) -> &[u8] {
+        match cheat {
+            MenuCheat::Table(effect) => self
+                .assets
+                .cheats
+                .iter()
+                .find(|cheat| cheat.effect == effect)
+                .map_or(&[], |cheat| &cheat.keys[..]),
+            MenuCheat::Debug(debug) => DEBUG_CHEATS
+                .iter()
+                .find(|&&(_, cheat)| cheat == debug)
+                .map_or(&[], |&(keys, _)| keys),
+        }
+    }
+
+    pub fn toggle_cheat_menu(&mut self) {
+        if self.options.cheat_menu && !self.tournament {
+            self.cheat.menu = !self.cheat.menu;
+        }
+    }
+
+    // Returns true if the key was used by the cheat menu.
+    pub fn cheat_menu_key(&mut self, key: VirtualKeyCode) -> bool {
+        if !self.cheat.menu {
+            return false;
+        }
+        let Some(idx) = SOUND_KEYS[..CHEAT_MENU.len()]
+            .iter()
+            .position(|&k| k == key)
+        else {
+            return false;
+        };
+        match CHEAT_MENU[idx].1 {
+            MenuCheat::Table(CheatEffect::Tilt) => self.cheat.no_tilt = !self.cheat.no_tilt,
+            MenuCheat::Table(CheatEffect::Slowdown) => {
+                self.cheat.slowdown = !self.cheat.slowdown;
+            }
+            // the ball count can only change before a game starts
+            MenuCheat::Table(CheatEffect::Balls) if self.in_attract => {
+                self.cheat.extra_balls = !self.cheat.extra_balls;
+                self.total_balls = if self.cheat.extra_balls {
+                    5
+                } else {
+                    self.options.balls.clamp(MIN_BALLS, MAX_BALLS)
+                };
+            }
+            MenuCheat::Table(_) => (),
+            MenuCheat::Debug(cheat) => self.toggle_debug_cheat(cheat),
+        }
+        self.note_cheats();
+        true
+    }
+
+    pub fn render_cheat_menu(&self, data: &mut [u8], height: usize) {
+        let mut lines = vec!["CHEATS".to_string()];
+        for (i, &(name, cheat)) in CHEAT_MENU.iter().enumerate() {
+            lines.push(format!(
+                "{} {name:11} {:3} {}",
+                (i + 1) % 10,
+                if self.menu_cheat_on(cheat) {
+                    "ON"
+                } else {
+                    "OFF"
+                },
+                String::from_utf8_lossy(self.menu_cheat_code(cheat)),
+            ));
+        }
+        let box_height = (lines.len() * 7 + 2).min(height);
+        for row in data[..box_height * 320].chunks_exact_mut(320) {
+            row.fill(0);
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let y = 2 + i * 7;
+            if y + 5 > box_height {
+                break;
+            }
+            let line = &line.as_bytes()[..line.len().min(40)];
+            self.render_board_text(data, 0, y, line);
+        }
+    }
+
+    pub fn dm_ball_debug(&mut self) {
+        let ball = self.ball.debug();
+        let layer = match ball.layer {
+            Layer::Ground => "GROUND",
+            Layer::Overhead => "OVERHEAD",
+        };
+        self.dm.clear();
+        self.dm_puts(
+            DmFont::H5,
+            DmCoord { x: 0, y: 1 },
+            format!("X{:4} Y{:4} {layer}", ball.pos.0, ball.pos.1).as_bytes(),
+        );
+        self.dm_puts(
+            DmFont::H5,
+            DmCoord { x: 0, y: 9 },
+            format!("VX{:6} VY{:6}", ball.speed.0, ball.speed.1).as_bytes(),
+        );
+    }
+
+    pub fn dm_speed(&mut self) {
+        for line in &mut self.dm.pixels[0..7] {
+            line[88..].fill(false);
+        }
+        self.dm_puts(
+            DmFont::H5,
+            DmCoord { x: 88, y: 1 },
+            format!("SPD{:4}", self.cheat.speed_percent()).as_bytes(),
+        );
+    }
+
+    // With frame stepping on, the game only advances when asked to: a whole
+    // game frame, or a single one of the physics frames it's made of.  The
+    // game frame then runs only the physics frames not stepped yet, and the
+    // last physics frame completes it.
+    pub fn frame_step_ticks(&mut self) -> u8 {
+        match self.cheat.step_request.take() {
+            Some(FrameStep::Game) => {
+                self.cheat.step_frames += 1;
+                1
+            }
+            Some(FrameStep::Physics) => {
+                self.physics_frame();
+                self.cheat.step_physics += 1;
+                if self.cheat.step_physics < self.physics_frames() {
+                    return 0;
+                }
+                self.cheat.step_frames += 1;
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub fn physics_frames(&self) -> u8 {
+        if self.cheat.slowdown {
+            3
+        } else {
+            4
+        }
+    }
+
+    // physics frames of the coming game frame already stepped on their own
+    pub fn take_stepped_physics(&mut self) -> u8 {
+        std::mem::take(&mut self.cheat.step_physics)
+    }
+
+    pub fn dm_frame_step(&mut self) {
+        for line in &mut self.dm.pixels[8..15] {
+            line[88..].fill(false);
+        }
+        self.dm_puts(
+            DmFont::H5,
+            DmCoord { x: 88, y: 9 },
+            format!("F{:5}.{}", self.cheat.step_frames, self.cheat.step_physics).as_bytes(),
+        );
+    }
+
+    pub fn dm_practice(&mut self) {
+        for line in &mut self.dm.pixels[0..7] {
+            line[..64].fill(false);
+        }
+        self.dm_puts(DmFont::H5, DmCoord { x: 0, y: 1 }, b"PRACTICE");
+    }
+
+    // In practice mode a drained ball is simply issued again, without bonus
+    // counting or moving on to the next ball.
+    pub fn practice_drained(&mut self) {
+        self.add_task(TaskKind::DrainSfx);
+        self.set_music_silence();
+        self.save_cur_player();
+        self.add_task(TaskKind::IssueBall);
+    }
+
+    // Taken at the end of the frame the ball was issued in, so the restored
+    // table starts on a fresh frame.  Balls issued again after draining in
+    // practice mode keep the first one.
+    pub fn snapshot_ball(&mut self) {
+        let key = (self.cur_player, self.cur_ball);
+        if self
+            .ball_snapshot
+            .as_ref()
+            .is_none_or(|&(snapshot_key, _)| snapshot_key != key)
+        {
+            self.ball_snapshot = Some((key, self.save_state()));
+        }
+    }
+
+    pub fn take_rewind(&mut self) -> Option<Vec<u8>> {
+        if !std::mem::take(&mut self.rewind) {
+            return None;
+        }
+        self.ball_snapshot.as_ref().map(|(_, state)| state.clone())
+    }
+
+    // The snapshot a rewound table was loaded from, to rewind to again.
+    pub fn set_ball_snapshot(&mut self, state: Vec<u8>) {
+        self.ball_snapshot = Some(((self.cur_player, self.cur_ball), state));
+    }
+
+    pub fn render_ball_debug(&self, data: &mut [u8], height: usize) {
+        let ball = self.ball.debug();
+        if ball.frozen {
+            return;
+        }
+        let (cx, cy) = self.ball.pos_center();
+        let cy = cy - self.scroll.pos() as i16;
+        let dx = ball.speed.0 / 64;
+        let dy = ball.speed.1 / 64;
+        let steps = dx.abs().max(dy.abs()).max(1);
+        for i in 0..=steps {
+            let x = cx + dx * i / steps;
+            let y = cy + dy * i / steps;
+            if (0..320).contains(&x) && (0..height as i16).contains(&y) {
+                data[y as usize * 320 + x as usize] = self.assets.dm_palette.index_on;
+            }
+        }
+    }
+
+    // Follows the ball, then shows each layer in turn.
+    pub fn cycle_physmap_layer(&mut self) {
+        self.cheat.physmap_layer = match self.cheat.physmap_layer {
+            None => Some(Layer::Ground),
+            Some(Layer::Ground) => Some(Layer::Overhead),
+            Some(Layer::Overhead) => None,
+        };
+    }
+
+    // Walls are tinted by material on every other pixel, so the board shows
+    // through.
+    pub fn render_physmap(&self, data: &mut [u8], pal: &[(u8, u8, u8)], height: usize, top: usize) {
+        let layer = self.cheat.physmap_layer.unwrap_or(self.ball.layer);
+        let map = &self.physmaps[layer];
+        let colors = PHYSMAP_COLORS.map(|color| nearest_color(pal, color, |_| true));
+        for y in 0..height {
+            let sy = y + top;
+            if sy >= map.dim().1 {
+                break;
+            }
+            for x in (sy & 1..320).step_by(2) {
+                let byte = map[(x, sy)];
+                if byte & 2 != 0 {
+                    data[y * 320 + x] = colors[byte as usize & 7];
+                }
+            }
+        }
+        let layer = match layer {
+            Layer::Ground => "GROUND",
+            Layer::Overhead => "OVERHEAD",
+        };
+        let follow = if self.cheat.physmap_layer.is_none() {
+            " BALL"
+        } else {
+            ""
+        };
+        for row in data[..10 * 320].chunks_exact_mut(320) {
+            row[..(8 + layer.len() + follow.len()) * 8 + 4].fill(0);
+        }
+        self.render_board_text(data, 2, 2, format!("PHYSMAP {layer}{follow}").as_bytes());
+    }
+
+    fn sound_entries(&self) -> Vec<SoundEntry> {
+        let jingles = self
+            .assets
+            .jingle_binds
+            .iter()
+            .filter(|(_, jingle)| jingle.is_some())
+            .map(|(bind, _)| SoundEntry::Jingle(bind));
+        let sfx = self
+            .assets
+            .sfx_binds
+            .iter()
+            .filter(|(_, sfx)| sfx.is_some())
+            .map(|(bind, _)| SoundEntry::Sfx(bind));
+        jingles.chain(sfx).collect()
+    }
+
+    // Number keys audition the entries on the current page, page up/down
+    // flip through the list.
+    pub fn sound_debug_key(&mut self, key: VirtualKeyCode) {
+        let entries = self.sound_entries();
+        let pages = entries.len().div_ceil(SOUND_PAGE_LEN).max(1);
+        match key {
+            VirtualKeyCode::PageUp => {
+                self.cheat.sound_page = (self.cheat.sound_page + pages - 1) % pages;
+            }
+            VirtualKeyCode::PageDown => {
+                self.cheat.sound_page = (self.cheat.sound_page + 1) % pages;
+            }
+            _ => {
+                let Some(idx) = SOUND_KEYS.iter().position(|&k| k == key) else {
+                    return;
+                };
+                match entries.get(self.cheat.sound_page * SOUND_PAGE_LEN + idx) {
+                    Some(&SoundEntry::Jingle(bind)) => {
+                        self.play_jingle_bind(bind);
+                    }
+                    Some(&SoundEntry::Sfx(bind)) => self.play_sfx_bind(bind),
+                    None => (),
+                }
+            }
+        }
+    }
+
+    pub fn render_board_text(&self, data: &mut [u8], x: usize, y: usize, msg: &[u8]) {
+        let font = &self.assets.dm_fonts[DmFont::H5];
+        for (i, chr) in msg.iter().enumerate() {
+            let Some(glyph) = font.get(&chr.to_ascii_uppercase()) else {
+                continue;
+            };
+            for (gy, &line) in glyph.iter().enumerate() {
+                for gx in 0..8 {
+                    if line << gx & 0x80 != 0 {
+                        data[(y + gy) * 320 + x + i * 8 + gx] = self.assets.dm_palette.index_on;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn render_sound_debug(&self, data: &mut [u8], height: usize) {
+        let entries = self.sound_entries();
+        let page = self.cheat.sound_page;
+        let mut lines = vec![
+            format!(
+                "POS {:02X} MUS {:02X} {}",
+                self.sequencer.position(),
+                self.sequencer.music(),
+                if self.sequencer.jingle_playing() {
+                    format!("REP {:02X}", self.sequencer.repeat())
+                } else {
+                    "LOOP".to_string()
+                }
+            ),
+            format!(
+                "PAGE {}({})",
+                page + 1,
+                entries.len().div_ceil(SOUND_PAGE_LEN)
+            ),
+        ];
+        for (i, entry) in entries
+            .iter()
+            .skip(page * SOUND_PAGE_LEN)
+            .take(SOUND_PAGE_LEN)
+            .enumerate()
+        {
+            let key = (i + 1) % 10;
+            lines.push(match *entry {
+                SoundEntry::Jingle(bind) => {
+                    let jingle = self.assets.jingle_binds[bind].unwrap();
+                    format!("{key} J {:02X} {:?}", jingle.position, bind)
+                }
+                SoundEntry::Sfx(bind) => {
+                    let sfx = self.assets.sfx_binds[bind].unwrap();
+                    format!("{key} S {:02X} {:?}", sfx.sample, bind)
+                }
+            });
+        }
+        let box_height = (lines.len() * 7 + 2).min(height);
+        for row in data[..box_height * 320].chunks_exact_mut(320) {
+            row[..240].fill(0);
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let y = 2 + i * 7;
+            if y + 5 > box_height {
+                break;
+            }
+            let line = &line.as_bytes()[..line.len().min(30)];
+            self.render_board_text(data, 0, y, line);
+        }
+    }
 }
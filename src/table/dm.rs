@@ -1,20 +1,41 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     assets::table::{
         dm::DmFont,
         script::{special_chars, DmAnimFrameId, DmAnimId, DmCoord, MsgId},
+        Assets,
     },
     bcd::Bcd,
 };
 
 use super::Table;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DotMatrix {
+    #[serde(with = "super::state::dm_rows")]
     pub pixels: [[bool; 160]; 16],
+    #[serde(with = "super::state::dm_rows")]
     saved: [[bool; 160]; 16],
     state: bool,
     blink: Option<Blink>,
+    #[serde(default)]
+    text: Vec<DmText>,
+    #[serde(default)]
+    saved_text: Vec<DmText>,
 }
 
+// A string written with dm_puts, kept so the display can be read out as text.
+#[derive(Clone, Serialize, Deserialize)]
+struct DmText {
+    x: i16,
+    y: i16,
+    width: i16,
+    height: i16,
+    text: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Blink {
     timer: u16,
     period: u16,
@@ -27,15 +48,19 @@ impl DotMatrix {
             saved: [[false; 160]; 16],
             state: true,
             blink: None,
+            text: vec![],
+            saved_text: vec![],
         }
     }
 
     pub fn save(&mut self) {
         self.saved = self.pixels;
+        self.saved_text = self.text.clone();
     }
 
     pub fn restore(&mut self) {
         self.pixels = self.saved;
+        self.text = self.saved_text.clone();
     }
 
     pub fn stop_blink(&mut self) {
@@ -65,12 +90,58 @@ impl DotMatrix {
         self.state = state;
     }
 
+    pub fn is_blinking(&self) -> bool {
+        self.blink.is_some()
+    }
+
     pub fn state(&self) -> bool {
         self.state
     }
 
     pub fn clear(&mut self) {
         self.pixels = [[false; 160]; 16];
+        self.text.clear();
+    }
+
+    fn put_text(&mut self, pos: DmCoord, height: usize, text: &[u8]) {
+        let new = DmText {
+            x: pos.x,
+            y: pos.y,
+            width: text.len() as i16 * 8,
+            height: height as i16,
+            text: text
+                .iter()
+                .map(|&chr| if chr == b'_' { ' ' } else { chr as char })
+                .collect(),
+        };
+        self.text.retain(|old| {
+            old.x >= new.x + new.width
+                || new.x >= old.x + old.width
+                || old.y >= new.y + new.height
+                || new.y >= old.y + old.height
+        });
+        if !new.text.trim().is_empty() {
+            self.text.push(new);
+        }
+    }
+
+    // Text written on the display, top to bottom, with strings on the same
+    // row joined left to right.
+    pub fn text_lines(&self) -> Vec<String> {
+        let mut text: Vec<_> = self.text.iter().collect();
+        text.sort_by_key(|entry| (entry.y, entry.x));
+        let mut lines: Vec<(i16, String)> = vec![];
+        for entry in text {
+            let words = entry.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            match lines.last_mut() {
+                Some((y, line)) if *y == entry.y => {
+                    line.push(' ');
+                    line.push_str(&words);
+                }
+                _ => lines.push((entry.y, words)),
+            }
+        }
+        lines.into_iter().map(|(_, line)| line).collect()
     }
 }
 
@@ -81,7 +152,11 @@ impl Table {
         } else if (special_chars::HIGH_SCORES..(special_chars::HIGH_SCORES + 12)).contains(&chr) {
             let idx = (chr - special_chars::HIGH_SCORES) / 3;
             let cidx = (chr - special_chars::HIGH_SCORES) % 3;
-            self.high_scores[idx as usize].name[cidx as usize]
+            self.high_scores
+                .get(idx as usize)
+                .and_then(|score| score.name.get(cidx as usize))
+                .copied()
+                .unwrap_or(b' ')
         } else if chr == special_chars::CUR_BALL {
             b'0' + self.cur_ball
         } else if chr == special_chars::CUR_PLAYER {
@@ -220,6 +295,12 @@ impl Table {
     }
 
     pub fn dm_puts(&mut self, font: DmFont, mut pos: DmCoord, msg: &[u8]) {
+        let len = msg.len().min(((160 - pos.x).max(1) as usize).div_ceil(8));
+        let text: Vec<u8> = msg[..len]
+            .iter()
+            .map(|&chr| self.dm_sub_char(chr))
+            .collect();
+        self.dm.put_text(pos, font.height(), &text);
         for &chr in msg {
             self.dm_put_char(font, pos, chr);
             pos.x += 8;
@@ -237,8 +318,9 @@ impl Table {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmAnim {
+    #[serde(with = "super::state::entity_idx")]
     anim: DmAnimId,
     frame_idx: usize,
     delay: u16,
@@ -255,6 +337,13 @@ impl ScriptTaskDmAnim {
         }
     }
 
+    pub fn fits(&self, assets: &Assets) -> bool {
+        assets
+            .anims
+            .get(self.anim)
+            .is_some_and(|anim| self.frame_idx <= anim.num_frames)
+    }
+
     pub fn run(&mut self, table: &mut Table) -> bool {
         assert_ne!(self.delay, 0);
         self.delay -= 1;
@@ -279,7 +368,7 @@ impl ScriptTaskDmAnim {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmWipeDown {
     pos: usize,
 }
@@ -298,7 +387,7 @@ impl ScriptTaskDmWipeDown {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmWipeRight {
     pos: usize,
 }
@@ -321,7 +410,7 @@ impl ScriptTaskDmWipeRight {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmWipeDownStriped {
     pos: usize,
 }
@@ -343,8 +432,9 @@ impl ScriptTaskDmWipeDownStriped {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmMsgScroll {
+    #[serde(with = "super::state::entity_idx")]
     msg: MsgId,
     pos: i16,
     target: i16,
@@ -360,6 +450,9 @@ impl ScriptTaskDmMsgScroll {
             down,
         }
     }
+    pub fn fits(&self, assets: &Assets) -> bool {
+        assets.msgs.get(self.msg).is_some()
+    }
     pub fn run(&mut self, table: &mut Table) -> bool {
         if self.down {
             self.pos += 1;
@@ -376,8 +469,9 @@ impl ScriptTaskDmMsgScroll {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmLongMsg {
+    #[serde(with = "super::state::entity_idx")]
     msg: MsgId,
     pos: usize,
     x: i16,
@@ -387,6 +481,9 @@ impl ScriptTaskDmLongMsg {
     pub fn new(msg: MsgId) -> Self {
         Self { msg, pos: 0, x: 0 }
     }
+    pub fn fits(&self, assets: &Assets) -> bool {
+        assets.msgs.get(self.msg).is_some()
+    }
     pub fn run(&mut self, table: &mut Table) -> bool {
         let msg = table.assets.msgs[self.msg].clone();
         if self.pos + 20 >= msg.len() {
@@ -404,7 +501,7 @@ impl ScriptTaskDmLongMsg {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskDmTowerHunt {
     target: u16,
     pos: u16,
@@ -0,0 +1,65 @@
+use std::{
+    fmt::{self, Display},
+    sync::mpsc::Sender,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Table;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameEvent {
+    BallDrained { player: u8, ball: u8 },
+    Tilt,
+    ModeStarted,
+    ExtraBallAwarded,
+    HighScoreAchieved { place: usize, score: u64 },
+    PlayerChanged(u8),
+    JackpotScored(u64),
+}
+
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            GameEvent::BallDrained { player, ball } => {
+                write!(f, "player {player} drained ball {ball}")
+            }
+            GameEvent::Tilt => write!(f, "tilt"),
+            GameEvent::ModeStarted => write!(f, "mode started"),
+            GameEvent::ExtraBallAwarded => write!(f, "extra ball"),
+            GameEvent::HighScoreAchieved { place, score } => {
+                write!(f, "high score {} with {score}", place + 1)
+            }
+            GameEvent::PlayerChanged(player) => write!(f, "player {player} up"),
+            GameEvent::JackpotScored(score) => write!(f, "jackpot {score}"),
+        }
+    }
+}
+
+impl Table {
+    pub fn with_events(mut self, sender: Sender<GameEvent>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    pub fn emit(&self, event: GameEvent) {
+        if self.running_ahead {
+            return;
+        }
+        if let Some(ref events) = self.events {
+            // the receiver going away just means nobody is listening anymore
+            let _ = events.send(event);
+        }
+    }
+
+    pub fn emit_jackpot(&self) {
+        self.emit(GameEvent::JackpotScored(self.score_jackpot.to_u64()));
+    }
+
+    pub fn set_cur_player(&mut self, player: u8) {
+        if self.cur_player != player {
+            self.cur_player = player;
+            self.emit(GameEvent::PlayerChanged(player));
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::table::{
@@ -13,18 +14,23 @@ use crate::{
 };
 
 use super::{
-    party::PartyState, show::ShowState, speed::SpeedState, stones::StonesState, tasks::TaskKind,
-    KbdState, Table,
+    events::GameEvent, party::PartyState, show::ShowState, speed::SpeedState, stones::StonesState,
+    tasks::TaskKind, KbdState, Table, REPLAY_MSG_FRAMES,
 };
 
 impl Table {
     pub fn init_game(&mut self) {
         self.kbd_state = KbdState::Main;
         self.script.enter_attract = false;
+        self.ball_snapshot = None;
         self.cur_ball = 1;
         self.cur_player = 1;
         self.got_top_score = false;
         self.got_high_score = false;
+        self.got_replay = false;
+        self.cheat.speed_used = false;
+        self.cheated = false;
+        self.note_cheats();
         self.in_game_start = true;
         self.score_jackpot = self.assets.score_jackpot_init;
         self.reset_player_state();
@@ -78,7 +84,7 @@ impl Table {
                 self.stones = StonesState::new();
                 self.raise_physmap(PhysmapBind::StonesGateTowerEntry);
                 self.raise_physmap(PhysmapBind::StonesGateKickback);
-                let target = thread_rng().gen_range(0..3);
+                let target = self.rng.gen_range(0..3);
                 self.stones.key_skillshot = Some(target);
                 self.light_blink(LightBind::StonesKey, target, 1, 0)
             }
@@ -89,7 +95,7 @@ impl Table {
         self.roll_trigger = None;
         self.at_spring = true;
         self.flipper_pressed = false;
-        self.space_pressed = false;
+        self.nudge_pressed = Default::default();
         self.silence_effect = false;
         self.in_drain = false;
         self.in_mode = false;
@@ -124,6 +130,10 @@ impl Table {
     }
 
     pub fn issue_ball(&mut self) {
+        self.aux_balls.clear();
+        // drop any debug speed picked with the function keys
+        self.scroll
+            .set_speed(self.options.scroll_speed.speed().into());
         self.in_drain = false;
         self.drained = false;
         self.in_plunger = true;
@@ -136,6 +146,7 @@ impl Table {
         }
         self.init_ball();
         self.ball_scored_points = false;
+        self.snapshot_pending = self.cheat.practice;
         if self.in_game_start {
             self.add_task(TaskKind::IssueBallFinish);
         } else {
@@ -155,12 +166,17 @@ impl Table {
     }
 
     pub fn issue_ball_release(&mut self) {
-        self.ball
-            .teleport(Layer::Ground, self.assets.issue_ball_release_pos, (10, 0));
+        self.ball.teleport(
+            Layer::Ground,
+            self.assets.issue_ball_release_pos,
+            (10, 0),
+            &mut self.rng,
+        );
     }
 
     pub fn abort_game(&mut self) {
-        self.ball.teleport(Layer::Ground, (300, 570), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (300, 570), (0, 0), &mut self.rng);
         self.kbd_state = KbdState::Main;
         self.add_task(TaskKind::GameOver);
         self.play_jingle_bind_force(JingleBind::Attract);
@@ -184,10 +200,28 @@ impl Table {
         self.reset_idle();
     }
 
+    // Awards the extra ball for reaching the replay score, once per player
+    // and game.  Checked once a game frame rather than wherever points are
+    // added.
+    pub fn check_replay(&mut self) {
+        let replay = self.options.replay_score;
+        if replay == 0 || self.got_replay || self.tournament || self.in_attract {
+            return;
+        }
+        if self.score_main < Bcd::from_u64(replay) {
+            return;
+        }
+        self.got_replay = true;
+        self.extra_ball();
+        self.play_jingle_bind(JingleBind::MatchWin);
+        self.replay_msg = REPLAY_MSG_FRAMES;
+    }
+
     pub fn effect_force_raw(&mut self, effect: Effect) {
         match effect.sound {
             EffectSound::Jingle(jingle) => {
                 self.sequencer.play_jingle(jingle, true, None);
+                self.caption_jingle(jingle);
             }
             EffectSound::Silent(_) => (),
         };
@@ -208,7 +242,11 @@ impl Table {
                 {
                     false
                 } else {
-                    self.sequencer.play_jingle(jingle, false, None)
+                    let played = self.sequencer.play_jingle(jingle, false, None);
+                    if played {
+                        self.caption_jingle(jingle);
+                    }
+                    played
                 }
             }
             EffectSound::Silent(priority) => priority >= self.sequencer.priority(),
@@ -242,6 +280,9 @@ impl Table {
         self.sequencer
             .play_jingle(jingle, true, Some(jingle.position));
         self.start_script(ScriptBind::Main);
+        if self.in_plunger {
+            self.start_ball_save();
+        }
         self.in_plunger = false;
         self.at_spring = false;
         self.party_on = false;
@@ -254,6 +295,7 @@ impl Table {
 
     pub fn extra_ball(&mut self) {
         self.extra_balls += 1;
+        self.emit(GameEvent::ExtraBallAwarded);
         match self.assets.table {
             TableId::Table1 => {
                 self.light_set(LightBind::PartyExtraBall, 0, true);
@@ -304,7 +346,7 @@ impl Table {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskAccBonus {
     frame: i8,
     digit: usize,
@@ -352,7 +394,7 @@ impl ScriptTaskAccBonus {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskMatch {
     pub count: u16,
     pub frames: u16,
@@ -360,7 +402,7 @@ pub struct ScriptTaskMatch {
     pub digit: u8,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScriptTaskMatchStones {
     pub frames: u16,
     pub timing_idx: usize,
@@ -382,7 +424,7 @@ impl ScriptTaskMatch {
             },
             b"_",
         );
-        let mut new_digit = thread_rng().gen_range(0..10);
+        let mut new_digit = table.rng.gen_range(0..10);
         if new_digit == self.digit {
             new_digit += 1;
             if new_digit == 10 {
@@ -445,3 +487,90 @@ impl ScriptTaskMatchStones {
         }
     }
 }
+
+const GAME_SUMMARY_FRAMES: u16 = 300;
+const GAME_SUMMARY_PAGE_FRAMES: u16 = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScriptTaskGameSummary {
+    timer: u16,
+    best: Bcd,
+    pub high_score: bool,
+}
+
+impl ScriptTaskGameSummary {
+    pub fn new(table: &mut Table) -> Self {
+        let best = table
+            .players
+            .iter()
+            .map(|player| player.score_main)
+            .max()
+            .unwrap_or(Bcd::ZERO);
+        let high_score = !table.cheated
+            && table
+                .players
+                .iter()
+                .any(|player| table.high_score_place(player.score_main).is_some());
+        if !high_score {
+            table.play_jingle_bind_force(JingleBind::GameOverSad);
+        }
+        table.kbd_state = KbdState::GameSummary;
+        Self {
+            timer: 0,
+            best,
+            high_score,
+        }
+    }
+
+    pub fn run(&mut self, table: &mut Table) -> bool {
+        if self.timer.is_multiple_of(GAME_SUMMARY_PAGE_FRAMES) {
+            table.dm_game_summary((self.timer / GAME_SUMMARY_PAGE_FRAMES) as usize, self.best);
+        }
+        self.timer += 1;
+        if self.timer < GAME_SUMMARY_FRAMES && table.kbd_state == KbdState::GameSummary {
+            return true;
+        }
+        table.kbd_state = KbdState::Main;
+        table.dm.clear();
+        false
+    }
+}
+
+impl Table {
+    fn dm_game_summary(&mut self, page: usize, best: Bcd) {
+        self.dm.clear();
+        if self.cheated && page == (GAME_SUMMARY_FRAMES / GAME_SUMMARY_PAGE_FRAMES - 1) as usize {
+            self.dm_puts(DmFont::H5, DmCoord { x: 8, y: 5 }, b"CHEATED - NO SCORE");
+            return;
+        }
+        if self.players.len() == 1 {
+            self.dm_puts(DmFont::H5, DmCoord { x: 44, y: 1 }, b"GAME OVER");
+            self.dm_put_bcd(
+                DmFont::H8,
+                DmCoord { x: 32, y: 6 },
+                self.players[0].score_main,
+                true,
+            );
+            return;
+        }
+        // two players per page, the best score marked
+        let pages = self.players.len().div_ceil(2);
+        for slot in 0..2 {
+            let idx = page % pages * 2 + slot;
+            let Some(player) = self.players.get(idx) else {
+                break;
+            };
+            let score = player.score_main;
+            let y = 1 + slot as i16 * 8;
+            self.dm_puts(
+                DmFont::H5,
+                DmCoord { x: 0, y },
+                format!("P{}", idx + 1).as_bytes(),
+            );
+            self.dm_put_bcd(DmFont::H5, DmCoord { x: 24, y }, score, false);
+            if score == best {
+                self.dm_puts(DmFont::H5, DmCoord { x: 128, y }, b"WIN");
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use winit::event::{ElementState, VirtualKeyCode};
+
+use crate::view::{Action, View};
+
+use super::{
+    tasks::{Task, TaskKind},
+    Table,
+};
+
+impl Table {
+    pub fn step(&mut self) -> Action {
+        self.run_frame()
+    }
+
+    pub fn inject_key(&mut self, key: VirtualKeyCode, state: ElementState) {
+        self.handle_key(key, state);
+    }
+
+    pub fn set_frame_step(&mut self, frame_step: bool) {
+        self.cheat.frame_step = frame_step;
+    }
+
+    pub fn at_spring(&self) -> bool {
+        self.at_spring
+    }
+
+    pub fn in_drain(&self) -> bool {
+        self.in_drain
+    }
+
+    pub fn cheat_codes(&self) -> Vec<Vec<u8>> {
+        self.assets
+            .cheats
+            .iter()
+            .map(|cheat| cheat.keys.to_vec())
+            .collect()
+    }
+
+    pub fn cheat_buf(&self) -> &[u8] {
+        &self.cheat.buf
+    }
+
+    pub fn tilt_counter(&self) -> u16 {
+        self.tilt_counter
+    }
+
+    pub fn mode_timeout(&self) -> (u8, u8) {
+        (self.mode_timeout_secs, self.mode_timeout_frames)
+    }
+
+    // Party Land's happy hour, with the task that starts the next one queued
+    pub fn start_happy_hour(&mut self) {
+        self.party_happy_hour();
+        self.add_task(TaskKind::PartyHappyHour);
+    }
+
+    pub fn task_timers(&self) -> Vec<u16> {
+        self.tasks.iter().map(Task::timer).collect()
+    }
+
+    pub fn blink_counters(&self) -> Vec<u8> {
+        self.lights.blink_counters()
+    }
+
+    pub fn flipper_held(&self) -> bool {
+        self.flipper_state.values().any(|&held| held)
+    }
+}
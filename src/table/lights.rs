@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use unnamed_entity::EntityVec;
 
 use crate::assets::table::{
@@ -7,17 +8,22 @@ use crate::assets::table::{
 
 use super::Table;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Lights {
+    #[serde(with = "super::state::entity_vec")]
     lights: EntityVec<LightId, LightState>,
+    #[serde(with = "super::state::entity_vec")]
     attract: EntityVec<AttractLightId, u16>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 struct LightState {
     lit: bool,
     state: bool,
     blink: Option<LightBlink>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LightBlink {
     ctr: u8,
     ctr_off: u8,
@@ -59,6 +65,16 @@ impl Lights {
         }
     }
 
+    pub fn fits(&self, assets: &Assets) -> bool {
+        self.lights.len() == assets.lights.len()
+    }
+
+    pub fn fit_attract(&mut self, assets: &Assets) {
+        if self.attract.len() != assets.attract_lights.len() {
+            self.attract = assets.attract_lights.map(|_, _| 0);
+        }
+    }
+
     pub fn reset(&mut self) {
         for light in self.lights.ids() {
             self.set_state(light, false);
@@ -76,6 +92,10 @@ impl Lights {
         self.lights[light].lit
     }
 
+    pub fn is_blinking(&self, light: LightId) -> bool {
+        self.lights[light].blink.is_some()
+    }
+
     pub fn state(&self, light: LightId) -> bool {
         self.lights[light].state
     }
@@ -93,6 +113,26 @@ impl Lights {
         };
     }
 
+    pub fn chase_frame(&mut self, step: u16) {
+        for (i, light) in self.lights.values_mut().enumerate() {
+            light.lit = (i + usize::from(step)) % 4 == 0;
+        }
+    }
+
+    pub fn end_chase(&mut self) {
+        for light in self.lights.values_mut() {
+            light.lit = light.state;
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn blink_counters(&self) -> Vec<u8> {
+        self.lights
+            .values()
+            .filter_map(|light| light.blink.as_ref().map(|blink| blink.ctr))
+            .collect()
+    }
+
     pub fn blink_frame(&mut self) {
         for light in self.lights.values_mut() {
             if let Some(ref mut blink) = light.blink {
@@ -0,0 +1,128 @@
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assets::table::physics::{BumperId, Layer, RollTrigger};
+
+use super::{ball::BallState, Table};
+
+// A ball in play besides the main one, along with the state Table keeps for
+// the ball it is currently simulating.  Each extra ball is swapped into
+// Table::ball while its physics and triggers run.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuxBall {
+    ball: BallState,
+    drained: bool,
+    hit_pos: Option<(i16, i16)>,
+    #[serde(with = "super::state::opt_entity_idx")]
+    hit_bumper: Option<BumperId>,
+    roll_trigger: Option<RollTrigger>,
+    prev_roll_trigger: Option<RollTrigger>,
+}
+
+impl Table {
+    pub fn add_ball(&mut self, layer: Layer, pos: (i16, i16), speed: (i16, i16)) {
+        let mut ball = BallState::new(self.hifps);
+        ball.teleport(layer, pos, speed, &mut self.rng);
+        self.aux_balls.push(AuxBall {
+            ball,
+            drained: false,
+            hit_pos: None,
+            hit_bumper: None,
+            roll_trigger: None,
+            prev_roll_trigger: None,
+        });
+    }
+
+    pub fn aux_balls_fit(&self) -> bool {
+        self.aux_balls.iter().all(|aux| {
+            aux.hit_bumper
+                .is_none_or(|bumper| self.assets.bumpers.get(bumper).is_some())
+        })
+    }
+
+    pub fn balls_in_play(&self) -> usize {
+        1 + self.aux_balls.len()
+    }
+
+    fn swap_ball(&mut self, idx: usize) {
+        let aux = &mut self.aux_balls[idx];
+        mem::swap(&mut self.ball, &mut aux.ball);
+        mem::swap(&mut self.drained, &mut aux.drained);
+        mem::swap(&mut self.hit_pos, &mut aux.hit_pos);
+        mem::swap(&mut self.hit_bumper, &mut aux.hit_bumper);
+        mem::swap(&mut self.roll_trigger, &mut aux.roll_trigger);
+        mem::swap(&mut self.prev_roll_trigger, &mut aux.prev_roll_trigger);
+    }
+
+    // Runs f for the main ball, then for each extra ball in its place.
+    pub fn for_each_ball(&mut self, f: impl Fn(&mut Table)) {
+        f(self);
+        for idx in 0..self.aux_balls.len() {
+            self.swap_ball(idx);
+            self.on_aux_ball = true;
+            f(self);
+            self.on_aux_ball = false;
+            self.swap_ball(idx);
+        }
+    }
+
+    // Drops the extra balls that drained.  When the main ball drains while
+    // others are still in play, one of them takes its place, so the ball in
+    // play only ends with the last one.
+    pub fn retire_drained_balls(&mut self) {
+        self.aux_balls.retain(|aux| !aux.drained);
+        if self.drained && !self.in_drain && !self.aux_balls.is_empty() {
+            self.swap_ball(self.aux_balls.len() - 1);
+            self.aux_balls.pop();
+        }
+    }
+
+    // Holes and locks hold their ball in tasks and scripts that only know the
+    // main ball, so an extra ball rolling into one is taken out of play
+    // instead, and the plunger lane exit starts nothing for it.  False if the
+    // trigger is left alone for the ball being simulated.
+    pub fn aux_ball_trigger(&mut self, kind: RollTrigger) -> bool {
+        if !self.on_aux_ball {
+            return false;
+        }
+        if matches!(
+            kind,
+            RollTrigger::PartySecret
+                | RollTrigger::PartySecretTilt
+                | RollTrigger::PartyTunnel
+                | RollTrigger::PartyTunnelTilt
+                | RollTrigger::PartyRampSnack
+                | RollTrigger::PartyDemon
+                | RollTrigger::SpeedPitStop
+                | RollTrigger::ShowVault
+                | RollTrigger::ShowCashpot
+                | RollTrigger::StonesTower
+                | RollTrigger::StonesTowerTilt
+                | RollTrigger::StonesWell
+                | RollTrigger::StonesWellTilt
+                | RollTrigger::StonesVault
+        ) {
+            self.ball.frozen = true;
+            self.drained = true;
+            return true;
+        }
+        matches!(
+            kind,
+            RollTrigger::PartyEnter
+                | RollTrigger::SpeedEnter
+                | RollTrigger::ShowEnter
+                | RollTrigger::StonesEnter
+        )
+    }
+
+    pub fn aux_ball_sprites(&self) -> impl Iterator<Item = ((i16, i16), Layer)> + '_ {
+        self.aux_balls.iter().map(|aux| {
+            let (x, mut y) = aux.ball.pos();
+            if !aux.ball.frozen {
+                y += self.push.offset();
+            }
+            ((x, y), aux.ball.layer)
+        })
+    }
+}
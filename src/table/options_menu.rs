@@ -0,0 +1,170 @@
+use winit::event::VirtualKeyCode;
+
+use crate::{
+    assets::table::{dm::DmFont, script::DmCoord},
+    config::{is_bound, BallColor, BallStyle, ColorMode, Resolution, ScrollSpeed, Step},
+};
+
+use super::{KbdState, Table};
+
+// The settings that can be changed from the pause menu without leaving the
+// table.  The ball count only takes effect from the next game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum OptionItem {
+    Balls,
+    Scrolling,
+    Music,
+    MusicVolume,
+    SfxVolume,
+    Resolution,
+    ColorMode,
+    Flashing,
+    Captions,
+    BallStyle,
+    BallColor,
+}
+
+const OPTIONS_MENU: [(OptionItem, &str); 11] = [
+    (OptionItem::Balls, "BALLS"),
+    (OptionItem::Scrolling, "SCROLL"),
+    (OptionItem::Music, "MUSIC"),
+    (OptionItem::MusicVolume, "MUSIC VOL"),
+    (OptionItem::SfxVolume, "SFX VOL"),
+    (OptionItem::Resolution, "RES"),
+    (OptionItem::ColorMode, "COLOR"),
+    (OptionItem::Flashing, "FLASHING"),
+    (OptionItem::Captions, "CAPTIONS"),
+    (OptionItem::BallStyle, "BALL"),
+    (OptionItem::BallColor, "BALL COLOR"),
+];
+
+impl Table {
+    pub fn open_options(&mut self) {
+        self.options_sel = 0;
+        self.kbd_state = KbdState::PausedOptions;
+        self.dm_options_menu();
+    }
+
+    fn close_options(&mut self) {
+        self.flush_options = true;
+        self.kbd_state = KbdState::Paused;
+        self.dm_pause_menu();
+    }
+
+    fn dm_options_menu(&mut self) {
+        self.dm.clear();
+        self.dm_puts(DmFont::H5, DmCoord { x: 52, y: 1 }, b"OPTIONS");
+        let (item, name) = OPTIONS_MENU[self.options_sel];
+        let line = format!("{name} {}", self.option_value(item));
+        let x = 80 - line.len() as i16 * 4;
+        self.dm_puts(DmFont::H8, DmCoord { x, y: 8 }, line.as_bytes());
+    }
+
+    fn option_value(&self, item: OptionItem) -> String {
+        let options = &self.options;
+        match item {
+            OptionItem::Balls => options.balls.to_string(),
+            OptionItem::Scrolling => match options.scroll_speed {
+                ScrollSpeed::Hard => "HARD".to_string(),
+                ScrollSpeed::Medium => "MEDIUM".to_string(),
+                ScrollSpeed::Soft => "SOFT".to_string(),
+                ScrollSpeed::Custom(_) => options.scroll_speed.speed().to_string(),
+            },
+            OptionItem::Music => if options.no_music { "OFF" } else { "ON" }.to_string(),
+            OptionItem::MusicVolume => options.music_volume.to_string(),
+            OptionItem::SfxVolume => options.sfx_volume.to_string(),
+            OptionItem::Resolution => match options.resolution {
+                Resolution::Normal => "NORMAL",
+                Resolution::High => "HIGH",
+                Resolution::Full => "FULL",
+            }
+            .to_string(),
+            OptionItem::ColorMode => match options.color_mode {
+                ColorMode::Color => "COLOR",
+                ColorMode::Mono => "MONO",
+                ColorMode::Deuteranopia => "DEUTAN",
+                ColorMode::Protanopia => "PROTAN",
+            }
+            .to_string(),
+            OptionItem::Captions => if options.captions { "ON" } else { "OFF" }.to_string(),
+            OptionItem::BallStyle => match options.ball_style {
+                BallStyle::Sprite => "NORMAL",
+                BallStyle::Outline => "OUTLINE",
+                BallStyle::Solid => "SOLID",
+            }
+            .to_string(),
+            OptionItem::BallColor => match options.ball_color {
+                BallColor::White => "WHITE",
+                BallColor::Yellow => "YELLOW",
+                BallColor::Cyan => "CYAN",
+                BallColor::Magenta => "MAGENTA",
+            }
+            .to_string(),
+            OptionItem::Flashing => if options.reduce_flashing {
+                "REDUCED"
+            } else {
+                "NORMAL"
+            }
+            .to_string(),
+        }
+    }
+
+    pub fn options_key(&mut self, key: VirtualKeyCode) {
+        match key {
+            _ if is_bound(&self.key_bindings.quit, key)
+                || is_bound(&self.key_bindings.pause, key) =>
+            {
+                self.close_options()
+            }
+            VirtualKeyCode::Up => {
+                self.options_sel = (self.options_sel + OPTIONS_MENU.len() - 1) % OPTIONS_MENU.len();
+                self.dm_options_menu();
+            }
+            VirtualKeyCode::Down => {
+                self.options_sel = (self.options_sel + 1) % OPTIONS_MENU.len();
+                self.dm_options_menu();
+            }
+            VirtualKeyCode::Left => self.step_option(Step::Down),
+            VirtualKeyCode::Right => self.step_option(Step::Up),
+            VirtualKeyCode::Return => self.step_option(Step::Cycle),
+            _ => (),
+        }
+    }
+
+    fn step_option(&mut self, step: Step) {
+        match OPTIONS_MENU[self.options_sel].0 {
+            OptionItem::Balls => self.options.step_balls(step),
+            OptionItem::Scrolling => {
+                self.options.step_scroll_speed(step);
+                self.scroll
+                    .set_speed(self.options.scroll_speed.speed().into());
+            }
+            OptionItem::Music => self.toggle_music(),
+            OptionItem::MusicVolume => {
+                self.options.step_volume(true, step);
+                self.player.set_music_volume(self.options.music_gain());
+            }
+            OptionItem::SfxVolume => {
+                self.options.step_volume(false, step);
+                self.player.set_sfx_volume(self.options.sfx_gain());
+            }
+            OptionItem::Resolution => {
+                let next = self.options.resolution.next();
+                let resolution = match step {
+                    Step::Down => next.next(),
+                    Step::Up | Step::Cycle => next,
+                };
+                self.set_resolution(resolution);
+            }
+            OptionItem::ColorMode => self.options.step_color_mode(),
+            OptionItem::Flashing => self.options.reduce_flashing = !self.options.reduce_flashing,
+            OptionItem::Captions => {
+                self.options.captions = !self.options.captions;
+                self.caption.set(None);
+            }
+            OptionItem::BallStyle => self.options.step_ball_style(),
+            OptionItem::BallColor => self.options.step_ball_color(),
+        }
+        self.dm_options_menu();
+    }
+}
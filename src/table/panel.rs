@@ -0,0 +1,88 @@
+use crate::{assets::table::dm::DmFont, view::PanelLayout};
+
+use super::Table;
+
+pub const PANEL_WIDTH: usize = 120;
+const LINE_HEIGHT: usize = 12;
+
+const PANEL_BLACK: u8 = 0;
+const PANEL_TEXT: u8 = 1;
+const PANEL_LABEL: u8 = 2;
+
+impl Table {
+    pub fn panel_layout(&self) -> Option<PanelLayout> {
+        self.options.side_panel.then_some(PanelLayout {
+            width: PANEL_WIDTH as u32,
+        })
+    }
+
+    pub fn render_panel(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        let (red, green, blue) = self.assets.dm_palette.color_on;
+        pal[usize::from(PANEL_BLACK)] = (0, 0, 0);
+        pal[usize::from(PANEL_TEXT)] = (red, green, blue);
+        pal[usize::from(PANEL_LABEL)] = (red / 2, green / 2, blue / 2);
+        data.fill(PANEL_BLACK);
+
+        let status = self.status();
+        let score = self.score_main.to_ascii();
+        let score = score.trim_ascii_start();
+        let mut lines: Vec<(u8, Vec<u8>)> = vec![
+            (PANEL_LABEL, b"SCORE".to_vec()),
+            (
+                PANEL_TEXT,
+                if score.is_empty() { b"0" } else { score }.to_vec(),
+            ),
+            (PANEL_LABEL, vec![]),
+            (
+                PANEL_TEXT,
+                format!("PLAYER {} OF {}", status.cur_player, status.total_players).into_bytes(),
+            ),
+            (
+                PANEL_TEXT,
+                format!("BALL {} OF {}", status.cur_ball, status.total_balls).into_bytes(),
+            ),
+        ];
+        if status.extra_balls != 0 {
+            lines.push((
+                PANEL_TEXT,
+                format!("EXTRA BALLS {}", status.extra_balls).into_bytes(),
+            ));
+        }
+        lines.push((PANEL_LABEL, vec![]));
+        if status.in_attract {
+            lines.push((PANEL_LABEL, b"GAME OVER".to_vec()));
+        } else if status.tilted {
+            lines.push((PANEL_TEXT, b"TILT".to_vec()));
+        } else if self.in_mode {
+            lines.push((PANEL_TEXT, b"MODE RUNNING".to_vec()));
+        }
+        for name in self.cheat.active_cheats() {
+            lines.push((PANEL_LABEL, name.as_bytes().to_vec()));
+        }
+        for (i, (color, text)) in lines.iter().enumerate() {
+            self.render_panel_text(data, 8, 8 + i * LINE_HEIGHT, text, *color);
+        }
+    }
+
+    fn render_panel_text(&self, data: &mut [u8], x: usize, y: usize, msg: &[u8], color: u8) {
+        let font = &self.assets.dm_fonts[DmFont::H8];
+        for (i, chr) in msg.iter().enumerate() {
+            let Some(glyph) = font.get(&chr.to_ascii_uppercase()) else {
+                continue;
+            };
+            for (gy, &line) in glyph.iter().enumerate() {
+                let Some(row) = data.get_mut((y + gy) * PANEL_WIDTH..(y + gy + 1) * PANEL_WIDTH)
+                else {
+                    return;
+                };
+                for gx in 0..8 {
+                    if line << gx & 0x80 != 0 {
+                        if let Some(pixel) = row.get_mut(x + i * 8 + gx) {
+                            *pixel = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
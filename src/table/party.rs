@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::table::{
@@ -12,6 +13,7 @@ use crate::{
 
 use super::{tasks::TaskKind, Table};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyState {
     pub flipper_lock_puke: bool,
 
@@ -215,7 +217,8 @@ impl Table {
     }
 
     pub fn party_start_drop_zone(&mut self) {
-        self.ball.teleport(Layer::Ground, (15, 47), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (15, 47), (0, 0), &mut self.rng);
         self.add_task(TaskKind::PartyDropZoneScroll);
         self.add_task(TaskKind::PartyDropZoneWait);
         self.party.drop_zone_scroll_pos = self.scroll.pos();
@@ -456,11 +459,13 @@ impl Table {
         self.light_blink(LightBind::PartyCycloneX5, 0, 6, 0);
         self.party.cyclone_x5 = true;
         self.add_task(TaskKind::PartySecretDrop);
-        self.ball.teleport(Layer::Ground, (15, 47), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (15, 47), (0, 0), &mut self.rng);
     }
 
     pub fn party_secret_tilt(&mut self) {
-        self.ball.teleport(Layer::Ground, (15, 47), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (15, 47), (0, 0), &mut self.rng);
         self.party_start_drop_zone();
     }
 
@@ -505,7 +510,8 @@ impl Table {
     }
 
     pub fn party_tunnel_tilt(&mut self) {
-        self.ball.teleport(Layer::Ground, (15, 47), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (15, 47), (0, 0), &mut self.rng);
         self.party_start_drop_zone();
     }
 
@@ -526,11 +532,12 @@ impl Table {
             self.party_arcade_pick_reward();
         }
         self.add_task(TaskKind::PartyDropZoneScroll);
-        self.ball.teleport(Layer::Ground, (15, 47), (0, 0));
+        self.ball
+            .teleport(Layer::Ground, (15, 47), (0, 0), &mut self.rng);
     }
 
     pub fn party_arcade_pick_reward(&mut self) {
-        let delay = match thread_rng().gen_range(0..6) {
+        let delay = match self.rng.gen_range(0..6) {
             0 => {
                 // side extra ball
                 self.light_set(LightBind::PartySideExtraBall, 0, true);
@@ -673,6 +680,7 @@ impl Table {
             } else {
                 self.start_script(ScriptBind::PartyJackpotModeRamp);
             }
+            self.emit_jackpot();
             self.score_main += self.score_jackpot;
             self.score_jackpot = self.assets.score_jackpot_init;
             got_something = true;
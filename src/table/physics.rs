@@ -1,6 +1,7 @@
-use enum_map::EnumMap;
+use enum_map::{Enum, EnumMap};
 use ndarray::{s, Array2};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::table::{
@@ -13,25 +14,57 @@ use crate::{
 
 use super::Table;
 
+const AIM_GUIDE_FRAMES: usize = 40;
+
+pub const KICKER_SPEED_BOOST: i16 = 2000;
+pub const BUMPER_SPEED_BOOST: i16 = 7000;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Enum)]
+pub enum NudgeDir {
+    Up,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PushState {
     offset_f9: i16,
+    offset_x_f9: i16,
     speed: i16,
     speed_attack: i16,
     speed_release: i16,
+    pub side_impulse: i16,
 }
 
 impl PushState {
     pub fn new(hifps: bool) -> Self {
         Self {
             offset_f9: 0,
+            offset_x_f9: 0,
             speed: 0,
             speed_attack: speed_fix(600, hifps),
             speed_release: speed_fix(-200, hifps),
+            side_impulse: speed_fix(400, hifps),
         }
     }
 
-    pub fn frame(&mut self, state: bool) {
-        if state {
+    pub fn set_hifps(&mut self, hifps: bool) {
+        let fresh = Self::new(hifps);
+        self.speed_attack = fresh.speed_attack;
+        self.speed_release = fresh.speed_release;
+        self.side_impulse = fresh.side_impulse;
+    }
+
+    pub fn frame(&mut self, nudge: &EnumMap<NudgeDir, bool>) {
+        let side = i16::from(nudge[NudgeDir::Right]) - i16::from(nudge[NudgeDir::Left]);
+        if side != 0 {
+            self.offset_x_f9 = (self.offset_x_f9 + side * self.speed_attack).clamp(-0x800, 0x800);
+        } else if self.offset_x_f9 > 0 {
+            self.offset_x_f9 = (self.offset_x_f9 + self.speed_release).max(0);
+        } else {
+            self.offset_x_f9 = (self.offset_x_f9 - self.speed_release).min(0);
+        }
+        if nudge[NudgeDir::Up] {
             self.speed = self.speed_attack;
             self.offset_f9 += self.speed;
             if self.offset_f9 > 0x800 {
@@ -51,8 +84,13 @@ impl PushState {
     pub fn offset(&self) -> i16 {
         self.offset_f9 >> 9
     }
+
+    pub fn offset_x(&self) -> i16 {
+        self.offset_x_f9 >> 9
+    }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FlipperState {
     pub pos: i16,
     pub speed: i16,
@@ -116,19 +154,21 @@ struct Collision {
 
 impl Table {
     pub fn physics_frame(&mut self) {
-        if self.ball.frozen {
-            self.push.frame(self.space_state);
-            self.flippers_move();
-            self.flippers_physmap_update();
-        } else {
-            if let Some(coll) = self.physics_check_collision() {
-                self.physics_new_dir(coll);
+        self.for_each_ball(|table| {
+            if !table.ball.frozen {
+                if let Some(coll) = table.physics_check_collision() {
+                    table.physics_new_dir(coll);
+                }
             }
-            self.push.frame(self.space_state);
-            self.flippers_move();
-            self.ball_move();
-            self.flippers_physmap_update();
-        }
+        });
+        self.push.frame(&self.nudge_state);
+        self.flippers_move();
+        self.for_each_ball(|table| {
+            if !table.ball.frozen {
+                table.ball_move();
+            }
+        });
+        self.flippers_physmap_update();
     }
 
     fn physics_check_collision(&mut self) -> Option<Collision> {
@@ -291,14 +331,50 @@ impl Table {
         }
     }
 
+    // Ball centers a game frame apart along the path of a ball launched with
+    // the spring pulled as far as it is now, ignoring the random part of the
+    // launch speed.  The simulation runs on the real ball and then puts back
+    // everything the physics touched.
+    pub fn predict_launch(&mut self) -> Vec<(i16, i16)> {
+        let saved_ball = self.ball.clone();
+        let saved = (self.hit_pos, self.hit_bumper, self.drained);
+        self.ball.speed = (0, self.spring_speed() - 0x80);
+        self.ball.frozen = false;
+        let mut points = vec![];
+        for _ in 0..AIM_GUIDE_FRAMES {
+            for _ in 0..4 {
+                if let Some(coll) = self.physics_check_collision() {
+                    self.physics_new_dir(coll);
+                }
+                self.ball_move();
+            }
+            self.ball_gravity();
+            self.check_transitions();
+            if self.drained {
+                break;
+            }
+            points.push(self.ball_center());
+        }
+        self.ball = saved_ball;
+        (self.hit_pos, self.hit_bumper, self.drained) = saved;
+        points
+    }
+
     fn ball_move(&mut self) {
         self.ball.pos_hires.0 += i32::from(self.ball.speed.0);
         self.ball.pos_hires.1 += i32::from(self.ball.speed.1);
         if self.ball.pos().1 >= 576 {
-            self.drained = true;
+            if self.cheat.flyball {
+                self.ball.pos_hires.1 = 575 << 10;
+                self.ball.speed.1 = 0;
+            } else {
+                self.drained = true;
+            }
+        }
+        if !self.cheat.flyball {
+            self.ball.speed.0 += self.ball.accel.0;
+            self.ball.speed.1 += self.ball.accel.1;
         }
-        self.ball.speed.0 += self.ball.accel.0;
-        self.ball.speed.1 += self.ball.accel.1;
         if self.ball.rotation < 0 {
             self.ball.rotation += 2;
             if self.ball.rotation > 0 {
@@ -312,14 +388,15 @@ impl Table {
         }
     }
 
+    fn spring_speed(&self) -> i16 {
+        let factor = if self.hifps { -166 } else { -138 };
+        factor * self.spring_pos as i16
+    }
+
     pub fn spring_release(&mut self) {
         if self.at_spring {
-            let factor = if self.hifps { -166 } else { -138 };
-            self.ball.speed = (
-                0,
-                factor * self.spring_pos as i16 - rand::thread_rng().gen_range(0..0x100),
-            );
-            self.ball.rotation = rand::thread_rng().gen_range(0..0x10);
+            self.ball.speed = (0, self.spring_speed() - self.rng.gen_range(0..0x100));
+            self.ball.rotation = self.rng.gen_range(0..0x10);
         }
         let volume = self.spring_pos * 2;
         self.play_sfx_bind_volume(SfxBind::SpringUp, volume);
@@ -382,11 +459,11 @@ impl Table {
             return;
         }
         let ramp = &self.assets.ramps[ramp];
-        self.ball.accel = if self.hifps {
+        self.ball.accel = self.tuning.scale_gravity(if self.hifps {
             ramp.accel_hires
         } else {
             ramp.accel
-        };
+        });
         if !self.options.angle_high {
             self.ball.accel.1 -= 3;
         }
@@ -421,6 +498,7 @@ impl Table {
             self.hit_bumper = None;
             let bumper = &self.assets.bumpers[bid];
             self.player.play_sfx(bumper.sfx, 0x40);
+            self.caption(Some("BUMPER"));
             self.score(bumper.score, Bcd::ZERO);
             self.mode_count_hit();
         }
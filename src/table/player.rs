@@ -1,17 +1,21 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{assets::table::lights::LightBind, bcd::Bcd, config::TableId};
 
 use super::{show::PrizeState, Table};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PlayerState {
     pub score_main: Bcd,
     pub score_bonus: Bcd,
     pub num_cyclone: u16,
     pub bcd_num_cyclone: Bcd,
+    #[serde(default)]
+    pub got_replay: bool,
     pub table: TablePlayerState,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum TablePlayerState {
     Party(PartyPlayerState),
     Speed(SpeedPlayerState),
@@ -26,6 +30,7 @@ impl PlayerState {
             score_bonus: Bcd::ZERO,
             num_cyclone: 0,
             bcd_num_cyclone: Bcd::ZERO,
+            got_replay: false,
             table: match table {
                 TableId::Table1 => TablePlayerState::Party(PartyPlayerState {
                     light_puke: [false; 4],
@@ -60,7 +65,7 @@ impl PlayerState {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PartyPlayerState {
     pub light_puke: [bool; 4],
     pub light_mad: [bool; 3],
@@ -70,7 +75,7 @@ pub struct PartyPlayerState {
     pub score_cyclone_skill_shot: Bcd,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SpeedPlayerState {
     pub cur_gear: u8,
     pub cur_speed: u8,
@@ -82,12 +87,12 @@ pub struct SpeedPlayerState {
     pub light_car: [bool; 5],
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct ShowPlayerState {
     pub prize_sets: u8,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct StonesPlayerState {
     pub cur_ghost: u8,
     pub ghost_active: bool,
@@ -105,6 +110,7 @@ impl Table {
         self.score_bonus = player.score_bonus;
         self.num_cyclone = player.num_cyclone;
         self.bcd_num_cyclone = player.bcd_num_cyclone;
+        self.got_replay = player.got_replay;
         match player.table {
             TablePlayerState::Party(party) => {
                 self.light_load(LightBind::PartyPuke, party.light_puke);
@@ -151,6 +157,7 @@ impl Table {
             score_bonus: self.score_bonus,
             num_cyclone: self.num_cyclone,
             bcd_num_cyclone: self.bcd_num_cyclone,
+            got_replay: self.got_replay,
             table: match self.assets.table {
                 TableId::Table1 => TablePlayerState::Party(PartyPlayerState {
                     light_puke: self.light_save(LightBind::PartyPuke),
@@ -0,0 +1,78 @@
+use enum_map::EnumMap;
+use rand::rngs::StdRng;
+
+use crate::{
+    config::{HighScore, HighScoreName},
+    view::View,
+};
+
+use super::{physics::NudgeDir, Table};
+
+// The parts of the table a frame can change that aren't in a saved state,
+// mostly inputs waiting to be taken by the next frame.
+macro_rules! kept_fields {
+    ($($field:ident: $ty:ty,)*) => {
+        struct KeptFields {
+            $($field: $ty,)*
+        }
+
+        impl Table {
+            fn keep_fields(&self) -> KeptFields {
+                KeptFields {
+                    $($field: self.$field.clone(),)*
+                }
+            }
+
+            fn put_back_fields(&mut self, kept: KeptFields) {
+                $(self.$field = kept.$field;)*
+            }
+        }
+    };
+}
+
+kept_fields! {
+    rng: StdRng,
+    high_scores: Vec<HighScore>,
+    flush_high_scores: bool,
+    snapshot_pending: bool,
+    name_buf: HighScoreName,
+    name_confirmed: bool,
+    flipper_pressed: bool,
+    nudge_pressed: EnumMap<NudgeDir, bool>,
+    spring_released: bool,
+    plunger_drag: Option<u32>,
+    dm_overlay: bool,
+    ball_saved_msg: u16,
+    replay_msg: u16,
+    start_key: Option<u8>,
+    aim_guide: Vec<(i16, i16)>,
+}
+
+impl Table {
+    // Run-ahead shows the frame after the current one, as it would be with
+    // the inputs held now, to hide a frame of input lag.  The frame is run
+    // without sound or events and then undone through the saved state.
+    pub fn render_ahead_frame(&mut self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        if self.paused() || self.quitting || self.in_attract || self.cheat.frame_step {
+            self.render(data, pal);
+            return;
+        }
+        let point = self.rollback_point();
+        let kept = self.keep_fields();
+        let caption = self.caption.get();
+        self.running_ahead = true;
+        self.sequencer.begin_ahead();
+        self.player.set_sfx_muted(true);
+        self.clear_dm_overlay();
+        let ticks = self.cheat.speed_ticks();
+        self.advance_game(ticks);
+        self.dm_overlay();
+        self.render(data, pal);
+        self.player.set_sfx_muted(false);
+        self.sequencer.end_ahead();
+        self.running_ahead = false;
+        self.caption.set(caption);
+        self.put_back_fields(kept);
+        self.roll_back(point);
+    }
+}
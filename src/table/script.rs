@@ -1,5 +1,5 @@
-use arrayref::array_ref;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use unnamed_entity::EntityId;
 
 use crate::{
@@ -7,10 +7,11 @@ use crate::{
         dm::DmFont,
         lights::LightBind,
         script::{DmCoord, ScriptBind, ScriptPosId, ScriptScore, Uop},
-        sound::JingleBind,
+        sound::{JingleBind, SfxBind},
+        Assets,
     },
     bcd::Bcd,
-    config::{HighScore, TableId},
+    config::{HighScore, TableId, MAX_HIGH_SCORES, MAX_NAME_LEN},
 };
 
 use super::{
@@ -18,12 +19,15 @@ use super::{
         ScriptTaskDmAnim, ScriptTaskDmLongMsg, ScriptTaskDmMsgScroll, ScriptTaskDmTowerHunt,
         ScriptTaskDmWipeDown, ScriptTaskDmWipeDownStriped, ScriptTaskDmWipeRight,
     },
-    game::{ScriptTaskAccBonus, ScriptTaskMatch, ScriptTaskMatchStones},
+    events::GameEvent,
+    game::{ScriptTaskAccBonus, ScriptTaskGameSummary, ScriptTaskMatch, ScriptTaskMatchStones},
     tasks::TaskKind,
     KbdState, Table,
 };
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ScriptState {
+    #[serde(with = "super::state::entity_idx")]
     pos: ScriptPosId,
     task: ScriptTask,
     timer_idle: u16,
@@ -33,7 +37,9 @@ pub struct ScriptState {
     repeat_cnt: u16,
 }
 
-#[derive(Debug)]
+// tagged so TOML keeps the variant name of tuple variants
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "task", content = "args")]
 pub enum ScriptTask {
     Placeholder,
     Default,
@@ -45,6 +51,7 @@ pub enum ScriptTask {
 
     WaitWhileGameStarting,
     AccBonus(ScriptTaskAccBonus),
+    PlayerUp(u16),
 
     Mode(ScriptScore),
 
@@ -59,11 +66,16 @@ pub enum ScriptTask {
 
     Match(ScriptTaskMatch),
     MatchStones(ScriptTaskMatchStones),
+    GameSummary(ScriptTaskGameSummary),
     RecordHighScores,
-    RecordHighScoresGetName(usize),
+    RecordHighScoresCelebrate(usize, u16),
+    RecordHighScoresGetName(usize, u16),
     RecordHighScoresFinish(u16),
 }
 
+const HIGH_SCORE_CELEBRATE_FRAMES: u16 = 150;
+const PLAYER_UP_FRAMES: u16 = 90;
+
 impl ScriptTask {
     pub fn run(&mut self, table: &mut Table) -> bool {
         match *self {
@@ -111,6 +123,15 @@ impl ScriptTask {
 
             ScriptTask::WaitWhileGameStarting => table.in_game_start,
             ScriptTask::AccBonus(ref mut task) => task.run(table),
+            ScriptTask::PlayerUp(ref mut time) => {
+                *time -= 1;
+                if *time != 0 {
+                    return true;
+                }
+                table.dm.clear();
+                table.add_task(TaskKind::IssueBall);
+                false
+            }
 
             ScriptTask::Mode(score) => table.mode_frame(score),
 
@@ -127,6 +148,18 @@ impl ScriptTask {
             ScriptTask::DmTowerHunt(ref mut task) => task.run(table),
             ScriptTask::Match(ref mut task) => task.run(table),
             ScriptTask::MatchStones(ref mut task) => task.run(table),
+            ScriptTask::GameSummary(ref mut task) => {
+                if task.run(table) {
+                    true
+                } else if task.high_score {
+                    // the sad jingle has already been played if nobody made it
+                    table.cur_player = 1;
+                    *self = ScriptTask::RecordHighScores;
+                    true
+                } else {
+                    false
+                }
+            }
             ScriptTask::RecordHighScores => {
                 if table.cur_player > table.total_players {
                     if !table.got_high_score {
@@ -135,44 +168,51 @@ impl ScriptTask {
                     false
                 } else {
                     let score = table.players[table.cur_player as usize - 1].score_main;
-                    for place in 0..4 {
-                        if score > table.high_scores[place].score {
-                            if !table.got_high_score {
-                                table.play_jingle_bind_force(JingleBind::GameOverHighScore);
-                                table.got_high_score = true;
-                            }
-                            table.dm_puts(
-                                DmFont::H13,
-                                DmCoord { x: 0, y: 1 },
-                                b"HIGHSCORE PL \x94 (   )",
-                            );
-                            *self = ScriptTask::RecordHighScoresGetName(place);
-                            table.kbd_state = KbdState::GetName;
-                            table.name_buf.clear();
+                    let place = table.high_score_place(score);
+                    if let Some(place) = place {
+                        table.emit(GameEvent::HighScoreAchieved {
+                            place,
+                            score: score.to_u64(),
+                        });
+                        if !table.got_high_score {
+                            table.play_jingle_bind_force(JingleBind::GameOverHighScore);
+                            table.got_high_score = true;
+                            table.dm.clear();
+                            table.dm_puts(DmFont::H13, DmCoord { x: 40, y: 1 }, b"HIGH SCORE");
+                            table.dm.start_blink(8);
+                            *self = ScriptTask::RecordHighScoresCelebrate(place, 0);
                             return true;
                         }
+                        *self = ScriptTask::RecordHighScoresGetName(place, 0);
+                        table.start_name_entry();
+                        return true;
                     }
                     table.cur_player += 1;
                     true
                 }
             }
-            ScriptTask::RecordHighScoresGetName(place) => {
-                let name = table.name_buf.clone();
-                table.dm_puts(
-                    DmFont::H13,
-                    DmCoord {
-                        x: 160 - 4 * 8,
-                        y: 1,
-                    },
-                    &name,
-                );
-                if name.len() == 3 {
+            ScriptTask::RecordHighScoresCelebrate(place, ref mut timer) => {
+                *timer += 1;
+                if *timer < HIGH_SCORE_CELEBRATE_FRAMES {
+                    table.lights.chase_frame(*timer / 4);
+                } else {
+                    table.lights.end_chase();
+                    table.dm.stop_blink();
+                    *self = ScriptTask::RecordHighScoresGetName(place, 0);
+                    table.start_name_entry();
+                }
+                true
+            }
+            ScriptTask::RecordHighScoresGetName(place, ref mut timer) => {
+                *timer = timer.wrapping_add(1);
+                table.dm_name_entry(*timer % 32 < 16);
+                if table.name_confirmed && !table.name_buf.is_empty() {
                     let score = HighScore {
                         score: table.players[table.cur_player as usize - 1].score_main,
-                        name: *array_ref![name, 0, 3],
+                        name: table.name_buf.clone(),
                     };
-                    table.high_scores.copy_within(place..3, place + 1);
-                    table.high_scores[place] = score;
+                    table.high_scores.insert(place, score);
+                    table.high_scores.truncate(MAX_HIGH_SCORES);
                     table.cur_player += 1;
                     table.flush_high_scores = true;
                     *self = ScriptTask::RecordHighScoresFinish(60);
@@ -205,6 +245,18 @@ impl ScriptState {
             repeat_cnt: 0,
         }
     }
+
+    pub fn fits(&self, assets: &Assets) -> bool {
+        if assets.scripts.get(self.pos).is_none() {
+            return false;
+        }
+        match &self.task {
+            ScriptTask::DmMsgScroll(task) => task.fits(assets),
+            ScriptTask::DmLongMsg(task) => task.fits(assets),
+            ScriptTask::DmAnim(task) => task.fits(assets),
+            _ => true,
+        }
+    }
 }
 
 impl Table {
@@ -219,6 +271,66 @@ impl Table {
         }
     }
 
+    pub fn high_score_place(&self, score: Bcd) -> Option<usize> {
+        self.high_scores
+            .iter()
+            .position(|entry| score > entry.score)
+            .or_else(|| {
+                (self.high_scores.len() < MAX_HIGH_SCORES && score > Bcd::ZERO)
+                    .then_some(self.high_scores.len())
+            })
+    }
+
+    fn high_score(&self, idx: usize) -> Bcd {
+        self.high_scores
+            .get(idx)
+            .map_or(Bcd::ZERO, |entry| entry.score)
+    }
+
+    // Announces the next player's turn, issuing their ball once done.
+    fn start_player_up(&mut self) {
+        self.dm.clear();
+        self.dm_puts(
+            DmFont::H8,
+            DmCoord { x: 48, y: 0 },
+            format!("PLAYER {}", self.cur_player).as_bytes(),
+        );
+        self.dm_puts(
+            DmFont::H5,
+            DmCoord { x: 56, y: 10 },
+            format!("BALL {}", self.cur_ball).as_bytes(),
+        );
+        self.play_sfx_bind(SfxBind::GameStart);
+        self.script.task = ScriptTask::PlayerUp(PLAYER_UP_FRAMES);
+    }
+
+    fn start_name_entry(&mut self) {
+        self.kbd_state = KbdState::GetName;
+        self.name_buf.clear();
+        self.name_confirmed = false;
+        self.dm_name_entry(true);
+    }
+
+    fn dm_name_entry(&mut self, caret: bool) {
+        self.dm.clear();
+        self.dm_puts(DmFont::H5, DmCoord { x: 8, y: 1 }, b"HIGHSCORE PLAYER \x94");
+        self.dm_puts(DmFont::H8, DmCoord { x: 24, y: 7 }, b"(");
+        let name = self.name_buf.clone();
+        self.dm_puts(DmFont::H8, DmCoord { x: 32, y: 7 }, &name);
+        self.dm_puts(
+            DmFont::H8,
+            DmCoord {
+                x: 32 + MAX_NAME_LEN as i16 * 8,
+                y: 7,
+            },
+            b")",
+        );
+        if caret && !self.name_buf.is_full() {
+            let x = 32 + name.len() * 8;
+            self.dm.pixels[15][x..x + 7].fill(true);
+        }
+    }
+
     pub fn start_script(&mut self, bind: ScriptBind) {
         self.start_script_raw(self.assets.script_binds[bind].unwrap());
     }
@@ -236,7 +348,7 @@ impl Table {
             ScriptScore::ModeHit => self.score_mode_hit,
             ScriptScore::ModeRamp => self.score_mode_ramp,
             ScriptScore::Jackpot => self.score_jackpot,
-            ScriptScore::HighScore(idx) => self.high_scores[idx].score,
+            ScriptScore::HighScore(idx) => self.high_score(idx),
             ScriptScore::Const(x) => x,
             ScriptScore::CycloneIncr => Bcd::from_ascii(b"100000"),
             ScriptScore::NumCyclone => self.bcd_num_cyclone,
@@ -375,7 +487,7 @@ impl Table {
                 self.script.task = ScriptTask::AccBonus(ScriptTaskAccBonus::new(self.score_bonus));
             }
             Uop::CheckTopScore => {
-                if !self.got_top_score && self.score_main > self.high_scores[0].score {
+                if !self.got_top_score && self.score_main > self.high_score(0) {
                     self.got_top_score = true;
                     self.run_uop(self.assets.script_binds[ScriptBind::TopScoreInterball].unwrap());
                 } else {
@@ -389,7 +501,7 @@ impl Table {
                         self.extra_balls -= 1;
                         self.run_uop(self.assets.script_binds[ScriptBind::ShootAgain].unwrap());
                     } else if self.cur_player != self.total_players {
-                        self.cur_player += 1;
+                        self.set_cur_player(self.cur_player + 1);
                         self.run_uop(self.assets.script_binds[ScriptBind::CheckMatch].unwrap());
                     } else {
                         self.run_uop(self.assets.script_binds[ScriptBind::PostMatch].unwrap());
@@ -407,21 +519,24 @@ impl Table {
                     self.extra_balls -= 1;
                     self.run_uop(self.assets.script_binds[ScriptBind::ShootAgain].unwrap());
                 } else if self.cur_player != self.total_players {
-                    self.cur_player += 1;
-                    self.add_task(TaskKind::IssueBall);
-                    self.run_uop(self.script.pos);
+                    self.set_cur_player(self.cur_player + 1);
+                    self.start_player_up();
                 } else if self.cur_ball != self.total_balls {
                     self.cur_ball += 1;
-                    self.cur_player = 1;
-                    self.add_task(TaskKind::IssueBall);
-                    self.run_uop(self.script.pos);
+                    self.set_cur_player(1);
+                    if self.total_players > 1 {
+                        self.start_player_up();
+                    } else {
+                        self.add_task(TaskKind::IssueBall);
+                        self.run_uop(self.script.pos);
+                    }
                 } else {
                     self.run_uop(self.assets.script_binds[ScriptBind::Match].unwrap());
                 }
             }
 
             Uop::Match => {
-                self.cur_player = 1;
+                self.set_cur_player(1);
                 self.play_jingle_bind_silence(JingleBind::MatchStart);
                 for i in 0..self.players.len() {
                     let digit = self.players[i].score_main.digits[10];
@@ -434,7 +549,7 @@ impl Table {
                         &[b'0' + digit],
                     );
                 }
-                let digit = thread_rng().gen_range(0..10);
+                let digit = self.rng.gen_range(0..10);
                 self.script.task = match self.assets.table {
                     TableId::Table1 => ScriptTask::Match(ScriptTaskMatch {
                         count: 22,
@@ -465,7 +580,7 @@ impl Table {
                 let mut found = false;
                 for (i, player) in self.players.iter().enumerate() {
                     if self.match_digit == Some(player.score_main.digits[10]) {
-                        self.cur_player = i as u8 + 1;
+                        self.set_cur_player(i as u8 + 1);
                         self.run_uop(self.assets.script_binds[ScriptBind::ShootAgain].unwrap());
                         found = true;
                         break;
@@ -476,8 +591,7 @@ impl Table {
                 }
             }
             Uop::RecordHighScores => {
-                self.cur_player = 1;
-                self.script.task = ScriptTask::RecordHighScores;
+                self.script.task = ScriptTask::GameSummary(ScriptTaskGameSummary::new(self));
             }
             Uop::GameOver => {
                 self.add_task(TaskKind::GameOver);
@@ -543,10 +657,12 @@ impl Table {
             Uop::PlayJingle(jingle) => {
                 self.script.task = ScriptTask::Delay(1);
                 self.sequencer.play_jingle(jingle, true, None);
+                self.caption_jingle(jingle);
             }
             Uop::PlaySfx(sfx, volume) => {
                 self.script.task = ScriptTask::Delay(1);
                 self.player.play_sfx(sfx, volume);
+                self.caption_sfx(sfx);
             }
 
             Uop::SetMusic(position) => {
@@ -559,6 +675,7 @@ impl Table {
                 self.script.task = ScriptTask::Mode(score);
             }
             Uop::ModeStart(timeout, score) => {
+                self.emit(GameEvent::ModeStarted);
                 self.pending_mode = false;
                 self.mode_timeout_secs = timeout + 1;
                 self.mode_timeout_frames = 1;
@@ -566,6 +683,7 @@ impl Table {
             }
             Uop::ModeStartOrContinue(timeout, score) => {
                 if self.pending_mode {
+                    self.emit(GameEvent::ModeStarted);
                     self.pending_mode = false;
                     self.mode_timeout_secs = timeout + 1;
                 }
@@ -683,7 +801,7 @@ impl Table {
         if self.got_top_score {
             return;
         }
-        if self.score_main > self.high_scores[0].score {
+        if self.score_main > self.high_score(0) {
             self.got_top_score = true;
             self.start_script(ScriptBind::TopScoreIngame);
         }
@@ -1,5 +1,13 @@
-use crate::config::{Options, Resolution, ScrollSpeed};
+use serde::{Deserialize, Serialize};
 
+use crate::config::{Options, Resolution};
+
+// Closest the ball gets to the top or bottom of the window when it is kept in
+// view.
+const BALL_VIEW_MARGIN: i16 = 16;
+const BALL_SIZE: i16 = 15;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ScrollState {
     pos: u16,
     raw_pos_f4: i16,
@@ -8,31 +16,22 @@ pub struct ScrollState {
     target_special: Option<u16>,
     ball_target: i16,
     attract_up: bool,
+    #[serde(default)]
+    keep_ball_in_view: bool,
 }
 
 impl ScrollState {
     pub fn new(options: &Options) -> Self {
-        let window_height = match options.resolution {
-            Resolution::Normal => 240 - 33,
-            Resolution::High => 350 - 33,
-            Resolution::Full => 576,
-        };
+        let window_height = window_height(options.resolution);
         Self {
             pos: 576 - window_height,
             raw_pos_f4: 0,
-            speed: match options.scroll_speed {
-                ScrollSpeed::Hard => 20,
-                ScrollSpeed::Medium => 11,
-                ScrollSpeed::Soft => 9,
-            },
+            speed: options.scroll_speed.speed().into(),
             window_height,
             target_special: None,
-            ball_target: match options.resolution {
-                Resolution::Normal => 75,
-                Resolution::High => 130,
-                Resolution::Full => 0,
-            },
+            ball_target: ball_target(options.resolution),
             attract_up: true,
+            keep_ball_in_view: options.keep_ball_in_view,
         }
     }
 
@@ -55,9 +54,30 @@ impl ScrollState {
         } else if delta >= self.ball_target + 40 {
             self.raw_pos_f4 += (delta - self.ball_target - 40) << 4;
         }
+        if self.keep_ball_in_view && self.target_special.is_none() {
+            let max_pos = (576 - self.window_height) as i16;
+            let upper = (ball_y - BALL_VIEW_MARGIN).clamp(0, max_pos);
+            let lower =
+                (ball_y + BALL_SIZE + BALL_VIEW_MARGIN - self.window_height as i16).clamp(0, upper);
+            let pos = self.raw_pos_f4 >> 4;
+            if pos > upper {
+                self.raw_pos_f4 = upper << 4;
+            } else if pos < lower {
+                self.raw_pos_f4 = lower << 4;
+            }
+        }
         self.pos = (self.raw_pos_f4 >> 4) as u16;
     }
 
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        self.window_height = window_height(resolution);
+        self.ball_target = ball_target(resolution);
+        let max_pos = 576 - self.window_height;
+        self.target_special = self.target_special.map(|target| target.min(max_pos));
+        self.pos = self.pos.min(max_pos);
+        self.raw_pos_f4 = (self.pos << 4) as i16;
+    }
+
     pub fn attract_frame(&mut self) {
         if self.window_height == 576 {
             self.pos = 0;
@@ -80,6 +100,15 @@ impl ScrollState {
         self.pos
     }
 
+    // sixteenths of a row past pos
+    pub fn frac(&self) -> u8 {
+        if self.window_height == 576 {
+            0
+        } else {
+            (self.raw_pos_f4 & 0xf) as u8
+        }
+    }
+
     pub fn set_speed(&mut self, speed: i16) {
         self.speed = speed;
     }
@@ -100,3 +129,19 @@ impl ScrollState {
         self.target_special = None;
     }
 }
+
+fn window_height(resolution: Resolution) -> u16 {
+    match resolution {
+        Resolution::Normal => 240 - 33,
+        Resolution::High => 350 - 33,
+        Resolution::Full => 576,
+    }
+}
+
+fn ball_target(resolution: Resolution) -> i16 {
+    match resolution {
+        Resolution::Normal => 75,
+        Resolution::High => 130,
+        Resolution::Full => 0,
+    }
+}
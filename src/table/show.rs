@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::table::{
@@ -13,13 +14,14 @@ use crate::{
 
 use super::{tasks::TaskKind, Table};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub enum PrizeState {
     None,
     Lit,
     Taken,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ShowState {
     pub score_cashpot: Bcd,
     pub prizes: [PrizeState; 6],
@@ -40,6 +42,7 @@ pub struct ShowState {
     pub light_phase_prize: u8,
     pub wheel_cycle: usize,
     pub wheel_pos: u8,
+    #[serde(skip)]
     pub wheel_timing: &'static [u16],
 }
 
@@ -246,7 +249,7 @@ impl Table {
             self.start_script(ScriptBind::ShowSpinWheelClearHalt);
             self.light_set_all(LightBind::ShowWheel, false);
             let target: u8 = if !self.light_state(LightBind::ShowCollectPrize, 0) {
-                thread_rng().gen_range(0..8)
+                self.rng.gen_range(0..8)
             } else if self.show.prizes[0] == PrizeState::Lit {
                 0
             } else if self.show.prizes[1] == PrizeState::Lit {
@@ -350,7 +353,8 @@ impl Table {
             self.effect(EffectBind::ShowCashpotLock);
             self.sequencer.set_music(0);
             self.sequencer.reset_priority();
-            self.ball.teleport(Layer::Ground, (304, 535), (10, 0));
+            self.ball
+                .teleport(Layer::Ground, (304, 535), (10, 0), &mut self.rng);
             self.drop_physmap(PhysmapBind::ShowGateVaultEntry);
         } else {
             self.incr_jackpot();
@@ -374,7 +378,8 @@ impl Table {
     pub fn show_cashpot_eject(&mut self) {
         self.play_sfx_bind(SfxBind::ShowEjectCashpot);
         self.light_set(LightBind::ShowCashpot, 0, false);
-        self.ball.teleport(Layer::Ground, (103, 233), (83, 1416));
+        self.ball
+            .teleport(Layer::Ground, (103, 233), (83, 1416), &mut self.rng);
     }
 
     pub fn show_ramp_right(&mut self) {
@@ -387,6 +392,7 @@ impl Table {
         if self.show.timeout_jackpot != 0 {
             self.show.timeout_jackpot = 1;
             let mut effect = self.assets.effects[EffectBind::ShowJackpot].unwrap();
+            self.emit_jackpot();
             effect.score_main = self.score_jackpot;
             self.effect_raw(effect);
             self.score_jackpot = self.assets.score_jackpot_init;
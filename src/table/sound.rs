@@ -1,7 +1,56 @@
-use crate::assets::table::sound::{JingleBind, SfxBind};
+use crate::assets::table::sound::{Jingle, JingleBind, Sfx, SfxBind};
 
 use super::Table;
 
+const CAPTION_FRAMES: u16 = 90;
+
+// What the captions option shows for each sound; music and sounds that play
+// all the time have none.
+fn jingle_caption(bind: JingleBind) -> Option<&'static str> {
+    Some(match bind {
+        JingleBind::Silence | JingleBind::Plunger | JingleBind::Main | JingleBind::Attract => {
+            return None
+        }
+        JingleBind::GameStart => "GAME START",
+        JingleBind::WarnTilt => "TILT WARNING",
+        JingleBind::Tilt => "TILT",
+        JingleBind::GameOverSad => "GAME OVER",
+        JingleBind::GameOverHighScore => "HIGH SCORE",
+        JingleBind::Drained => "BALL LOST",
+        JingleBind::MatchStart => "MATCH",
+        JingleBind::MatchWin => "FANFARE",
+        JingleBind::ModeEndHit | JingleBind::ModeEndRamp => "MODE OVER",
+        JingleBind::PartyJackpot | JingleBind::ShowJackpot => "JACKPOT",
+        JingleBind::SpeedModeHit => "MODE HIT",
+        JingleBind::ShowSpinWheel => "WHEEL SPINS",
+        JingleBind::ShowMultiBonus => "MULTI BONUS",
+        JingleBind::ShowExtraBallLit => "EXTRA BALL LIT",
+        JingleBind::ShowPrizeIncoming => "PRIZE COMING",
+        JingleBind::StonesTowerHuntEnd => "TOWER HUNT OVER",
+    })
+}
+
+fn sfx_caption(bind: SfxBind) -> Option<&'static str> {
+    Some(match bind {
+        SfxBind::FlipperPress | SfxBind::SpringUp | SfxBind::TickBonus => return None,
+        SfxBind::BallDrained => "DRAIN",
+        SfxBind::IssueBall => "NEW BALL",
+        SfxBind::RollInner => "INLANE",
+        SfxBind::GameStart => "GAME START",
+        SfxBind::RollTrigger => "ROLLOVER",
+        SfxBind::RaiseHitTargets => "TARGETS UP",
+        SfxBind::PartySnacksRelease => "SNACKS",
+        SfxBind::PartyHitDuck => "DUCK HIT",
+        SfxBind::PartyArcadeButton => "ARCADE BUTTON",
+        SfxBind::SpeedEjectPit => "PIT EJECT",
+        SfxBind::SpeedHitTarget | SfxBind::ShowHitTrigger => "TARGET HIT",
+        SfxBind::ShowEjectCashpot => "CASHPOT EJECT",
+        SfxBind::StonesEject => "EJECT",
+        SfxBind::StonesHitStone => "STONE HIT",
+        SfxBind::StonesHitBone => "BONE HIT",
+    })
+}
+
 impl Table {
     pub fn play_sfx_bind(&self, bind: SfxBind) {
         self.play_sfx_bind_volume(bind, 0x40)
@@ -10,24 +59,80 @@ impl Table {
     pub fn play_sfx_bind_volume(&self, bind: SfxBind, volume: u8) {
         if let Some(sfx) = self.assets.sfx_binds[bind] {
             self.player.play_sfx(sfx, volume);
+            self.caption(sfx_caption(bind));
         }
     }
 
     pub fn play_jingle_bind(&self, bind: JingleBind) -> bool {
         let jingle = self.assets.jingle_binds[bind].unwrap();
-        self.sequencer.play_jingle(jingle, false, None)
+        self.caption_played(self.sequencer.play_jingle(jingle, false, None), bind)
     }
 
     pub fn play_jingle_bind_force(&self, bind: JingleBind) -> bool {
         let jingle = self.assets.jingle_binds[bind].unwrap();
-        self.sequencer.play_jingle(jingle, true, None)
+        self.caption_played(self.sequencer.play_jingle(jingle, true, None), bind)
     }
 
     pub fn play_jingle_bind_silence(&self, bind: JingleBind) -> bool {
         let jingle = self.assets.jingle_binds[bind].unwrap();
         let silence = self.assets.jingle_binds[JingleBind::Silence].unwrap();
-        self.sequencer
-            .play_jingle(jingle, false, Some(silence.position))
+        let played = self
+            .sequencer
+            .play_jingle(jingle, false, Some(silence.position));
+        self.caption_played(played, bind)
+    }
+
+    fn caption_played(&self, played: bool, bind: JingleBind) -> bool {
+        if played {
+            self.caption(jingle_caption(bind));
+        }
+        played
+    }
+
+    pub fn caption(&self, text: Option<&'static str>) {
+        if let (true, Some(text)) = (self.options.captions, text) {
+            self.caption.set(Some((text, CAPTION_FRAMES)));
+        }
+    }
+
+    // Jingles and sounds played straight from the table data get the caption
+    // of the bind they're the same as, if any.
+    pub fn caption_jingle(&self, jingle: Jingle) {
+        let bind = self
+            .assets
+            .jingle_binds
+            .iter()
+            .find(|(_, bound)| bound.is_some_and(|bound| bound.position == jingle.position));
+        if let Some((bind, _)) = bind {
+            self.caption(jingle_caption(bind));
+        }
+    }
+
+    pub fn caption_sfx(&self, sfx: Sfx) {
+        let bind = self.assets.sfx_binds.iter().find(|(_, bound)| {
+            bound.is_some_and(|bound| bound.sample == sfx.sample && bound.period == sfx.period)
+        });
+        if let Some((bind, _)) = bind {
+            self.caption(sfx_caption(bind));
+        }
+    }
+
+    pub fn caption_frame(&mut self) {
+        if let Some((text, frames)) = self.caption.get() {
+            self.caption.set((frames > 1).then_some((text, frames - 1)));
+        }
+    }
+
+    // In the bottom right corner of the board, above the dot matrix.
+    pub fn render_caption(&self, data: &mut [u8], height: usize) {
+        let Some((text, _)) = self.caption.get() else {
+            return;
+        };
+        let width = text.len() * 8 + 4;
+        for row in data[(height - 10) * 320..height * 320].chunks_exact_mut(320) {
+            row[320 - width..].fill(0);
+        }
+        self.render_board_text(data, 320 - width + 2, height - 8, text.as_bytes());
     }
 
     pub fn set_music_silence(&self) {
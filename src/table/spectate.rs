@@ -0,0 +1,27 @@
+use crate::spectate::Snapshot;
+
+use super::Table;
+
+impl Table {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            table: self.table_id(),
+            status: self.status(),
+            ball_pos: self.ball.pos(),
+            ball_layer: self.ball.layer,
+            dm: self.dm.pixels,
+            dm_lit: self.dm.state(),
+            events: vec![],
+        }
+    }
+
+    // Puts the ball and the dot matrix where a served game has them, for a
+    // spectator.  Nothing else runs, so the table stays as it is otherwise.
+    pub fn show_snapshot(&mut self, snapshot: &Snapshot) {
+        self.ball.layer = snapshot.ball_layer;
+        self.ball.set_pos(snapshot.ball_pos);
+        self.scroll.update(snapshot.ball_pos.1);
+        self.dm.pixels = snapshot.dm;
+        self.dm.set_state(snapshot.dm_lit);
+    }
+}
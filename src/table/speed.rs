@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     assets::table::{
         lights::LightBind,
@@ -10,6 +12,7 @@ use crate::{
 
 use super::{tasks::TaskKind, Table};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SpeedState {
     pub blink_bur: [bool; 3],
     pub blink_nin: [bool; 3],
@@ -358,6 +361,7 @@ impl Table {
         self.mode_count_ramp();
         self.incr_jackpot();
         if self.light_state(LightBind::SpeedMiniRampJackpot, 0) {
+            self.emit_jackpot();
             self.score_main += self.score_jackpot;
             self.score_jackpot = self.assets.score_jackpot_init;
             if self.in_mode_ramp {
@@ -0,0 +1,378 @@
+use std::io;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use unnamed_entity::EntityVec;
+
+use crate::{
+    assets::table::{
+        flippers::FlipperId,
+        physics::{BumperId, Layer, RollTrigger},
+    },
+    bcd::Bcd,
+    config::{Config, Options, TableId},
+    data::DataSource,
+};
+
+use super::{
+    ball::BallState,
+    cheat::CheatState,
+    dm::DotMatrix,
+    lights::Lights,
+    multiball::AuxBall,
+    party::PartyState,
+    physics::{FlipperState, PushState},
+    player::PlayerState,
+    script::ScriptState,
+    scroll::ScrollState,
+    show::ShowState,
+    speed::SpeedState,
+    stones::StonesState,
+    tasks::Task,
+    KbdState, Table,
+};
+
+pub mod entity_idx {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use unnamed_entity::EntityId;
+
+    pub fn serialize<I: EntityId, S: Serializer>(id: &I, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u64(id.to_idx() as u64)
+    }
+
+    pub fn deserialize<'de, I: EntityId, D: Deserializer<'de>>(d: D) -> Result<I, D::Error> {
+        Ok(I::from_idx(usize::deserialize(d)?))
+    }
+}
+
+pub mod opt_entity_idx {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use unnamed_entity::EntityId;
+
+    pub fn serialize<I: EntityId, S: Serializer>(id: &Option<I>, s: S) -> Result<S::Ok, S::Error> {
+        match id {
+            Some(id) => s.serialize_some(&(id.to_idx() as u64)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, I: EntityId, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<I>, D::Error> {
+        Ok(Option::<usize>::deserialize(d)?.map(I::from_idx))
+    }
+}
+
+pub mod entity_vec {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use unnamed_entity::{EntityId, EntityVec};
+
+    pub fn serialize<I: EntityId, V: Serialize, S: Serializer>(
+        vec: &EntityVec<I, V>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.collect_seq(vec.values())
+    }
+
+    pub fn deserialize<'de, I: EntityId, V: Deserialize<'de>, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<EntityVec<I, V>, D::Error> {
+        Ok(Vec::<V>::deserialize(d)?.into_iter().collect())
+    }
+}
+
+// Dot matrix contents as one string per row, '#' for a lit dot.
+pub mod dm_rows {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(pixels: &[[bool; 160]; 16], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(pixels.iter().map(|row| {
+            row.iter()
+                .map(|&lit| if lit { '#' } else { '.' })
+                .collect::<String>()
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[[bool; 160]; 16], D::Error> {
+        let rows = Vec::<String>::deserialize(d)?;
+        if rows.len() != 16 || rows.iter().any(|row| row.len() != 160) {
+            return Err(D::Error::custom("dot matrix must be 16 rows of 160 dots"));
+        }
+        let mut pixels = [[false; 160]; 16];
+        for (line, row) in pixels.iter_mut().zip(rows) {
+            for (pixel, chr) in line.iter_mut().zip(row.bytes()) {
+                *pixel = chr == b'#';
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PhysmapPatch {
+    layer: Layer,
+    idx: u32,
+    val: u8,
+}
+
+// The dynamic part of the table, written and read back in one go.  Assets,
+// the sound player and anything derived from the options are rebuilt by
+// Table::new instead, and held-down inputs are dropped.
+macro_rules! saved_fields {
+    ($($(#[$attr:meta])* $field:ident: $ty:ty,)*) => {
+        #[derive(Serialize)]
+        struct SavedFieldsRef<'a> {
+            $($(#[$attr])* $field: &'a $ty,)*
+        }
+
+        #[derive(Deserialize)]
+        struct SavedFields {
+            $($(#[$attr])* $field: $ty,)*
+        }
+
+        impl Table {
+            fn saved_fields(&self) -> SavedFieldsRef<'_> {
+                SavedFieldsRef {
+                    $($field: &self.$field,)*
+                }
+            }
+
+            fn clone_saved_fields(&self) -> SavedFields {
+                SavedFields {
+                    $($field: self.$field.clone(),)*
+                }
+            }
+
+            fn restore_fields(&mut self, saved: SavedFields) {
+                $(self.$field = saved.$field;)*
+            }
+        }
+    };
+}
+
+saved_fields! {
+    scroll: ScrollState,
+    lights: Lights,
+    push: PushState,
+    spring_pos: u8,
+    dm: DotMatrix,
+    script: ScriptState,
+    tasks: Vec<Task>,
+    ball: BallState,
+    cheat: CheatState,
+    #[serde(with = "entity_vec")]
+    flippers: EntityVec<FlipperId, FlipperState>,
+
+    in_attract: bool,
+    in_game_start: bool,
+    in_plunger: bool,
+    at_spring: bool,
+    in_drain: bool,
+    drained: bool,
+    got_top_score: bool,
+    party_on: bool,
+    special_plunger_event: bool,
+    match_digit: Option<u8>,
+    ball_scored_points: bool,
+    tilted: bool,
+    tilt_counter: u16,
+    ball_save_frames: u16,
+    silence_effect: bool,
+    timer_stop: bool,
+    block_drain: bool,
+    got_high_score: bool,
+    #[serde(default)]
+    cheated: bool,
+    #[serde(default)]
+    got_replay: bool,
+
+    in_mode: bool,
+    in_mode_hit: bool,
+    in_mode_ramp: bool,
+    pending_mode: bool,
+    pending_mode_hit: bool,
+    pending_mode_ramp: bool,
+    mode_timeout_frames: u8,
+    mode_timeout_secs: u8,
+
+    kbd_state: KbdState,
+    flippers_enabled: bool,
+    start_keys_active: bool,
+
+    cur_player: u8,
+    total_players: u8,
+    cur_ball: u8,
+    total_balls: u8,
+    extra_balls: u8,
+    bonus_mult_early: u8,
+    bonus_mult_late: u8,
+    players: Vec<PlayerState>,
+
+    score_main: Bcd,
+    score_bonus: Bcd,
+    score_jackpot: Bcd,
+    score_mode_hit: Bcd,
+    score_mode_ramp: Bcd,
+    score_raising_millions: Bcd,
+    num_cyclone: u16,
+    num_cyclone_target: u16,
+    bcd_num_cyclone: Bcd,
+    score_cyclone_bonus: Bcd,
+    hold_bonus: bool,
+
+    hit_pos: Option<(i16, i16)>,
+    #[serde(with = "opt_entity_idx")]
+    hit_bumper: Option<BumperId>,
+    roll_trigger: Option<RollTrigger>,
+    prev_roll_trigger: Option<RollTrigger>,
+    #[serde(default)]
+    aux_balls: Vec<AuxBall>,
+
+    party: PartyState,
+    speed: SpeedState,
+    show: ShowState,
+    stones: StonesState,
+}
+
+pub(super) struct RollbackPoint {
+    fields: SavedFields,
+    physmaps: Vec<PhysmapPatch>,
+}
+
+#[derive(Serialize)]
+struct SavedTableRef<'a> {
+    table: TableId,
+    options: Options,
+    seed: u32,
+    music: u32,
+    physmaps: Vec<PhysmapPatch>,
+    state: SavedFieldsRef<'a>,
+}
+
+#[derive(Deserialize)]
+struct SavedTable {
+    table: TableId,
+    options: Options,
+    seed: u32,
+    music: u32,
+    physmaps: Vec<PhysmapPatch>,
+    state: SavedFields,
+}
+
+impl Table {
+    // The saved state kept in memory, to undo frames with.
+    pub(super) fn rollback_point(&self) -> RollbackPoint {
+        RollbackPoint {
+            fields: self.clone_saved_fields(),
+            physmaps: self.physmap_patches(),
+        }
+    }
+
+    pub(super) fn roll_back(&mut self, point: RollbackPoint) {
+        self.restore_fields(point.fields);
+        // the patches were taken from these maps, so they always fit
+        let fits = self.restore_physmaps(&point.physmaps);
+        debug_assert!(fits);
+    }
+
+    // Only the physmap bytes that differ from the assets' are kept.
+    fn physmap_patches(&self) -> Vec<PhysmapPatch> {
+        let mut physmaps = vec![];
+        for (layer, map) in &self.physmaps {
+            let orig = &self.assets.physmaps[layer];
+            for (idx, (&val, &orig)) in map.iter().zip(orig.iter()).enumerate() {
+                if val != orig {
+                    physmaps.push(PhysmapPatch {
+                        layer,
+                        idx: idx as u32,
+                        val,
+                    });
+                }
+            }
+        }
+        physmaps
+    }
+
+    // false if a patch is out of range
+    fn restore_physmaps(&mut self, patches: &[PhysmapPatch]) -> bool {
+        for (layer, map) in &mut self.physmaps {
+            map.assign(&self.assets.physmaps[layer]);
+        }
+        for patch in patches {
+            let map = &mut self.physmaps[patch.layer];
+            let (w, h) = map.dim();
+            let idx = patch.idx as usize;
+            if idx >= w * h {
+                return false;
+            }
+            map[(idx / h, idx % h)] = patch.val;
+        }
+        true
+    }
+
+    fn fits_assets(&self) -> bool {
+        self.lights.fits(&self.assets)
+            && self.script.fits(&self.assets)
+            && self.flippers.len() == self.assets.flippers.len()
+            && self
+                .hit_bumper
+                .is_none_or(|bumper| self.assets.bumpers.get(bumper).is_some())
+            && self.aux_balls_fit()
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        let saved = SavedTableRef {
+            table: self.assets.table,
+            options: self.options,
+            // the generator itself can't be stored, so the loaded game continues
+            // from a fresh seed drawn from it
+            seed: self.rng.clone().gen::<u32>(),
+            music: self.sequencer.save(),
+            physmaps: self.physmap_patches(),
+            state: self.saved_fields(),
+        };
+        toml::to_string(&saved).unwrap().into_bytes()
+    }
+
+    pub fn load_state(
+        data: &dyn DataSource,
+        mut config: Config,
+        saved: &[u8],
+    ) -> io::Result<Table> {
+        let saved = std::str::from_utf8(saved)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let saved: SavedTable =
+            toml::from_str(saved).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        config.options = saved.options;
+        config.ignore_table_options = true;
+        let mut table = Table::new(data, config, saved.table, 0)?;
+        table.restore_fields(saved.state);
+        // indices into the assets are only checked here, a mismatch would
+        // otherwise panic somewhere in the middle of a frame
+        if !table.fits_assets() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "saved state doesn't match the table assets",
+            ));
+        }
+        // the attract light show may have been changed since the save
+        table.lights.fit_attract(&table.assets);
+        table.rng = StdRng::seed_from_u64(saved.seed.into());
+        table.show.wheel_timing = ShowState::new(table.hifps).wheel_timing;
+        if !table.restore_physmaps(&saved.physmaps) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "physmap patch out of range",
+            ));
+        }
+        table.sequencer.restore(saved.music);
+        table.sequencer.set_no_music(table.options.no_music);
+        if matches!(
+            table.kbd_state,
+            KbdState::Paused | KbdState::PausedConfirmQuit | KbdState::PausedOptions
+        ) {
+            table.player.pause();
+        }
+        Ok(table)
+    }
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     assets::table::{
         lights::LightBind,
@@ -10,6 +12,7 @@ use crate::{
 
 use super::{tasks::TaskKind, Table};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StonesState {
     pub flipper_lock_key: bool,
     pub flipper_lock_rip: bool,
@@ -477,6 +480,7 @@ impl Table {
             self.stones.tower_jackpot = false;
             self.light_set(LightBind::StonesTowerJackpot, 0, false);
             self.effect_force(EffectBind::StonesTowerJackpot);
+            self.emit_jackpot();
             self.score_main += self.score_jackpot;
             self.score_jackpot = self.assets.score_jackpot_init;
             visible_effect = true;
@@ -576,7 +580,8 @@ impl Table {
 
     pub fn stones_tower_eject(&mut self) {
         self.play_sfx_bind(SfxBind::StonesEject);
-        self.ball.teleport(Layer::Overhead, (141, 143), (0, -3333));
+        self.ball
+            .teleport(Layer::Overhead, (141, 143), (0, -3333), &mut self.rng);
         self.stones.in_tower = false;
     }
 
@@ -597,7 +602,8 @@ impl Table {
             self.sequencer.reset_priority();
             visible_effect |= self.effect(EffectBind::StonesLock);
             self.silence_effect = true;
-            self.ball.teleport(Layer::Ground, (300, 530), (10, 0));
+            self.ball
+                .teleport(Layer::Ground, (300, 530), (10, 0), &mut self.rng);
             self.special_plunger_event = true;
             self.stones.in_well = false;
             self.set_music_plunger();
@@ -648,7 +654,8 @@ impl Table {
             self.sequencer.reset_priority();
             visible_effect |= self.effect(EffectBind::StonesLock);
             self.silence_effect = true;
-            self.ball.teleport(Layer::Ground, (300, 530), (10, 0));
+            self.ball
+                .teleport(Layer::Ground, (300, 530), (10, 0), &mut self.rng);
             self.special_plunger_event = true;
             self.stones.in_vault = false;
             self.set_music_plunger();
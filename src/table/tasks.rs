@@ -1,4 +1,5 @@
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::table::{
@@ -13,7 +14,8 @@ use crate::{
 
 use super::{show::PrizeState, KbdState, Table};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "task", content = "args")]
 pub enum TaskKind {
     SetStartKeysActive,
     PartyOn,
@@ -22,6 +24,7 @@ pub enum TaskKind {
     IssueBallRelease,
     IssueBallSfx,
     IssueBallRaiseSfx,
+    BallSaveLaunch,
     DrainSfx,
     GameOver,
     PartyDropZoneStart(u16),
@@ -93,12 +96,18 @@ pub enum TaskKind {
     StonesScreamExtra,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Task {
     timer: u16,
     kind: TaskKind,
 }
 
 impl Task {
+    #[cfg(feature = "headless")]
+    pub fn timer(&self) -> u16 {
+        self.timer
+    }
+
     pub fn run(&mut self, table: &mut Table) -> bool {
         if self.timer != self.kind.delay(table) {
             self.timer += 1;
@@ -115,6 +124,10 @@ impl Task {
             TaskKind::IssueBallRelease => table.issue_ball_release(),
             TaskKind::IssueBallSfx => table.play_sfx_bind(SfxBind::IssueBall),
             TaskKind::IssueBallRaiseSfx => table.play_sfx_bind(SfxBind::RaiseHitTargets),
+            TaskKind::BallSaveLaunch => {
+                table.spring_pos = 0x18;
+                table.spring_released = true;
+            }
             TaskKind::DrainSfx => table.play_sfx_bind(SfxBind::BallDrained),
             TaskKind::GameOver => {
                 table.kbd_state = KbdState::Main;
@@ -137,7 +150,8 @@ impl Task {
                 table.ball.teleport(
                     Layer::Overhead,
                     (15, 47),
-                    (0, rand::thread_rng().gen_range(0..0x80)),
+                    (0, table.rng.gen_range(0..0x80)),
+                    &mut table.rng,
                 );
                 table.play_sfx_bind(SfxBind::IssueBall);
                 table.light_set_all(LightBind::PartyDrop, false);
@@ -183,7 +197,11 @@ impl Task {
                 table.party.cyclone_x5 = false;
                 table.light_set(LightBind::PartyCycloneX5, 0, false);
             }
-            TaskKind::PartyTunnelFreeze => table.ball.teleport(Layer::Ground, (15, 47), (0, 0)),
+            TaskKind::PartyTunnelFreeze => {
+                table
+                    .ball
+                    .teleport(Layer::Ground, (15, 47), (0, 0), &mut table.rng)
+            }
             TaskKind::PartyArcadePickReward => {
                 if table.in_mode || table.party.arcade_ready {
                     if table.tilted {
@@ -235,7 +253,9 @@ impl Task {
             }
             TaskKind::PartySnacksRelease => {
                 table.play_sfx_bind(SfxBind::PartySnacksRelease);
-                table.ball.teleport(Layer::Overhead, (3, 253), (0, -2500));
+                table
+                    .ball
+                    .teleport(Layer::Overhead, (3, 253), (0, -2500), &mut table.rng);
                 table.add_task(TaskKind::PartySnacksFinish);
             }
             TaskKind::PartySnacksFinish => table.party.in_snack = false,
@@ -246,7 +266,9 @@ impl Task {
             TaskKind::PartyDemonRelease => {
                 table.light_set(LightBind::PartyDemonHead, 0, false);
                 table.play_sfx_bind(SfxBind::IssueBall);
-                table.ball.teleport(Layer::Ground, (257, 310), (-575, 1575));
+                table
+                    .ball
+                    .teleport(Layer::Ground, (257, 310), (-575, 1575), &mut table.rng);
                 table.party.in_demon = false;
             }
             TaskKind::PartySideExtraBallFinish => table.block_drain = false,
@@ -330,7 +352,9 @@ impl Task {
             }
             TaskKind::SpeedPitStop(_) => {
                 table.play_sfx_bind(SfxBind::SpeedEjectPit);
-                table.ball.teleport(Layer::Ground, (256, 41), (-2100, 800));
+                table
+                    .ball
+                    .teleport(Layer::Ground, (256, 41), (-2100, 800), &mut table.rng);
             }
             TaskKind::SpeedUnblinkCar => {
                 table.light_set_all(LightBind::SpeedCarPart, false);
@@ -433,13 +457,17 @@ impl Task {
             TaskKind::StonesTowerEjectNow => table.stones_tower_eject(),
             TaskKind::StonesWellEject => {
                 table.play_sfx_bind(SfxBind::StonesEject);
-                table.ball.teleport(Layer::Ground, (275, 245), (-666, 1666));
+                table
+                    .ball
+                    .teleport(Layer::Ground, (275, 245), (-666, 1666), &mut table.rng);
                 table.stones.in_well = false;
             }
             TaskKind::StonesVaultEject => {
                 table.play_sfx_bind(SfxBind::StonesEject);
                 table.drop_physmap(PhysmapBind::StonesGateKickback);
-                table.ball.teleport(Layer::Ground, (2, 532), (0, -2880));
+                table
+                    .ball
+                    .teleport(Layer::Ground, (2, 532), (0, -2880), &mut table.rng);
                 table.stones.in_vault = false;
                 table.add_task(TaskKind::StonesRaiseKickback);
             }
@@ -500,6 +528,7 @@ impl TaskKind {
             TaskKind::IssueBallRelease => 80,
             TaskKind::IssueBallSfx => 45,
             TaskKind::IssueBallRaiseSfx => 5,
+            TaskKind::BallSaveLaunch => 140,
             TaskKind::DrainSfx => 5,
             TaskKind::GameOver => 0,
             TaskKind::PartyDropZoneStart(delay) => delay,
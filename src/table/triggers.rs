@@ -61,6 +61,9 @@ impl Table {
     }
 
     pub fn do_roll_trigger(&mut self, kind: RollTrigger) {
+        if self.aux_ball_trigger(kind) {
+            return;
+        }
         match kind {
             RollTrigger::Dummy => (),
             RollTrigger::PlungerBottom => self.at_spring = true,
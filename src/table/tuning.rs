@@ -0,0 +1,136 @@
+use winit::event::VirtualKeyCode;
+
+use crate::assets::table::physics::MATERIALS;
+
+use super::{
+    physics::{speed_fix, BUMPER_SPEED_BOOST, KICKER_SPEED_BOOST},
+    Table,
+};
+
+// Physics constants that can be changed while playing with the tuning cheat,
+// to try out how the table feels.  Nothing here is saved.
+pub struct Tuning {
+    // percent of the ramps' own acceleration
+    gravity: i16,
+    bounce_factors: [i16; 8],
+    kicker_boost: i16,
+    bumper_boost: i16,
+    sel: usize,
+}
+
+const MATERIAL_NAMES: [&str; 8] = [
+    "DUMMY 0", "DUMMY 1", "FLIPPER", "RUBBER", "DUMMY 4", "DUMMY 5", "STEEL", "PLASTIC",
+];
+
+// gravity, the materials' bounce factors, then the kicker and bumper boosts
+const TUNING_ITEMS: usize = 11;
+
+// (step, min, max)
+const GRAVITY_RANGE: (i16, i16, i16) = (10, 0, 300);
+// the bounce factor divides the speed into the wall that's taken away, so
+// it has to stay above 256
+const BOUNCE_RANGE: (i16, i16, i16) = (20, 260, 2000);
+const BOOST_RANGE: (i16, i16, i16) = (250, 0, 15000);
+
+impl Tuning {
+    pub fn stock() -> Self {
+        Tuning {
+            gravity: 100,
+            bounce_factors: MATERIALS.map(|material| material.bounce_factor),
+            kicker_boost: KICKER_SPEED_BOOST,
+            bumper_boost: BUMPER_SPEED_BOOST,
+            sel: 0,
+        }
+    }
+
+    pub fn scale_gravity(&self, accel: (i16, i16)) -> (i16, i16) {
+        if self.gravity == 100 {
+            return accel;
+        }
+        let scale = |v: i16| (i32::from(v) * i32::from(self.gravity) / 100) as i16;
+        (scale(accel.0), scale(accel.1))
+    }
+
+    fn item(&mut self, idx: usize) -> (&mut i16, (i16, i16, i16)) {
+        match idx {
+            0 => (&mut self.gravity, GRAVITY_RANGE),
+            1..=8 => (&mut self.bounce_factors[idx - 1], BOUNCE_RANGE),
+            9 => (&mut self.kicker_boost, BOOST_RANGE),
+            _ => (&mut self.bumper_boost, BOOST_RANGE),
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["TUNING".to_string(), format!("GRAVITY {:5}", self.gravity)];
+        for (name, factor) in MATERIAL_NAMES.iter().zip(self.bounce_factors) {
+            lines.push(format!("{name:7} {factor:5}"));
+        }
+        lines.push(format!("KICKER  {:5}", self.kicker_boost));
+        lines.push(format!("BUMPER  {:5}", self.bumper_boost));
+        for (i, line) in lines[1..].iter_mut().enumerate() {
+            line.insert_str(0, if i == self.sel { "- " } else { "  " });
+        }
+        lines
+    }
+}
+
+impl Table {
+    // The tuned values, on top of what rebuild_physics sets up for the frame
+    // rate.
+    pub fn apply_tuning(&mut self) {
+        for (material, &factor) in self.materials.iter_mut().zip(&self.tuning.bounce_factors) {
+            material.bounce_factor = factor;
+        }
+        self.kicker_speed_boost = speed_fix(self.tuning.kicker_boost, self.hifps);
+        self.bumper_speed_boost = speed_fix(self.tuning.bumper_boost, self.hifps);
+    }
+
+    pub fn reset_tuning(&mut self) {
+        self.tuning = Tuning::stock();
+        self.apply_tuning();
+    }
+
+    // Returns true if the key was used by the tuning panel.
+    pub fn tuning_key(&mut self, key: VirtualKeyCode) -> bool {
+        if !self.cheat.tuning {
+            return false;
+        }
+        let tuning = &mut self.tuning;
+        match key {
+            VirtualKeyCode::LBracket => tuning.sel = (tuning.sel + TUNING_ITEMS - 1) % TUNING_ITEMS,
+            VirtualKeyCode::RBracket => tuning.sel = (tuning.sel + 1) % TUNING_ITEMS,
+            VirtualKeyCode::Semicolon | VirtualKeyCode::Apostrophe => {
+                let (value, (step, min, max)) = tuning.item(tuning.sel);
+                *value = if key == VirtualKeyCode::Semicolon {
+                    (*value - step).max(min)
+                } else {
+                    (*value + step).min(max)
+                };
+                self.apply_tuning();
+            }
+            VirtualKeyCode::Backslash => {
+                let sel = tuning.sel;
+                self.reset_tuning();
+                self.tuning.sel = sel;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render_tuning(&self, data: &mut [u8], height: usize) {
+        let lines = self.tuning.lines();
+        let box_height = (lines.len() * 7 + 2).min(height);
+        let top = height - box_height;
+        for row in data[top * 320..height * 320].chunks_exact_mut(320) {
+            row[..124].fill(0);
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let y = top + 2 + i * 7;
+            if y + 5 > height {
+                break;
+            }
+            self.render_board_text(data, 0, y, line.as_bytes());
+        }
+    }
+}
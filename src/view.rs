@@ -1,20 +1,68 @@
-use winit::event::{ElementState, VirtualKeyCode};
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
 
-use crate::config::{HighScore, Options, TableId};
+use crate::{
+    config::{HighScore, Options, Resolution, TableId},
+    spectate::Snapshot,
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Route {
     Intro(Option<TableId>),
+    // the one-screen table picker, with the cursor on the given table
+    TableSelect(Option<TableId>),
     Table(TableId),
+    // the intro's attract demo, played on the given table
+    AttractDemo(TableId),
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftShoulder,
+    RightShoulder,
+    Start,
+    Select,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Nudge,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub enum Action {
     None,
     Navigate(Route),
     Exit,
     SaveOptions(Options),
-    SaveHighScores(TableId, [HighScore; 4]),
+    SaveHighScores(TableId, Vec<HighScore>),
+    ResetHighScores,
+    // load this table state, keeping it to rewind to again
+    RewindBall(Vec<u8>),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GameStatus {
+    pub score: u64,
+    pub cur_player: u8,
+    pub total_players: u8,
+    pub cur_ball: u8,
+    pub total_balls: u8,
+    pub extra_balls: u8,
+    pub tilted: bool,
+    pub in_mode: bool,
+    pub in_attract: bool,
+}
+
+// A panel shown to the right of the view's frame, as tall as the frame and
+// with a palette of its own.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PanelLayout {
+    pub width: u32,
 }
 
 pub trait View {
@@ -22,5 +70,54 @@ pub trait View {
     fn get_fps(&self) -> u32;
     fn run_frame(&mut self) -> Action;
     fn handle_key(&mut self, key: VirtualKeyCode, state: ElementState);
+    fn handle_button(&mut self, _button: PadButton, _state: ElementState) {}
+    // pos is in framebuffer pixels; returns false to let the button act as a flipper
+    fn handle_mouse(
+        &mut self,
+        _pos: (u32, u32),
+        _button: MouseButton,
+        _state: ElementState,
+    ) -> bool {
+        false
+    }
+    // key releases won't arrive for keys held when the window lost focus
+    fn on_focus_lost(&mut self) {}
     fn render(&self, data: &mut [u8], pal: &mut [(u8, u8, u8)]);
+    // renders the frame after the current one and goes back, for run-ahead
+    fn render_ahead(&mut self, data: &mut [u8], pal: &mut [(u8, u8, u8)]) {
+        self.render(data, pal);
+    }
+    // draws a short line of text in the top right corner over a rendered frame
+    fn render_frame_stats(&self, _data: &mut [u8], _pal: &mut [(u8, u8, u8)], _text: &[u8]) {}
+    // switches to another resolution on the fly; false if the view can't
+    fn set_resolution(&mut self, _resolution: Resolution) -> bool {
+        false
+    }
+    fn get_extra_panel(&self) -> Option<PanelLayout> {
+        None
+    }
+    fn render_panel(&self, _data: &mut [u8], _pal: &mut [(u8, u8, u8)]) {}
+    // rows at the top of the frame showing the scrolled board, and how far
+    // (in sixteenths) to blend each of them into the row below
+    fn scroll_blend(&self) -> Option<(usize, u8)> {
+        None
+    }
+    fn game_status(&self) -> Option<GameStatus> {
+        None
+    }
+    // text currently shown on the dot matrix display, one string per row
+    fn display_text(&self) -> Vec<String> {
+        vec![]
+    }
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+    // what a spectator needs to follow the game
+    fn snapshot(&self) -> Option<Snapshot> {
+        None
+    }
+    // high scores not saved yet, to write out before the game exits
+    fn take_unsaved_high_scores(&mut self) -> Option<(TableId, Vec<HighScore>)> {
+        None
+    }
 }
@@ -0,0 +1,519 @@
+#![cfg(feature = "headless")]
+
+use std::{io::Cursor, path::Path};
+
+use pfr::{
+    assets::iff::Image,
+    assets::table::physics::Layer,
+    attract::AttractDemo,
+    config::{ColorMode, Config, KeyBindings, Resolution, TableId},
+    data::{open_data_source, DataSource},
+    game::{Game, Input, Key},
+    lang::Language,
+    palette::{expand, expand_naive, rgba_lut},
+    sound::loader,
+    spectate::{read_header, read_snapshot, write_header, write_snapshot, Snapshot},
+    table::{GameEvent, Table},
+    view::{Action, GameStatus, Route, View},
+};
+use winit::event::{ElementState, VirtualKeyCode};
+
+// Tests that need the game data are ignored by default, run them with
+// `PFR_DATA=<dir> cargo test --features headless -- --ignored`.
+fn data_source() -> Box<dyn DataSource> {
+    let dir = std::env::var_os("PFR_DATA").expect("PFR_DATA is not set");
+    open_data_source(Path::new(&dir)).unwrap()
+}
+
+fn config() -> Config {
+    Config {
+        no_audio: true,
+        ..Config::default()
+    }
+}
+
+fn step_until(table: &mut Table, frames: u32, f: impl Fn(&Table) -> bool) -> bool {
+    for _ in 0..frames {
+        if f(table) {
+            return true;
+        }
+        table.step();
+    }
+    f(table)
+}
+
+fn press(table: &mut Table, key: VirtualKeyCode, frames: u32) {
+    table.inject_key(key, ElementState::Pressed);
+    for _ in 0..frames {
+        table.step();
+    }
+    table.inject_key(key, ElementState::Released);
+    table.step();
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn plunged_ball_drains() {
+    let data = data_source();
+    for table_id in [
+        TableId::Table1,
+        TableId::Table2,
+        TableId::Table3,
+        TableId::Table4,
+    ] {
+        let mut table = Table::new(&*data, config(), table_id, 0).unwrap();
+        for _ in 0..60 {
+            table.step();
+        }
+        press(&mut table, VirtualKeyCode::Return, 1);
+        assert!(
+            step_until(&mut table, 60 * 60, |t| !t.in_attract() && t.at_spring()),
+            "{table_id:?}: ball never reached the plunger"
+        );
+        press(&mut table, VirtualKeyCode::Down, 60);
+        assert!(
+            step_until(&mut table, 60 * 600, |t| t.in_drain()),
+            "{table_id:?}: ball never drained"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn focus_loss_releases_flippers() {
+    let data = data_source();
+    let mut table = Table::new(&*data, config(), TableId::Table1, 0).unwrap();
+    table.step();
+    table.inject_key(VirtualKeyCode::LShift, ElementState::Pressed);
+    table.step();
+    assert!(table.flipper_held());
+    table.on_focus_lost();
+    table.step();
+    assert!(!table.flipper_held(), "flipper stuck after losing focus");
+    table.inject_key(VirtualKeyCode::LShift, ElementState::Pressed);
+    table.step();
+    assert!(table.flipper_held());
+    table.inject_key(VirtualKeyCode::LShift, ElementState::Released);
+    table.step();
+    assert!(!table.flipper_held());
+}
+
+fn letter_key(chr: u8) -> VirtualKeyCode {
+    use VirtualKeyCode::*;
+    const LETTERS: [VirtualKeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    if chr == b' ' {
+        Space
+    } else {
+        LETTERS[usize::from(chr - b'A')]
+    }
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn key_repeat_types_cheat_letters_once() {
+    let data = data_source();
+    let mut table = Table::new(&*data, config(), TableId::Table1, 0).unwrap();
+    table.step();
+    let code = table
+        .cheat_codes()
+        .into_iter()
+        .find(|code| code.len() >= 3)
+        .expect("no cheat codes");
+    for (i, &chr) in code[..2].iter().enumerate() {
+        let key = letter_key(chr);
+        // auto-repeat sends more presses without releases in between
+        for _ in 0..3 {
+            table.inject_key(key, ElementState::Pressed);
+            table.step();
+        }
+        assert_eq!(table.cheat_buf(), &code[..i + 1]);
+        table.inject_key(key, ElementState::Released);
+        table.step();
+    }
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn pause_freezes_timers() {
+    let data = data_source();
+    let mut table = Table::new(&*data, config(), TableId::Table1, 0).unwrap();
+    for _ in 0..60 {
+        table.step();
+    }
+    press(&mut table, VirtualKeyCode::Return, 1);
+    assert!(step_until(&mut table, 60 * 60, |t| !t.in_attract() && t.at_spring()));
+    press(&mut table, VirtualKeyCode::Down, 60);
+    for _ in 0..60 {
+        table.step();
+    }
+    table.start_happy_hour();
+    assert!(
+        step_until(&mut table, 60 * 30, |t| t.mode_timeout() != (0, 0)),
+        "happy hour didn't start"
+    );
+    table.inject_key(VirtualKeyCode::Space, ElementState::Pressed);
+    table.step();
+    table.inject_key(VirtualKeyCode::Space, ElementState::Released);
+    let tilt_counter = table.tilt_counter();
+    let mode_timeout = table.mode_timeout();
+    let task_timers = table.task_timers();
+    let blink_counters = table.blink_counters();
+    assert_ne!(tilt_counter, 0, "nudge didn't count towards a tilt");
+    assert!(!task_timers.is_empty(), "no task running");
+    assert!(!blink_counters.is_empty(), "no light blinking");
+    press(&mut table, VirtualKeyCode::P, 0);
+    for _ in 0..600 {
+        table.step();
+    }
+    // nudging and flipping while paused must not carry over either
+    press(&mut table, VirtualKeyCode::Space, 10);
+    press(&mut table, VirtualKeyCode::LShift, 10);
+    assert_eq!(table.tilt_counter(), tilt_counter);
+    assert_eq!(table.mode_timeout(), mode_timeout);
+    assert_eq!(table.task_timers(), task_timers);
+    assert_eq!(table.blink_counters(), blink_counters);
+    table.inject_key(VirtualKeyCode::P, ElementState::Pressed);
+    table.inject_key(VirtualKeyCode::P, ElementState::Released);
+    assert_eq!(table.tilt_counter(), tilt_counter);
+    assert!(!table.flipper_held());
+    table.step();
+    assert_eq!(table.tilt_counter(), tilt_counter - 1);
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn run_ahead_is_undone() {
+    let data = data_source();
+    let mut tables = [0, 1].map(|_| Table::new(&*data, config(), TableId::Table2, 0).unwrap());
+    let (w, h) = tables[0].get_resolution();
+    let mut frame = vec![0; (w * h) as usize];
+    let mut pal = [(0, 0, 0); 256];
+    let inputs = [
+        (60, VirtualKeyCode::Return),
+        (200, VirtualKeyCode::Down),
+        (400, VirtualKeyCode::LShift),
+        (450, VirtualKeyCode::RShift),
+    ];
+    for i in 0..900 {
+        for table in &mut tables {
+            for &(at, key) in &inputs {
+                if i == at {
+                    table.inject_key(key, ElementState::Pressed);
+                }
+                if i == at + 30 {
+                    table.inject_key(key, ElementState::Released);
+                }
+            }
+            table.step();
+        }
+        tables[1].render_ahead(&mut frame, &mut pal);
+    }
+    assert!(!tables[0].in_attract());
+    assert_eq!(tables[0].save_state(), tables[1].save_state());
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn game_api_plays_a_table() {
+    let data = data_source();
+    let mut game = Game::new(data, config(), Route::Table(TableId::Table3), 0).unwrap();
+    assert_eq!(game.table(), Some(TableId::Table3));
+    for _ in 0..60 {
+        game.tick().unwrap();
+    }
+    game.input(Input::Key(Key::Return), true);
+    game.tick().unwrap();
+    game.input(Input::Key(Key::Return), false);
+    for _ in 0..600 {
+        game.tick().unwrap();
+    }
+    let status = game.status().unwrap();
+    assert!(!status.in_attract);
+    assert_eq!(status.cur_ball, 1);
+    let (w, h) = game.resolution();
+    let mut rgba = vec![0; (w * h * 4) as usize];
+    game.render_rgba(&mut rgba);
+    assert!(rgba.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn attract_demo_ends_on_key_press() {
+    let data = data_source();
+    let mut demo = AttractDemo::new(&*data, config(), TableId::Table2).unwrap();
+    for _ in 0..600 {
+        assert_eq!(demo.run_frame(), Action::None);
+    }
+    assert!(!demo.display_text().is_empty());
+    demo.handle_key(VirtualKeyCode::Space, ElementState::Pressed);
+    assert_eq!(
+        demo.run_frame(),
+        Action::Navigate(Route::Intro(Some(TableId::Table2)))
+    );
+}
+
+#[test]
+fn spectator_snapshots_round_trip() {
+    let mut dm = [[false; 160]; 16];
+    dm[3][17] = true;
+    dm[15][159] = true;
+    let snapshot = Snapshot {
+        table: TableId::Table4,
+        status: GameStatus {
+            score: 123_456_789,
+            cur_player: 2,
+            total_players: 3,
+            cur_ball: 1,
+            total_balls: 3,
+            extra_balls: 1,
+            tilted: false,
+            in_mode: true,
+            in_attract: false,
+        },
+        ball_pos: (280, -5),
+        ball_layer: Layer::Overhead,
+        dm,
+        dm_lit: true,
+        events: vec![
+            GameEvent::BallDrained { player: 1, ball: 2 },
+            GameEvent::HighScoreAchieved {
+                place: 3,
+                score: 50_000_000,
+            },
+            GameEvent::JackpotScored(1_000_000),
+        ],
+    };
+    let mut wire = vec![];
+    write_header(&mut wire).unwrap();
+    write_snapshot(&mut wire, &snapshot).unwrap();
+    let mut wire = &wire[..];
+    read_header(&mut wire).unwrap();
+    assert_eq!(read_snapshot(&mut wire).unwrap(), snapshot);
+    assert!(wire.is_empty());
+
+    let mut wire = vec![];
+    write_header(&mut wire).unwrap();
+    wire[4] += 1;
+    assert!(read_header(&mut &wire[..]).is_err());
+}
+
+#[test]
+fn broken_modules_are_errors() {
+    // a module with one pattern, all of it silent
+    let mut module = vec![0; 20 + 31 * 30 + 134 + 0x400];
+    module[20 + 31 * 30] = 1;
+    assert!(loader::load(&mut Cursor::new(&module)).is_ok());
+
+    let mut no_song = module.clone();
+    no_song[20 + 31 * 30] = 0;
+    assert!(loader::load(&mut Cursor::new(&no_song)).is_err());
+
+    let mut bad_effect = module.clone();
+    bad_effect[20 + 31 * 30 + 134 + 2] = 0x08;
+    assert!(loader::load(&mut Cursor::new(&bad_effect)).is_err());
+
+    let mut bad_sample = module.clone();
+    bad_sample[20 + 31 * 30 + 134] = 0x20;
+    assert!(loader::load(&mut Cursor::new(&bad_sample)).is_err());
+
+    assert!(loader::load(&mut Cursor::new(&module[..100])).is_err());
+}
+
+#[test]
+fn partial_table_options_round_trip() {
+    let config: Config = toml::from_str("[table_options.Table1]\nresolution = \"High\"\n").unwrap();
+    assert_eq!(
+        config.options_for(TableId::Table1).resolution,
+        Resolution::High
+    );
+    assert_eq!(
+        config.options_for(TableId::Table2).resolution,
+        config.options.resolution
+    );
+    let saved: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(saved, config);
+}
+
+#[test]
+fn partial_key_bindings_round_trip() {
+    let config: Config = toml::from_str("[key_bindings]\nplunger = [\"Return\", \"Z\"]\n").unwrap();
+    assert_eq!(&config.key_bindings.plunger[..], &[Key::Return, Key::Z][..]);
+    assert_eq!(
+        config.key_bindings.flipper_left,
+        KeyBindings::default().flipper_left
+    );
+    let saved: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(saved, config);
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn extra_ball_ends_before_the_last_one() {
+    let data = data_source();
+    for table_id in [
+        TableId::Table1,
+        TableId::Table2,
+        TableId::Table3,
+        TableId::Table4,
+    ] {
+        let mut table = Table::new(&*data, config(), table_id, 0).unwrap();
+        for _ in 0..60 {
+            table.step();
+        }
+        press(&mut table, VirtualKeyCode::Return, 1);
+        assert!(step_until(&mut table, 60 * 60, |t| !t.in_attract() && t.at_spring()));
+        press(&mut table, VirtualKeyCode::Down, 60);
+        for _ in 0..120 {
+            table.step();
+        }
+        let ball = table.ball_debug();
+        table.add_ball(ball.layer, ball.pos, ball.speed);
+        assert_eq!(table.balls_in_play(), 2);
+        assert!(
+            step_until(&mut table, 60 * 600, |t| t.in_drain()),
+            "{table_id:?}: balls never drained"
+        );
+        assert_eq!(table.balls_in_play(), 1, "{table_id:?}");
+    }
+}
+
+#[test]
+#[ignore = "needs PFR_DATA"]
+fn physics_steps_add_up_to_a_game_frame() {
+    let data = data_source();
+    let plunged = || {
+        let mut table = Table::new(&*data, config(), TableId::Table1, 0).unwrap();
+        for _ in 0..60 {
+            table.step();
+        }
+        press(&mut table, VirtualKeyCode::Return, 1);
+        assert!(step_until(&mut table, 60 * 60, |t| !t.in_attract() && t.at_spring()));
+        press(&mut table, VirtualKeyCode::Down, 60);
+        for _ in 0..30 {
+            table.step();
+        }
+        table.set_frame_step(true);
+        table
+    };
+    let mut by_frame = plunged();
+    let mut by_physics = plunged();
+    let start = by_frame.ball_debug();
+    press(&mut by_frame, VirtualKeyCode::Period, 1);
+    for _ in 0..4 {
+        press(&mut by_physics, VirtualKeyCode::Comma, 1);
+    }
+    assert_ne!(by_frame.ball_debug(), start);
+    assert_eq!(by_physics.ball_debug(), by_frame.ball_debug());
+    assert_eq!(by_physics.status(), by_frame.status());
+}
+
+#[test]
+fn language_strings_fit_the_menus() {
+    let printable = |text: &str| {
+        text.bytes()
+            .all(|chr| chr.is_ascii_uppercase() || chr.is_ascii_digit() || b" :.-".contains(&chr))
+    };
+    for language in [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Swedish,
+    ] {
+        let strings = language.strings();
+        let (labels, actions) = strings.options.split_at(13);
+        // values start at column 16, after the labels at column 2
+        for label in labels {
+            assert!(label.len() <= 13, "{language:?}: {label}");
+        }
+        let confirm = strings.yes.len().max(strings.no.len());
+        assert!(2 + actions[0].len() + 2 + confirm <= 24, "{language:?}");
+        assert!(2 + actions[1].len() <= 24, "{language:?}");
+        for title in [strings.options_title, strings.select_table] {
+            assert!(title.len() <= 24, "{language:?}: {title}");
+        }
+        let values = [
+            strings.on,
+            strings.off,
+            strings.high,
+            strings.low,
+            strings.hard,
+            strings.medium,
+            strings.soft,
+            strings.normal,
+            strings.full,
+            strings.color,
+            strings.mono,
+            strings.fit,
+            strings.reduced,
+        ];
+        for value in values {
+            assert!(value.len() <= 8, "{language:?}: {value}");
+        }
+        assert!(strings.speed.len() <= 5, "{language:?}");
+        let left_text = [strings.left_text_menu, strings.left_text_options];
+        for line in left_text.iter().flatten().flatten() {
+            assert!(line.len() <= 12, "{language:?}: {line}");
+        }
+        let all = strings.options.iter().chain(&values).chain([
+            &strings.options_title,
+            &strings.select_table,
+            &strings.yes,
+            &strings.no,
+            &strings.speed,
+        ]);
+        for text in all.chain(left_text.iter().flatten().flatten()) {
+            assert!(printable(text), "{language:?}: {text}");
+        }
+    }
+}
+
+#[test]
+fn broken_images_are_errors() {
+    let mut form = b"PBM ".to_vec();
+    form.extend(b"BMHD\0\0\0\x14\0\x02\0\x01");
+    form.extend([0; 0x10]);
+    form.extend(b"CMAP\0\0\x03\0");
+    form.extend([0; 0x300]);
+    form.extend(b"BODY\0\0\0\x03\x01\x05\x07\0");
+    let mut image = b"FORM".to_vec();
+    image.extend((form.len() as u32).to_be_bytes());
+    image.extend(form);
+    let parsed = Image::try_parse(&image[..image.len() - 1]);
+    assert!(parsed.is_err());
+    let mut bad_run = image.clone();
+    let len = bad_run.len();
+    bad_run[len - 4] = 0x05;
+    assert!(Image::try_parse(&bad_run).is_err());
+    assert!(Image::try_parse(&image[..6]).is_err());
+    let parsed = Image::try_parse(&image).unwrap();
+    assert_eq!(parsed.data[(0, 0)], 5);
+    assert_eq!(parsed.data[(1, 0)], 7);
+}
+
+#[test]
+fn palette_expansion_matches_naive() {
+    let mut seed = 0x1234_5678u32;
+    let color: Vec<_> = (0..256)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let [red, green, blue, _] = seed.to_le_bytes();
+            (red, green, blue)
+        })
+        .collect();
+    let mut mono = color.clone();
+    ColorMode::Mono.apply(&mut mono);
+    let faded: Vec<_> = color
+        .iter()
+        .map(|&(red, green, blue)| (red / 3, green / 3, blue / 3))
+        .collect();
+    let src: Vec<u8> = (0..=255).cycle().take(256 * 3 + 5).collect();
+    for (name, pal) in [("color", &color), ("mono", &mono), ("faded", &faded)] {
+        let mut naive = vec![0; src.len() * 4];
+        let mut fast = vec![0; src.len() * 4];
+        expand_naive(pal, &src, &mut naive);
+        expand(&rgba_lut(pal), &src, &mut fast);
+        assert!(naive == fast, "{name} palette expands differently");
+    }
+}